@@ -0,0 +1,50 @@
+package aili
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"fmt"
+	"mime/multipart"
+	"net/http"
+)
+
+func (p *OpenAIProvider) NewSpeechRequest(ctx context.Context, text, model, voice string) (*http.Request, error) {
+	requestBody, err := json.Marshal(map[string]interface{}{"model": model, "voice": voice, "input": text, "response_format": "mp3"})
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	req, err := newJSONRequest(ctx, p.baseURL+"/audio/speech", requestBody)
+	if err != nil {
+		return nil, err
+	}
+	req.Header.Set("Authorization", "Bearer "+p.apiKey)
+	return req, nil
+}
+
+func (p *OpenAIProvider) NewTranscriptionRequest(ctx context.Context, audio []byte, filename, model string) (*http.Request, error) {
+	var body bytes.Buffer
+	writer := multipart.NewWriter(&body)
+	if err := writer.WriteField("model", model); err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	part, err := writer.CreateFormFile("file", filename)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	if _, err := part.Write(audio); err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	if err := writer.Close(); err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+
+	req, err := http.NewRequestWithContext(ctx, http.MethodPost, p.baseURL+"/audio/transcriptions", &body)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request: %w", err)
+	}
+	req.Header.Set("Content-Type", writer.FormDataContentType())
+	req.Header.Set("User-Agent", "AIChat/1.0")
+	req.Header.Set("Authorization", "Bearer "+p.apiKey)
+	return req, nil
+}
@@ -0,0 +1,313 @@
+package aili
+
+import (
+	"context"
+	"crypto/tls"
+	"errors"
+	"fmt"
+	"io"
+	"math/rand"
+	"net"
+	"net/http"
+	"regexp"
+	"strings"
+	"time"
+)
+
+const (
+	requestTimeout    = 30 * time.Second
+	requestsPerSecond = 10
+	backoffFactor     = 2
+	defaultMaxTokens  = 1024
+)
+
+type Config struct {
+	Provider           string
+	APIKey             string
+	BaseURL            string
+	Model              string
+	Temperature        float64
+	TopP               float64
+	MaxTokens          int
+	Candidates         int
+	CandidateSelection string
+	JSONMode           bool
+	Schema             map[string]interface{}
+	MaxAttempts        int
+	RetryBackoff       time.Duration
+	ConnectTimeout     time.Duration
+	StreamReconnect    bool
+}
+
+type Client struct {
+	Config     Config
+	HTTPClient *http.Client
+	Provider   Provider
+	Stream     bool
+	Tools      []ToolDefinition
+	Limiter    *time.Ticker
+
+	Prepare       func(history []Message, model string) []Message
+	ResolveModel  func(model string) string
+	NewProgress   func() func(deltas int)
+	OnResponse    func(header http.Header)
+	OnRetry       func(attempt, maxAttempts int, err error, wait time.Duration)
+	WaitRateLimit func(ctx context.Context, wait time.Duration) error
+	Failover      func(err error) bool
+}
+
+func NewClient(config Config) (*Client, error) {
+	switch config.Provider {
+	case "groq", "openai", "anthropic":
+		if config.APIKey == "" {
+			return nil, fmt.Errorf("the %s provider needs an API key", config.Provider)
+		}
+	case "ollama":
+	default:
+		return nil, fmt.Errorf("unknown provider %q, expected \"groq\", \"openai\", \"anthropic\" or \"ollama\"", config.Provider)
+	}
+	if config.TopP <= 0 {
+		config.TopP = 1
+	}
+	if config.MaxTokens <= 0 {
+		config.MaxTokens = defaultMaxTokens
+	}
+	if config.Candidates < 1 {
+		config.Candidates = 1
+	}
+	if config.MaxAttempts < 1 {
+		config.MaxAttempts = 1
+	}
+	if config.RetryBackoff <= 0 {
+		config.RetryBackoff = time.Second
+	}
+
+	return &Client{
+		Config:     config,
+		HTTPClient: NewHTTPClient(config.ConnectTimeout),
+		Provider:   NewProvider(config),
+		Stream:     true,
+		Limiter:    time.NewTicker(time.Second / requestsPerSecond),
+	}, nil
+}
+
+func NewHTTPClient(connectTimeout time.Duration) *http.Client {
+	dialer := &net.Dialer{Timeout: connectTimeout}
+
+	return &http.Client{
+		Timeout: requestTimeout,
+		Transport: &http.Transport{
+			DialContext:         dialer.DialContext,
+			TLSHandshakeTimeout: connectTimeout,
+			TLSClientConfig:     &tls.Config{MinVersion: tls.VersionTLS12},
+			MaxIdleConns:        100,
+			MaxConnsPerHost:     100,
+			IdleConnTimeout:     90 * time.Second,
+			DisableCompression:  true,
+			ForceAttemptHTTP2:   true,
+			MaxIdleConnsPerHost: 100,
+		},
+	}
+}
+
+func (c *Config) jsonMode() bool {
+	return c.JSONMode || c.Schema != nil
+}
+
+func (c *Config) responseFormat() map[string]interface{} {
+	if c.Schema != nil {
+		return map[string]interface{}{
+			"type":        "json_schema",
+			"json_schema": map[string]interface{}{"name": "response", "schema": c.Schema},
+		}
+	}
+	return map[string]interface{}{"type": "json_object"}
+}
+
+func Ask(ctx context.Context, client *Client, history []Message, model string) (Completion, error) {
+	if model == "" {
+		model = client.Provider.DefaultModel()
+	}
+	var (
+		completion  Completion
+		err         error
+		maxAttempts = max(client.Config.MaxAttempts, 1)
+		backoff     = client.Config.RetryBackoff
+	)
+	if backoff <= 0 {
+		backoff = time.Second
+	}
+
+	for attempt := 1; attempt <= maxAttempts; attempt++ {
+		if client.throttle(ctx) != nil {
+			return Completion{}, ErrStreamCancelled
+		}
+
+		completion, err = client.complete(ctx, history, client.resolveModel(model))
+		if err == nil {
+			return completion, nil
+		}
+		if errors.Is(err, ErrStreamCancelled) {
+			return completion, err
+		}
+		if client.Failover != nil && client.Failover(err) {
+			attempt--
+			continue
+		}
+		if errors.Is(err, ErrStreamInterrupted) && !client.Config.StreamReconnect {
+			return completion, err
+		}
+		if !errors.Is(err, ErrStreamInterrupted) && !IsTransient(err) {
+			return completion, err
+		}
+
+		if attempt < maxAttempts {
+			var apiErr *APIError
+			if errors.As(err, &apiErr) && apiErr.StatusCode == http.StatusTooManyRequests {
+				wait := apiErr.RetryAfter
+				if wait <= 0 {
+					wait = backoff
+				}
+				if client.waitRateLimit(ctx, wait) != nil {
+					return Completion{}, ErrStreamCancelled
+				}
+				continue
+			}
+
+			jitter := time.Duration(rand.Int63n(int64(backoff)))
+			sleepTime := backoff + jitter
+			if client.OnRetry != nil {
+				client.OnRetry(attempt+1, maxAttempts, err, sleepTime)
+			}
+			select {
+			case <-time.After(sleepTime):
+			case <-ctx.Done():
+				return Completion{}, ErrStreamCancelled
+			}
+			backoff *= time.Duration(backoffFactor)
+		}
+	}
+
+	return Completion{}, fmt.Errorf("failed after %d attempts, last error: %w", maxAttempts, err)
+}
+
+func (c *Client) throttle(ctx context.Context) error {
+	if c.Limiter == nil {
+		return ctx.Err()
+	}
+	select {
+	case <-c.Limiter.C:
+		return nil
+	case <-ctx.Done():
+		return ctx.Err()
+	}
+}
+
+func (c *Client) resolveModel(model string) string {
+	if c.ResolveModel != nil {
+		return c.ResolveModel(model)
+	}
+	return model
+}
+
+func (c *Client) waitRateLimit(ctx context.Context, wait time.Duration) error {
+	if c.WaitRateLimit != nil {
+		return c.WaitRateLimit(ctx, wait)
+	}
+	select {
+	case <-time.After(wait):
+		return nil
+	case <-ctx.Done():
+		return ctx.Err()
+	}
+}
+
+func (c *Client) complete(ctx context.Context, history []Message, model string) (Completion, error) {
+	requestCtx, cancel := context.WithTimeout(ctx, requestTimeout)
+	defer cancel()
+
+	response, err := c.send(requestCtx, history, model)
+	if err != nil {
+		if ctx.Err() != nil {
+			return Completion{}, ErrStreamCancelled
+		}
+		if c.Config.ConnectTimeout > 0 && isConnectTimeout(err) {
+			return Completion{}, fmt.Errorf("timed out connecting to the API after %ds: %w", int(c.Config.ConnectTimeout/time.Second), err)
+		}
+		return Completion{}, fmt.Errorf("failed to send request: %w", err)
+	}
+	defer response.Body.Close()
+	if c.OnResponse != nil {
+		c.OnResponse(response.Header)
+	}
+
+	if response.StatusCode != http.StatusOK {
+		body, _ := io.ReadAll(response.Body)
+		return Completion{}, NewAPIError(response, body)
+	}
+
+	if !c.Stream {
+		candidates, err := c.Provider.ParseResponse(response.Body)
+		return selectCandidate(candidates, c.Config.CandidateSelection), err
+	}
+
+	var progress func(deltas int)
+	if c.NewProgress != nil {
+		progress = c.NewProgress()
+	}
+
+	candidates, err := c.Provider.ParseStream(response.Body, progress)
+	completion := selectCandidate(candidates, c.Config.CandidateSelection)
+	if err != nil && ctx.Err() != nil {
+		return completion, ErrStreamCancelled
+	}
+	if err != nil {
+		return completion, fmt.Errorf("%w: %w", ErrStreamInterrupted, err)
+	}
+	return completion, nil
+}
+
+func (c *Client) send(ctx context.Context, history []Message, model string) (*http.Response, error) {
+	if c.Prepare != nil {
+		history = c.Prepare(history, model)
+	}
+	req, err := c.Provider.NewChatRequest(ctx, &c.Config, history, model, c.Stream, c.Tools)
+	if err != nil {
+		return nil, err
+	}
+	return c.HTTPClient.Do(req)
+}
+
+func selectCandidate(candidates []Completion, criterion string) Completion {
+	if len(candidates) == 0 {
+		return Completion{}
+	}
+
+	selected := candidates[0]
+	switch {
+	case criterion == "longest":
+		for _, candidate := range candidates[1:] {
+			if len(candidate.Content) > len(selected.Content) {
+				selected = candidate
+			}
+		}
+	case criterion == "shortest":
+		for _, candidate := range candidates[1:] {
+			if candidate.Content != "" && (selected.Content == "" || len(candidate.Content) < len(selected.Content)) {
+				selected = candidate
+			}
+		}
+	case strings.HasPrefix(criterion, "regex:"):
+		pattern, err := regexp.Compile(strings.TrimPrefix(criterion, "regex:"))
+		if err != nil {
+			return selected
+		}
+		for _, candidate := range candidates {
+			if pattern.MatchString(candidate.Content) {
+				return candidate
+			}
+		}
+	}
+
+	return selected
+}
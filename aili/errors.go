@@ -0,0 +1,127 @@
+package aili
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net"
+	"net/http"
+	"strconv"
+	"strings"
+	"syscall"
+	"time"
+)
+
+var ErrStreamCancelled = errors.New("response stream cancelled")
+
+var ErrStreamInterrupted = errors.New("response stream interrupted after it began")
+
+type APIError struct {
+	StatusCode int
+	Body       string
+	Message    string
+	Type       string
+	Code       string
+	RetryAfter time.Duration
+}
+
+type apiErrorDetail struct {
+	Message string          `json:"message"`
+	Type    string          `json:"type"`
+	Code    json.RawMessage `json:"code"`
+}
+
+func NewAPIError(response *http.Response, body []byte) *APIError {
+	apiErr := &APIError{StatusCode: response.StatusCode, Body: strings.TrimSpace(string(body)), RetryAfter: retryAfter(response.Header)}
+
+	var payload struct {
+		Error json.RawMessage `json:"error"`
+	}
+	if err := json.Unmarshal(body, &payload); err != nil || len(payload.Error) == 0 {
+		return apiErr
+	}
+
+	var detail apiErrorDetail
+	if err := json.Unmarshal(payload.Error, &detail); err == nil {
+		apiErr.Message, apiErr.Type = detail.Message, detail.Type
+		if err := json.Unmarshal(detail.Code, &apiErr.Code); err != nil {
+			apiErr.Code = strings.Trim(string(detail.Code), `"`)
+		}
+		return apiErr
+	}
+	json.Unmarshal(payload.Error, &apiErr.Message)
+	return apiErr
+}
+
+func (e *APIError) Error() string {
+	if e.Message == "" {
+		return fmt.Sprintf("API request failed with status %d: %s", e.StatusCode, e.Body)
+	}
+	kind := e.Code
+	if kind == "" {
+		kind = e.Type
+	}
+	if kind == "" {
+		return fmt.Sprintf("API request failed with status %d: %s", e.StatusCode, e.Message)
+	}
+	return fmt.Sprintf("API request failed with status %d (%s): %s", e.StatusCode, kind, e.Message)
+}
+
+func (e *APIError) IsModelUnavailable() bool {
+	if e.StatusCode == http.StatusNotFound || e.Code == "model_not_found" || e.Code == "model_decommissioned" {
+		return true
+	}
+	text := strings.ToLower(e.Message + " " + e.Body)
+	return strings.Contains(text, "model_not_found") || strings.Contains(text, "decommissioned")
+}
+
+func (e *APIError) IsContextTooLong() bool {
+	if e.Code == "context_length_exceeded" {
+		return true
+	}
+	text := strings.ToLower(e.Message)
+	return strings.Contains(text, "context length") || strings.Contains(text, "context window") || strings.Contains(text, "too long")
+}
+
+func IsTransient(err error) bool {
+	var apiErr *APIError
+	if errors.As(err, &apiErr) {
+		return apiErr.StatusCode >= 500 || apiErr.StatusCode == http.StatusRequestTimeout || apiErr.StatusCode == http.StatusTooManyRequests
+	}
+
+	var netErr net.Error
+	if errors.As(err, &netErr) && netErr.Timeout() {
+		return true
+	}
+	var dnsErr *net.DNSError
+	if errors.As(err, &dnsErr) && dnsErr.IsTemporary {
+		return true
+	}
+	return errors.Is(err, syscall.ECONNRESET) || errors.Is(err, syscall.ECONNREFUSED) || errors.Is(err, io.ErrUnexpectedEOF)
+}
+
+func isConnectTimeout(err error) bool {
+	var opErr *net.OpError
+	if errors.As(err, &opErr) && opErr.Op == "dial" && opErr.Timeout() {
+		return true
+	}
+	return strings.Contains(err.Error(), "TLS handshake timeout")
+}
+
+func retryAfter(header http.Header) time.Duration {
+	if value := header.Get("Retry-After"); value != "" {
+		if seconds, err := strconv.Atoi(value); err == nil {
+			return time.Duration(seconds) * time.Second
+		}
+		if at, err := http.ParseTime(value); err == nil {
+			return time.Until(at)
+		}
+	}
+	for _, name := range []string{"x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"} {
+		if reset, err := time.ParseDuration(header.Get(name)); err == nil && reset > 0 {
+			return reset
+		}
+	}
+	return 0
+}
@@ -0,0 +1,117 @@
+package aili
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"strings"
+	"time"
+)
+
+type Message struct {
+	Role       string     `json:"role"`
+	Content    string     `json:"content"`
+	Images     []string   `json:"images,omitempty"`
+	ToolCalls  []ToolCall `json:"tool_calls,omitempty"`
+	ToolCallID string     `json:"tool_call_id,omitempty"`
+	Timestamp  time.Time  `json:"timestamp"`
+	Model      string     `json:"model,omitempty"`
+	Cancelled  bool       `json:"cancelled,omitempty"`
+	Tokens     int        `json:"tokens,omitempty"`
+}
+
+type contentPart struct {
+	Type string `json:"type"`
+	Text string `json:"text"`
+}
+
+type imageURL struct {
+	URL string `json:"url"`
+}
+
+type imagePart struct {
+	Type     string    `json:"type"`
+	Text     string    `json:"text,omitempty"`
+	ImageURL *imageURL `json:"image_url,omitempty"`
+}
+
+type Completion struct {
+	Content      string
+	FinishReason string
+	ToolCalls    []ToolCall
+}
+
+type ToolCall struct {
+	ID       string       `json:"id"`
+	Type     string       `json:"type"`
+	Function ToolFunction `json:"function"`
+}
+
+type ToolFunction struct {
+	Name      string `json:"name"`
+	Arguments string `json:"arguments"`
+}
+
+type ToolDefinition struct {
+	Name        string
+	Description string
+	Parameters  map[string]interface{}
+}
+
+func (m *Message) UnmarshalJSON(data []byte) error {
+	type message Message
+	var raw struct {
+		message
+		Content json.RawMessage `json:"content"`
+	}
+	if err := json.Unmarshal(data, &raw); err != nil {
+		return err
+	}
+	*m = Message(raw.message)
+
+	content := bytes.TrimSpace(raw.Content)
+	switch {
+	case len(content) == 0 || bytes.Equal(content, []byte("null")):
+		return nil
+	case content[0] == '[':
+		var parts []contentPart
+		if err := json.Unmarshal(content, &parts); err != nil {
+			return fmt.Errorf("invalid message content: %w", err)
+		}
+		var texts []string
+		for _, part := range parts {
+			if part.Type == "text" {
+				texts = append(texts, part.Text)
+			}
+		}
+		m.Content = strings.Join(texts, "\n")
+		return nil
+	}
+	return json.Unmarshal(content, &m.Content)
+}
+
+func messageContent(msg Message) interface{} {
+	if len(msg.Images) == 0 {
+		return msg.Content
+	}
+	parts := []imagePart{{Type: "text", Text: msg.Content}}
+	for _, url := range msg.Images {
+		parts = append(parts, imagePart{Type: "image_url", ImageURL: &imageURL{URL: url}})
+	}
+	return parts
+}
+
+func toolSchemas(tools []ToolDefinition) []map[string]interface{} {
+	definitions := make([]map[string]interface{}, 0, len(tools))
+	for _, tool := range tools {
+		definitions = append(definitions, map[string]interface{}{
+			"type": "function",
+			"function": map[string]interface{}{
+				"name":        tool.Name,
+				"description": tool.Description,
+				"parameters":  tool.Parameters,
+			},
+		})
+	}
+	return definitions
+}
@@ -0,0 +1,728 @@
+package aili
+
+import (
+	"bufio"
+	"bytes"
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"sort"
+	"strings"
+	"time"
+)
+
+const (
+	groqBaseURL        = "https://api.groq.com/openai/v1"
+	openAIBaseURL      = "https://api.openai.com/v1"
+	anthropicBaseURL   = "https://api.anthropic.com/v1"
+	ollamaBaseURL      = "http://localhost:11434"
+	anthropicVersion   = "2023-06-01"
+	groqDefaultModel   = "llama-3.1-70b-versatile"
+	openAIDefaultModel = "gpt-4o-mini"
+	claudeDefaultModel = "claude-3-5-sonnet-latest"
+	ollamaDefaultModel = "llama3.1"
+	maxStreamLineBytes = 4 * 1024 * 1024
+	MaxCandidates      = 16
+	MaxToolCalls       = 16
+)
+
+type Provider interface {
+	Name() string
+	DefaultModel() string
+	NewChatRequest(ctx context.Context, config *Config, history []Message, model string, stream bool, tools []ToolDefinition) (*http.Request, error)
+	NewModelsRequest(ctx context.Context) (*http.Request, error)
+	ParseStream(body io.Reader, progress func(deltas int)) ([]Completion, error)
+	ParseResponse(body io.Reader) ([]Completion, error)
+	ParseModels(body io.Reader) ([]ModelInfo, error)
+	NewEmbeddingsRequest(ctx context.Context, inputs []string, model string) (*http.Request, error)
+	ParseEmbeddings(body io.Reader) ([][]float64, error)
+}
+
+type ModelInfo struct {
+	ID            string `json:"id"`
+	ContextWindow int    `json:"context_window,omitempty"`
+}
+
+type OpenAIProvider struct {
+	name         string
+	baseURL      string
+	apiKey       string
+	defaultModel string
+}
+
+type anthropicProvider struct {
+	baseURL      string
+	apiKey       string
+	defaultModel string
+}
+
+type ollamaProvider struct {
+	baseURL string
+	model   string
+}
+
+type openAIMessage struct {
+	Role       string      `json:"role"`
+	Content    interface{} `json:"content"`
+	ToolCalls  []ToolCall  `json:"tool_calls,omitempty"`
+	ToolCallID string      `json:"tool_call_id,omitempty"`
+}
+
+type ollamaMessage struct {
+	Role    string `json:"role"`
+	Content string `json:"content"`
+}
+
+type ollamaChunk struct {
+	Message    ollamaMessage `json:"message"`
+	Done       bool          `json:"done"`
+	DoneReason string        `json:"done_reason"`
+	Error      string        `json:"error"`
+}
+
+type anthropicMessage struct {
+	Role    string `json:"role"`
+	Content string `json:"content"`
+}
+
+type anthropicEvent struct {
+	Type  string         `json:"type"`
+	Delta anthropicDelta `json:"delta"`
+	Error anthropicError `json:"error"`
+}
+
+type anthropicDelta struct {
+	Text       string `json:"text"`
+	StopReason string `json:"stop_reason"`
+}
+
+type anthropicError struct {
+	Message string `json:"message"`
+}
+
+type anthropicResponse struct {
+	Content    []anthropicBlock `json:"content"`
+	StopReason string           `json:"stop_reason"`
+}
+
+type anthropicBlock struct {
+	Type string `json:"type"`
+	Text string `json:"text"`
+}
+
+type choiceDelta struct {
+	Index        int
+	Content      string
+	FinishReason string
+	ToolCalls    []toolCallDelta
+}
+
+type toolCallDelta struct {
+	Index int
+	ToolCall
+}
+
+func NewProvider(config Config) Provider {
+	switch config.Provider {
+	case "openai":
+		return &OpenAIProvider{name: "openai", baseURL: orDefault(config.BaseURL, openAIBaseURL), apiKey: config.APIKey, defaultModel: orDefault(config.Model, openAIDefaultModel)}
+	case "anthropic":
+		return &anthropicProvider{baseURL: orDefault(config.BaseURL, anthropicBaseURL), apiKey: config.APIKey, defaultModel: orDefault(config.Model, claudeDefaultModel)}
+	case "ollama":
+		return &ollamaProvider{baseURL: strings.TrimRight(orDefault(config.BaseURL, ollamaBaseURL), "/"), model: orDefault(config.Model, ollamaDefaultModel)}
+	default:
+		return &OpenAIProvider{name: "groq", baseURL: orDefault(config.BaseURL, groqBaseURL), apiKey: config.APIKey, defaultModel: orDefault(config.Model, groqDefaultModel)}
+	}
+}
+
+func orDefault(value, fallback string) string {
+	if value == "" {
+		return fallback
+	}
+	return value
+}
+
+func currentTimeNote() string {
+	return fmt.Sprintf("Current date and time: %s", time.Now().Format(time.RFC3339))
+}
+
+func newJSONRequest(ctx context.Context, url string, body []byte) (*http.Request, error) {
+	req, err := http.NewRequestWithContext(ctx, http.MethodPost, url, bytes.NewReader(body))
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request: %w", err)
+	}
+	req.Header.Set("Content-Type", "application/json")
+	req.Header.Set("User-Agent", "AIChat/1.0")
+	return req, nil
+}
+
+func newGetRequest(ctx context.Context, url string) (*http.Request, error) {
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, url, nil)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request: %w", err)
+	}
+	req.Header.Set("User-Agent", "AIChat/1.0")
+	return req, nil
+}
+
+func (p *OpenAIProvider) Name() string {
+	return p.name
+}
+
+func (p *OpenAIProvider) DefaultModel() string {
+	return p.defaultModel
+}
+
+func (p *OpenAIProvider) NewChatRequest(ctx context.Context, config *Config, history []Message, model string, stream bool, tools []ToolDefinition) (*http.Request, error) {
+	requestBody, err := createRequestBody(config, history, model, stream, tools)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+
+	req, err := newJSONRequest(ctx, p.baseURL+"/chat/completions", requestBody)
+	if err != nil {
+		return nil, err
+	}
+	req.Header.Set("Authorization", "Bearer "+p.apiKey)
+	return req, nil
+}
+
+func (p *OpenAIProvider) NewModelsRequest(ctx context.Context) (*http.Request, error) {
+	req, err := newGetRequest(ctx, p.baseURL+"/models")
+	if err != nil {
+		return nil, err
+	}
+	req.Header.Set("Authorization", "Bearer "+p.apiKey)
+	return req, nil
+}
+
+func (p *OpenAIProvider) ParseStream(body io.Reader, progress func(deltas int)) ([]Completion, error) {
+	return processStreamResponse(body, progress)
+}
+
+func (p *OpenAIProvider) ParseResponse(body io.Reader) ([]Completion, error) {
+	return processFullResponse(body)
+}
+
+func (p *OpenAIProvider) ParseModels(body io.Reader) ([]ModelInfo, error) {
+	return parseModelList(body)
+}
+
+func (p *OpenAIProvider) NewEmbeddingsRequest(ctx context.Context, inputs []string, model string) (*http.Request, error) {
+	requestBody, err := json.Marshal(map[string]interface{}{"model": model, "input": inputs})
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	req, err := newJSONRequest(ctx, p.baseURL+"/embeddings", requestBody)
+	if err != nil {
+		return nil, err
+	}
+	req.Header.Set("Authorization", "Bearer "+p.apiKey)
+	return req, nil
+}
+
+func (p *OpenAIProvider) ParseEmbeddings(body io.Reader) ([][]float64, error) {
+	var response struct {
+		Data []struct {
+			Index     int       `json:"index"`
+			Embedding []float64 `json:"embedding"`
+		} `json:"data"`
+	}
+	if err := json.NewDecoder(body).Decode(&response); err != nil {
+		return nil, fmt.Errorf("failed to decode embeddings: %w", err)
+	}
+	vectors := make([][]float64, len(response.Data))
+	for _, item := range response.Data {
+		if item.Index < 0 || item.Index >= len(vectors) {
+			return nil, fmt.Errorf("embedding index %d out of range", item.Index)
+		}
+		vectors[item.Index] = item.Embedding
+	}
+	return vectors, nil
+}
+
+func parseModelList(body io.Reader) ([]ModelInfo, error) {
+	var response struct {
+		Data []ModelInfo `json:"data"`
+	}
+	if err := json.NewDecoder(body).Decode(&response); err != nil {
+		return nil, fmt.Errorf("failed to decode model list: %w", err)
+	}
+	return response.Data, nil
+}
+
+func createRequestBody(config *Config, truncatedHistory []Message, model string, stream bool, tools []ToolDefinition) ([]byte, error) {
+	apiMessages := []openAIMessage{
+		{Role: "system", Content: currentTimeNote()},
+	}
+
+	for _, msg := range truncatedHistory {
+		apiMessages = append(apiMessages, openAIMessage{
+			Role:       msg.Role,
+			Content:    messageContent(msg),
+			ToolCalls:  msg.ToolCalls,
+			ToolCallID: msg.ToolCallID,
+		})
+	}
+
+	body := map[string]interface{}{
+		"messages":    apiMessages,
+		"model":       model,
+		"temperature": config.Temperature,
+		"max_tokens":  config.MaxTokens,
+		"top_p":       config.TopP,
+		"stream":      stream,
+		"stop":        []string{"\n\nHuman:", "\n\nAssistant:"},
+	}
+	if config.Candidates > 1 {
+		body["n"] = config.Candidates
+	}
+	if len(tools) > 0 {
+		body["tools"] = toolSchemas(tools)
+	}
+	if config.jsonMode() {
+		body["response_format"] = config.responseFormat()
+	}
+
+	return json.Marshal(body)
+}
+
+func processStreamResponse(body io.Reader, progress func(deltas int)) ([]Completion, error) {
+	scanner := bufio.NewScanner(body)
+	scanner.Buffer(make([]byte, 0, 64*1024), maxStreamLineBytes)
+	var buffers []*strings.Builder
+	var finishReasons []string
+	var toolCalls [][]ToolCall
+	var lastError error
+	deltas := 0
+
+	for scanner.Scan() {
+		line := scanner.Text()
+		if !strings.HasPrefix(line, "data: ") {
+			continue
+		}
+
+		data := strings.TrimPrefix(line, "data: ")
+		if data == "[DONE]" {
+			break
+		}
+
+		var jsonResponse map[string]interface{}
+		if err := json.Unmarshal([]byte(data), &jsonResponse); err != nil {
+			lastError = err
+			continue
+		}
+
+		hasContent := false
+		for _, choice := range extractContent(jsonResponse) {
+			if choice.Index < 0 || choice.Index >= MaxCandidates {
+				continue
+			}
+			for len(buffers) <= choice.Index {
+				buffers = append(buffers, &strings.Builder{})
+				finishReasons = append(finishReasons, "")
+				toolCalls = append(toolCalls, nil)
+			}
+			if choice.Content != "" {
+				buffers[choice.Index].WriteString(choice.Content)
+				hasContent = true
+			}
+			toolCalls[choice.Index] = mergeToolCallDeltas(toolCalls[choice.Index], choice.ToolCalls)
+			if choice.FinishReason != "" {
+				finishReasons[choice.Index] = choice.FinishReason
+			}
+		}
+
+		if progress != nil && hasContent {
+			deltas++
+			progress(deltas)
+		}
+	}
+
+	if err := scanner.Err(); err != nil {
+		if errors.Is(err, bufio.ErrTooLong) {
+			return collectCandidates(buffers, finishReasons, toolCalls), fmt.Errorf("stream line exceeded %d bytes, aborting malformed response: %w", maxStreamLineBytes, err)
+		}
+		return collectCandidates(buffers, finishReasons, toolCalls), fmt.Errorf("failed to read stream: %w", err)
+	}
+
+	if lastError != nil {
+		return nil, fmt.Errorf("error processing stream: %w", lastError)
+	}
+
+	return collectCandidates(buffers, finishReasons, toolCalls), nil
+}
+
+func processFullResponse(body io.Reader) ([]Completion, error) {
+	var jsonResponse map[string]interface{}
+	if err := json.NewDecoder(body).Decode(&jsonResponse); err != nil {
+		return nil, fmt.Errorf("failed to decode response: %w", err)
+	}
+
+	choices := extractContent(jsonResponse)
+	sort.SliceStable(choices, func(i, j int) bool { return choices[i].Index < choices[j].Index })
+	candidates := make([]Completion, 0, len(choices))
+	for _, choice := range choices {
+		candidates = append(candidates, Completion{
+			Content:      strings.TrimSpace(choice.Content),
+			FinishReason: choice.FinishReason,
+			ToolCalls:    mergeToolCallDeltas(nil, choice.ToolCalls),
+		})
+	}
+	return candidates, nil
+}
+
+func collectCandidates(buffers []*strings.Builder, finishReasons []string, toolCalls [][]ToolCall) []Completion {
+	candidates := make([]Completion, len(buffers))
+	for i, buffer := range buffers {
+		candidates[i] = Completion{Content: strings.TrimSpace(buffer.String()), FinishReason: finishReasons[i], ToolCalls: toolCalls[i]}
+	}
+	return candidates
+}
+
+func mergeToolCallDeltas(calls []ToolCall, deltas []toolCallDelta) []ToolCall {
+	for _, delta := range deltas {
+		if delta.Index < 0 || delta.Index >= MaxToolCalls {
+			continue
+		}
+		for len(calls) <= delta.Index {
+			calls = append(calls, ToolCall{Type: "function"})
+		}
+		call := &calls[delta.Index]
+		if delta.ID != "" {
+			call.ID = delta.ID
+		}
+		if delta.Function.Name != "" {
+			call.Function.Name = delta.Function.Name
+		}
+		call.Function.Arguments += delta.Function.Arguments
+	}
+	return calls
+}
+
+func parseToolCallDeltas(raw interface{}) []toolCallDelta {
+	items, ok := raw.([]interface{})
+	if !ok {
+		return nil
+	}
+	deltas := make([]toolCallDelta, 0, len(items))
+	for position, item := range items {
+		call, ok := item.(map[string]interface{})
+		if !ok {
+			continue
+		}
+		delta := toolCallDelta{Index: position}
+		if rawIndex, ok := call["index"].(float64); ok {
+			delta.Index = int(rawIndex)
+		}
+		delta.ID, _ = call["id"].(string)
+		if function, ok := call["function"].(map[string]interface{}); ok {
+			delta.Function.Name, _ = function["name"].(string)
+			delta.Function.Arguments, _ = function["arguments"].(string)
+		}
+		deltas = append(deltas, delta)
+	}
+	return deltas
+}
+
+func extractContent(jsonResponse map[string]interface{}) []choiceDelta {
+	choices, ok := jsonResponse["choices"].([]interface{})
+	if !ok {
+		return nil
+	}
+
+	deltas := make([]choiceDelta, 0, len(choices))
+	for position, rawChoice := range choices {
+		choice, ok := rawChoice.(map[string]interface{})
+		if !ok {
+			continue
+		}
+
+		parsed := choiceDelta{Index: position}
+		if rawIndex, ok := choice["index"].(float64); ok {
+			parsed.Index = int(rawIndex)
+		}
+		if finishReason, ok := choice["finish_reason"].(string); ok {
+			parsed.FinishReason = finishReason
+		}
+		if delta, ok := choice["delta"].(map[string]interface{}); ok {
+			parsed.Content, _ = delta["content"].(string)
+			parsed.ToolCalls = parseToolCallDeltas(delta["tool_calls"])
+		} else if message, ok := choice["message"].(map[string]interface{}); ok {
+			parsed.Content, _ = message["content"].(string)
+			parsed.ToolCalls = parseToolCallDeltas(message["tool_calls"])
+		}
+		deltas = append(deltas, parsed)
+	}
+
+	return deltas
+}
+
+func (p *anthropicProvider) Name() string {
+	return "anthropic"
+}
+
+func (p *anthropicProvider) DefaultModel() string {
+	return p.defaultModel
+}
+
+func (p *anthropicProvider) NewChatRequest(ctx context.Context, config *Config, history []Message, model string, stream bool, _ []ToolDefinition) (*http.Request, error) {
+	system := []string{currentTimeNote()}
+	var messages []anthropicMessage
+	for _, msg := range history {
+		if msg.Role == "system" {
+			system = append(system, msg.Content)
+			continue
+		}
+		if msg.Role == "tool" || msg.Content == "" {
+			continue
+		}
+		messages = append(messages, anthropicMessage{Role: msg.Role, Content: msg.Content})
+	}
+
+	requestBody, err := json.Marshal(map[string]interface{}{
+		"model":       model,
+		"system":      strings.Join(system, "\n\n"),
+		"messages":    messages,
+		"max_tokens":  config.MaxTokens,
+		"temperature": config.Temperature,
+		"top_p":       config.TopP,
+		"stream":      stream,
+	})
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+
+	req, err := newJSONRequest(ctx, p.baseURL+"/messages", requestBody)
+	if err != nil {
+		return nil, err
+	}
+	p.authorize(req)
+	return req, nil
+}
+
+func (p *anthropicProvider) NewModelsRequest(ctx context.Context) (*http.Request, error) {
+	req, err := newGetRequest(ctx, p.baseURL+"/models")
+	if err != nil {
+		return nil, err
+	}
+	p.authorize(req)
+	return req, nil
+}
+
+func (p *anthropicProvider) authorize(req *http.Request) {
+	req.Header.Set("x-api-key", p.apiKey)
+	req.Header.Set("anthropic-version", anthropicVersion)
+}
+
+func (p *anthropicProvider) ParseStream(body io.Reader, progress func(deltas int)) ([]Completion, error) {
+	scanner := bufio.NewScanner(body)
+	scanner.Buffer(make([]byte, 0, 64*1024), maxStreamLineBytes)
+	var content strings.Builder
+	var completion Completion
+	deltas := 0
+
+	for scanner.Scan() {
+		data, ok := strings.CutPrefix(scanner.Text(), "data: ")
+		if !ok {
+			continue
+		}
+
+		var event anthropicEvent
+		if err := json.Unmarshal([]byte(data), &event); err != nil {
+			return nil, fmt.Errorf("error processing stream: %w", err)
+		}
+
+		switch event.Type {
+		case "content_block_delta":
+			if event.Delta.Text != "" {
+				content.WriteString(event.Delta.Text)
+				deltas++
+				if progress != nil {
+					progress(deltas)
+				}
+			}
+		case "message_delta":
+			completion.FinishReason = event.Delta.StopReason
+		case "error":
+			completion.Content = strings.TrimSpace(content.String())
+			return []Completion{completion}, fmt.Errorf("stream error: %s", event.Error.Message)
+		}
+		if event.Type == "message_stop" {
+			break
+		}
+	}
+
+	completion.Content = strings.TrimSpace(content.String())
+	if err := scanner.Err(); err != nil {
+		if errors.Is(err, bufio.ErrTooLong) {
+			return []Completion{completion}, fmt.Errorf("stream line exceeded %d bytes, aborting malformed response: %w", maxStreamLineBytes, err)
+		}
+		return []Completion{completion}, fmt.Errorf("failed to read stream: %w", err)
+	}
+	return []Completion{completion}, nil
+}
+
+func (p *anthropicProvider) ParseModels(body io.Reader) ([]ModelInfo, error) {
+	return parseModelList(body)
+}
+
+func (p *anthropicProvider) NewEmbeddingsRequest(context.Context, []string, string) (*http.Request, error) {
+	return nil, errors.New("anthropic does not provide an embeddings endpoint, index with another provider")
+}
+
+func (p *anthropicProvider) ParseEmbeddings(io.Reader) ([][]float64, error) {
+	return nil, errors.New("anthropic does not provide an embeddings endpoint")
+}
+
+func (p *anthropicProvider) ParseResponse(body io.Reader) ([]Completion, error) {
+	var response anthropicResponse
+	if err := json.NewDecoder(body).Decode(&response); err != nil {
+		return nil, fmt.Errorf("failed to decode response: %w", err)
+	}
+
+	var content strings.Builder
+	for _, block := range response.Content {
+		if block.Type == "text" {
+			content.WriteString(block.Text)
+		}
+	}
+	return []Completion{{Content: strings.TrimSpace(content.String()), FinishReason: response.StopReason}}, nil
+}
+
+func (p *ollamaProvider) Name() string {
+	return "ollama"
+}
+
+func (p *ollamaProvider) DefaultModel() string {
+	return p.model
+}
+
+func (p *ollamaProvider) NewChatRequest(ctx context.Context, config *Config, history []Message, model string, stream bool, _ []ToolDefinition) (*http.Request, error) {
+	messages := []ollamaMessage{{Role: "system", Content: currentTimeNote()}}
+	for _, msg := range history {
+		if msg.Role == "tool" || msg.Content == "" {
+			continue
+		}
+		messages = append(messages, ollamaMessage{Role: msg.Role, Content: msg.Content})
+	}
+
+	body := map[string]interface{}{
+		"model":    model,
+		"messages": messages,
+		"stream":   stream,
+		"options": map[string]interface{}{
+			"temperature": config.Temperature,
+			"top_p":       config.TopP,
+			"num_predict": config.MaxTokens,
+		},
+	}
+	if config.Schema != nil {
+		body["format"] = config.Schema
+	} else if config.jsonMode() {
+		body["format"] = "json"
+	}
+	requestBody, err := json.Marshal(body)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	return newJSONRequest(ctx, p.baseURL+"/api/chat", requestBody)
+}
+
+func (p *ollamaProvider) NewModelsRequest(ctx context.Context) (*http.Request, error) {
+	return newGetRequest(ctx, p.baseURL+"/api/tags")
+}
+
+func (p *ollamaProvider) ParseStream(body io.Reader, progress func(deltas int)) ([]Completion, error) {
+	scanner := bufio.NewScanner(body)
+	scanner.Buffer(make([]byte, 0, 64*1024), maxStreamLineBytes)
+	var content strings.Builder
+	var completion Completion
+	deltas := 0
+
+	for scanner.Scan() {
+		line := strings.TrimSpace(scanner.Text())
+		if line == "" {
+			continue
+		}
+
+		var chunk ollamaChunk
+		if err := json.Unmarshal([]byte(line), &chunk); err != nil {
+			return nil, fmt.Errorf("error processing stream: %w", err)
+		}
+		if chunk.Error != "" {
+			completion.Content = strings.TrimSpace(content.String())
+			return []Completion{completion}, fmt.Errorf("stream error: %s", chunk.Error)
+		}
+
+		if chunk.Message.Content != "" {
+			content.WriteString(chunk.Message.Content)
+			deltas++
+			if progress != nil {
+				progress(deltas)
+			}
+		}
+		if chunk.Done {
+			completion.FinishReason = chunk.DoneReason
+			break
+		}
+	}
+
+	completion.Content = strings.TrimSpace(content.String())
+	if err := scanner.Err(); err != nil {
+		if errors.Is(err, bufio.ErrTooLong) {
+			return []Completion{completion}, fmt.Errorf("stream line exceeded %d bytes, aborting malformed response: %w", maxStreamLineBytes, err)
+		}
+		return []Completion{completion}, fmt.Errorf("failed to read stream: %w", err)
+	}
+	return []Completion{completion}, nil
+}
+
+func (p *ollamaProvider) ParseResponse(body io.Reader) ([]Completion, error) {
+	var chunk ollamaChunk
+	if err := json.NewDecoder(body).Decode(&chunk); err != nil {
+		return nil, fmt.Errorf("failed to decode response: %w", err)
+	}
+	if chunk.Error != "" {
+		return nil, fmt.Errorf("ollama error: %s", chunk.Error)
+	}
+	return []Completion{{Content: strings.TrimSpace(chunk.Message.Content), FinishReason: chunk.DoneReason}}, nil
+}
+
+func (p *ollamaProvider) ParseModels(body io.Reader) ([]ModelInfo, error) {
+	var response struct {
+		Models []struct {
+			Name string `json:"name"`
+		} `json:"models"`
+	}
+	if err := json.NewDecoder(body).Decode(&response); err != nil {
+		return nil, fmt.Errorf("failed to decode model list: %w", err)
+	}
+
+	models := make([]ModelInfo, 0, len(response.Models))
+	for _, model := range response.Models {
+		models = append(models, ModelInfo{ID: model.Name})
+	}
+	return models, nil
+}
+
+func (p *ollamaProvider) NewEmbeddingsRequest(ctx context.Context, inputs []string, model string) (*http.Request, error) {
+	requestBody, err := json.Marshal(map[string]interface{}{"model": model, "input": inputs})
+	if err != nil {
+		return nil, fmt.Errorf("failed to create request body: %w", err)
+	}
+	return newJSONRequest(ctx, p.baseURL+"/api/embed", requestBody)
+}
+
+func (p *ollamaProvider) ParseEmbeddings(body io.Reader) ([][]float64, error) {
+	var response struct {
+		Embeddings [][]float64 `json:"embeddings"`
+	}
+	if err := json.NewDecoder(body).Decode(&response); err != nil {
+		return nil, fmt.Errorf("failed to decode embeddings: %w", err)
+	}
+	return response.Embeddings, nil
+}
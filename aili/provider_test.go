@@ -0,0 +1,22 @@
+package aili
+
+import (
+	"bufio"
+	"errors"
+	"strings"
+	"testing"
+)
+
+func TestProcessStreamResponseRejectsOversizedLine(t *testing.T) {
+	stream := `data: {"choices":[{"index":0,"delta":{"content":"partial"}}]}` + "\n" +
+		"data: " + strings.Repeat("x", maxStreamLineBytes+1) + "\n" +
+		"data: [DONE]\n"
+
+	candidates, err := processStreamResponse(strings.NewReader(stream), nil)
+	if !errors.Is(err, bufio.ErrTooLong) {
+		t.Fatalf("expected an error wrapping bufio.ErrTooLong, got %v", err)
+	}
+	if len(candidates) != 1 || candidates[0].Content != "partial" {
+		t.Fatalf("expected the content read before the long line to be kept, got %+v", candidates)
+	}
+}
@@ -0,0 +1,204 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+var pathAnnotations = []string{"path=", "file=", "filename="}
+
+type CodeBlock struct {
+	Language string
+	Path     string
+	Code     string
+}
+
+func extractCodeBlocks(text string) []CodeBlock {
+	var blocks []CodeBlock
+	var current *CodeBlock
+	var code []string
+	for _, line := range strings.Split(text, "\n") {
+		trimmed := strings.TrimSpace(line)
+		if !strings.HasPrefix(trimmed, "```") {
+			if current != nil {
+				code = append(code, line)
+			}
+			continue
+		}
+		if current != nil {
+			current.Code = strings.Join(code, "\n")
+			blocks = append(blocks, *current)
+			current, code = nil, nil
+			continue
+		}
+		language, path := parseFenceInfo(strings.TrimPrefix(trimmed, "```"))
+		current = &CodeBlock{Language: language, Path: path}
+	}
+	return blocks
+}
+
+func parseFenceInfo(info string) (string, string) {
+	fields := strings.Fields(info)
+	if len(fields) == 0 {
+		return "", ""
+	}
+	language, path := fields[0], ""
+	for _, field := range fields {
+		for _, prefix := range pathAnnotations {
+			if value, ok := strings.CutPrefix(field, prefix); ok {
+				path = strings.Trim(value, `"'`)
+			}
+		}
+	}
+	if path != "" {
+		return language, path
+	}
+	if lang, file, ok := strings.Cut(language, ":"); ok && file != "" {
+		return lang, file
+	}
+	return language, ""
+}
+
+func (s *ChatSession) resolveApplyPath(name string) (string, error) {
+	root := "."
+	if s.workspace != nil {
+		root = s.workspace.root
+	}
+	root, err := filepath.Abs(root)
+	if err != nil {
+		return "", fmt.Errorf("failed to resolve %s: %w", root, err)
+	}
+	if root, err = filepath.EvalSymlinks(root); err != nil {
+		return "", fmt.Errorf("failed to resolve %s: %w", root, err)
+	}
+	cleaned := filepath.Clean(filepath.FromSlash(name))
+	if filepath.IsAbs(cleaned) {
+		cleaned, err = filepath.Rel(root, cleaned)
+		if err != nil {
+			return "", fmt.Errorf("%s is outside %s", name, root)
+		}
+	}
+	if cleaned == ".." || strings.HasPrefix(cleaned, ".."+string(filepath.Separator)) {
+		return "", fmt.Errorf("%s is outside %s", name, root)
+	}
+
+	resolved, err := resolveExisting(filepath.Join(root, cleaned))
+	if err != nil {
+		return "", fmt.Errorf("failed to resolve %s: %w", name, err)
+	}
+	if rel, err := filepath.Rel(root, resolved); err != nil || rel == ".." || strings.HasPrefix(rel, ".."+string(filepath.Separator)) {
+		return "", fmt.Errorf("%s is outside %s", name, root)
+	}
+	return resolved, nil
+}
+
+func resolveExisting(path string) (string, error) {
+	existing, rest := path, ""
+	for {
+		if _, err := os.Lstat(existing); err == nil {
+			break
+		}
+		parent := filepath.Dir(existing)
+		if parent == existing {
+			break
+		}
+		rest = filepath.Join(filepath.Base(existing), rest)
+		existing = parent
+	}
+	resolved, err := filepath.EvalSymlinks(existing)
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(resolved, rest), nil
+}
+
+func (s *ChatSession) applyCodeBlock(ctx context.Context, name, code string) error {
+	path, err := s.resolveApplyPath(name)
+	if err != nil {
+		return err
+	}
+	existing, err := os.ReadFile(path)
+	if err != nil && !errors.Is(err, os.ErrNotExist) {
+		return fmt.Errorf("failed to read %s: %w", path, err)
+	}
+	mode := os.FileMode(0644)
+	if info, err := os.Stat(path); err == nil {
+		mode = info.Mode().Perm()
+	}
+
+	code = strings.TrimRight(code, "\n") + "\n"
+	if existing == nil {
+		fmt.Printf("%sNew file %s (%d lines)%s\n", colorCyan, name, len(splitLines(code)), colorReset)
+		printDiff(name, diffLines(nil, splitLines(code)))
+	} else if printDiff(name, diffLines(splitLines(string(existing)), splitLines(code))) == 0 {
+		fmt.Printf("%s%s already matches the code block.%s\n", colorDim, name, colorReset)
+		return nil
+	}
+	if !s.confirm(ctx, "Write "+name+"?") {
+		fmt.Printf("%sSkipped %s%s\n", colorDim, name, colorReset)
+		return nil
+	}
+
+	if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+		return fmt.Errorf("failed to create directory for %s: %w", name, err)
+	}
+	if err := os.WriteFile(path, []byte(code), mode); err != nil {
+		return fmt.Errorf("failed to write %s: %w", name, err)
+	}
+	fmt.Printf("%sWrote %s%s\n", colorGreen, name, colorReset)
+	return nil
+}
+
+func (s *ChatSession) offerAnnotatedBlocks(ctx context.Context, response string) {
+	for _, block := range extractCodeBlocks(response) {
+		if block.Path == "" {
+			continue
+		}
+		if err := s.applyCodeBlock(ctx, block.Path, block.Code); err != nil {
+			fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		}
+	}
+}
+
+func handleApplyCommand(ctx context.Context, session *ChatSession, path string) error {
+	reply, ok := lastReply(session.conversation.getHistory())
+	if !ok {
+		fmt.Printf("%sNo response to apply yet.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	blocks := extractCodeBlocks(reply.Content)
+	if len(blocks) == 0 {
+		fmt.Printf("%sThe last response has no code blocks.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	if path == "" {
+		annotated := 0
+		for _, block := range blocks {
+			if block.Path != "" {
+				annotated++
+			}
+		}
+		if annotated == 0 {
+			fmt.Printf("%sUsage: /apply <path> writes the last code block to a file.%s\n", colorYellow, colorReset)
+			return nil
+		}
+		session.offerAnnotatedBlocks(ctx, reply.Content)
+		return nil
+	}
+
+	block := blocks[len(blocks)-1]
+	for _, candidate := range blocks {
+		if candidate.Path != "" && filepath.Clean(candidate.Path) == filepath.Clean(path) {
+			block = candidate
+		}
+	}
+	if err := session.applyCodeBlock(ctx, path, block.Code); err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+	}
+	return nil
+}
@@ -0,0 +1,139 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+func readAttachment(path string) (string, error) {
+	info, err := os.Stat(path)
+	if err != nil {
+		return "", fmt.Errorf("failed to read %s: %w", path, err)
+	}
+	if info.IsDir() {
+		return "", fmt.Errorf("%s is a directory", path)
+	}
+	if extract, ok := documentExtractor(path); ok {
+		return extract(path)
+	}
+	return readTextFile(path)
+}
+
+func readTextFile(path string) (string, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return "", fmt.Errorf("failed to read %s: %w", path, err)
+	}
+	if bytes.IndexByte(data, 0) >= 0 {
+		return "", fmt.Errorf("%s looks like a binary file", path)
+	}
+	return strings.TrimRight(string(data), "\n"), nil
+}
+
+func formatAttachment(path, content, part string) string {
+	label := "File: " + path
+	if part != "" {
+		label += " (" + part + ")"
+	}
+	language := strings.TrimPrefix(filepath.Ext(path), ".")
+	if _, ok := documentExtractor(path); ok {
+		language = ""
+	}
+	return fmt.Sprintf("%s\n```%s\n%s\n```", label, language, content)
+}
+
+func chunkByTokens(content string, budget int) []string {
+	var chunks []string
+	var current []string
+	tokens := 0
+	for _, line := range strings.Split(content, "\n") {
+		lineTokens := len(strings.Fields(line))
+		if tokens+lineTokens > budget && len(current) > 0 {
+			chunks = append(chunks, strings.Join(current, "\n"))
+			current, tokens = nil, 0
+		}
+		current = append(current, line)
+		tokens += lineTokens
+	}
+	if len(current) > 0 {
+		chunks = append(chunks, strings.Join(current, "\n"))
+	}
+	return chunks
+}
+
+func (s *ChatSession) attachmentBudget() int {
+	return max(promptBudget(s.config, s.model)-s.conversation.tokens(), 0)
+}
+
+func (s *ChatSession) attachFile(ctx context.Context, path string) error {
+	content, err := readAttachment(path)
+	if err != nil {
+		return err
+	}
+	return s.attachContent(ctx, path, content, formatAttachment)
+}
+
+func (s *ChatSession) attachContent(ctx context.Context, path, content string, format func(path, content, part string) string) error {
+	tokens := len(strings.Fields(content))
+	budget := s.attachmentBudget()
+	if tokens <= budget {
+		s.attachments = append(s.attachments, format(path, content, ""))
+		fmt.Printf("%sAttached %s (%d tokens) to your next message.%s\n", colorGreen, path, tokens, colorReset)
+		return nil
+	}
+
+	fmt.Printf("%s%s is %d tokens but only %d fit in the context budget.%s\n", colorYellow, path, tokens, budget, colorReset)
+	choice, _ := s.prompt(ctx, "[t]runcate, [c]hunk across messages, [i]ndex for retrieval or [a]bort?")
+	chunks := chunkByTokens(content, max(budget, minPromptBudget))
+	switch strings.ToLower(strings.TrimSpace(choice)) {
+	case "t", "truncate":
+		s.attachments = append(s.attachments, format(path, chunks[0], "truncated"))
+		fmt.Printf("%sAttached the first %d tokens of %s.%s\n", colorGreen, len(strings.Fields(chunks[0])), path, colorReset)
+	case "c", "chunk":
+		for i, chunk := range chunks {
+			s.attachmentChunks = append(s.attachmentChunks, format(path, chunk, fmt.Sprintf("part %d/%d", i+1, len(chunks))))
+		}
+		s.attachments = append(s.attachments, s.attachmentChunks[0])
+		s.attachmentChunks = s.attachmentChunks[1:]
+		fmt.Printf("%sSplit %s into %d parts, one per message.%s\n", colorGreen, path, len(chunks), colorReset)
+	case "i", "index":
+		return s.indexAttachment(ctx, path, content)
+	default:
+		return errors.New("attachment cancelled")
+	}
+	return nil
+}
+
+func (s *ChatSession) withAttachments(message string) string {
+	if len(s.attachments) == 0 {
+		return message
+	}
+	blocks := append(s.attachments, message)
+	s.attachments = nil
+	if len(s.attachmentChunks) > 0 {
+		s.attachments = []string{s.attachmentChunks[0]}
+		s.attachmentChunks = s.attachmentChunks[1:]
+		fmt.Printf("%sThe next part will be attached to your next message (%d left).%s\n", colorDim, len(s.attachmentChunks)+1, colorReset)
+	}
+	return strings.Join(blocks, "\n\n")
+}
+
+func handleAttachCommand(ctx context.Context, session *ChatSession, path string) error {
+	if path == "" {
+		if len(session.attachments) == 0 {
+			fmt.Printf("%sUsage: /attach <path>%s\n", colorYellow, colorReset)
+			return nil
+		}
+		fmt.Printf("%s%d attachment(s) pending for your next message.%s\n", colorCyan, len(session.attachments), colorReset)
+		return nil
+	}
+	if err := session.attachFile(ctx, path); err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+	}
+	return nil
+}
@@ -0,0 +1,269 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"code/aili"
+)
+
+var audioRecorders = [][]string{
+	{"rec", "-q", "-c", "1", "-r", "16000"},
+	{"arecord", "-q", "-f", "S16_LE", "-c", "1", "-r", "16000"},
+}
+
+var audioPlayers = [][]string{
+	{"ffplay", "-nodisp", "-autoexit", "-loglevel", "quiet"},
+	{"mpv", "--no-video", "--really-quiet"},
+	{"afplay"},
+	{"paplay"},
+}
+
+func speechBackend(config *Config) string {
+	if config.TTSCommand != "" {
+		return config.TTSCommand
+	}
+	return config.TTSModel + "/" + config.TTSVoice
+}
+
+func (s *ChatSession) speakResponse(ctx context.Context, text string) {
+	fmt.Printf("%sSpeaking, press Ctrl+C to stop.%s\n", colorDim, colorReset)
+	turnCtx, endTurn := s.beginTurn(ctx)
+	defer endTurn()
+	if err := s.speak(turnCtx, text); err != nil {
+		fmt.Printf("%sFailed to speak response: %v%s\n", colorRed, err, colorReset)
+	}
+}
+
+func speakableText(text string) string {
+	var lines []string
+	inCodeBlock := false
+	for _, line := range strings.Split(text, "\n") {
+		if strings.HasPrefix(strings.TrimSpace(line), "```") {
+			if !inCodeBlock {
+				lines = append(lines, "(code omitted)")
+			}
+			inCodeBlock = !inCodeBlock
+			continue
+		}
+		if !inCodeBlock {
+			lines = append(lines, strings.NewReplacer("*", "", "`", "", "#", "").Replace(line))
+		}
+	}
+	return strings.TrimSpace(strings.Join(lines, "\n"))
+}
+
+func (s *ChatSession) speak(ctx context.Context, text string) error {
+	text = speakableText(text)
+	if text == "" {
+		return nil
+	}
+	if fields := strings.Fields(s.config.TTSCommand); len(fields) > 0 {
+		cmd := exec.CommandContext(ctx, fields[0], fields[1:]...)
+		cmd.Stdin = strings.NewReader(text)
+		return ignoreCancel(ctx, cmd.Run())
+	}
+
+	provider, ok := s.apiClient.Provider.(*aili.OpenAIProvider)
+	if !ok {
+		return fmt.Errorf("the %s provider has no speech endpoint, set tts_command to use a local engine", s.apiClient.Provider.Name())
+	}
+	audio, err := s.apiClient.fetchSpeech(ctx, provider, text)
+	if err != nil {
+		return ignoreCancel(ctx, err)
+	}
+	return ignoreCancel(ctx, playAudio(ctx, audio, s.config.AudioPlayer))
+}
+
+func (c *APIClient) fetchSpeech(ctx context.Context, provider *aili.OpenAIProvider, text string) ([]byte, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
+	defer cancel()
+
+	req, err := provider.NewSpeechRequest(ctx, text, c.config.TTSModel, c.config.TTSVoice)
+	if err != nil {
+		return nil, err
+	}
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return nil, fmt.Errorf("failed to request speech: %w", err)
+	}
+	defer response.Body.Close()
+	body, err := io.ReadAll(response.Body)
+	if err != nil {
+		return nil, fmt.Errorf("failed to read speech: %w", err)
+	}
+	if response.StatusCode != http.StatusOK {
+		return nil, aili.NewAPIError(response, body)
+	}
+	return body, nil
+}
+
+func findAudioPlayer(configured string) ([]string, error) {
+	if fields := strings.Fields(configured); len(fields) > 0 {
+		return fields, nil
+	}
+	for _, player := range audioPlayers {
+		if _, err := exec.LookPath(player[0]); err == nil {
+			return player, nil
+		}
+	}
+	return nil, errors.New("no audio player found, install ffplay or mpv or set audio_player")
+}
+
+func playAudio(ctx context.Context, audio []byte, configured string) error {
+	player, err := findAudioPlayer(configured)
+	if err != nil {
+		return err
+	}
+	file, err := os.CreateTemp("", "aili-speech-*.mp3")
+	if err != nil {
+		return fmt.Errorf("failed to create temp file: %w", err)
+	}
+	defer os.Remove(file.Name())
+	if _, err := file.Write(audio); err != nil {
+		file.Close()
+		return fmt.Errorf("failed to write temp file: %w", err)
+	}
+	if err := file.Close(); err != nil {
+		return fmt.Errorf("failed to write temp file: %w", err)
+	}
+	return exec.CommandContext(ctx, player[0], append(player[1:], file.Name())...).Run()
+}
+
+func ignoreCancel(ctx context.Context, err error) error {
+	if ctx.Err() != nil {
+		return nil
+	}
+	return err
+}
+
+func transcriptionModel(config *Config, provider *aili.OpenAIProvider) string {
+	if config.TranscriptionModel != "" {
+		return config.TranscriptionModel
+	}
+	if provider.Name() == "openai" {
+		return "whisper-1"
+	}
+	return "whisper-large-v3"
+}
+
+func (c *APIClient) transcribe(ctx context.Context, provider *aili.OpenAIProvider, audio []byte) (string, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
+	defer cancel()
+
+	req, err := provider.NewTranscriptionRequest(ctx, audio, "voice.wav", transcriptionModel(c.config, provider))
+	if err != nil {
+		return "", err
+	}
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return "", fmt.Errorf("failed to request transcription: %w", err)
+	}
+	defer response.Body.Close()
+	body, err := io.ReadAll(response.Body)
+	if err != nil {
+		return "", fmt.Errorf("failed to read transcription: %w", err)
+	}
+	if response.StatusCode != http.StatusOK {
+		return "", aili.NewAPIError(response, body)
+	}
+
+	var result struct {
+		Text string `json:"text"`
+	}
+	if err := json.Unmarshal(body, &result); err != nil {
+		return "", fmt.Errorf("failed to parse transcription: %w", err)
+	}
+	return strings.TrimSpace(result.Text), nil
+}
+
+func findAudioRecorder(configured string) ([]string, error) {
+	if fields := strings.Fields(configured); len(fields) > 0 {
+		return fields, nil
+	}
+	for _, recorder := range audioRecorders {
+		if _, err := exec.LookPath(recorder[0]); err == nil {
+			return recorder, nil
+		}
+	}
+	return nil, errors.New("no audio recorder found, install sox or arecord or set audio_recorder")
+}
+
+func (s *ChatSession) record(ctx context.Context) ([]byte, error) {
+	recorder, err := findAudioRecorder(s.config.AudioRecorder)
+	if err != nil {
+		return nil, err
+	}
+	dir, err := os.MkdirTemp("", "aili-voice-")
+	if err != nil {
+		return nil, fmt.Errorf("failed to create temp dir: %w", err)
+	}
+	defer os.RemoveAll(dir)
+	path := filepath.Join(dir, "voice.wav")
+
+	cmd := exec.Command(recorder[0], append(recorder[1:], path)...)
+	if err := cmd.Start(); err != nil {
+		return nil, fmt.Errorf("failed to start %s: %w", recorder[0], err)
+	}
+	fmt.Printf("%s● Recording with %s...%s\n", colorRed, recorder[0], colorReset)
+	_, ok := s.prompt(ctx, "Press Enter to stop.")
+	cmd.Process.Signal(os.Interrupt)
+	cmd.Wait()
+	if !ok {
+		return nil, nil
+	}
+
+	audio, err := os.ReadFile(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to read recording: %w", err)
+	}
+	if len(audio) == 0 {
+		return nil, errors.New("the recording is empty")
+	}
+	return audio, nil
+}
+
+func handleVoiceCommand(ctx context.Context, session *ChatSession, _ string) error {
+	provider, ok := session.apiClient.Provider.(*aili.OpenAIProvider)
+	if !ok {
+		fmt.Printf("%sThe %s provider has no transcription endpoint.%s\n", colorRed, session.apiClient.Provider.Name(), colorReset)
+		return nil
+	}
+
+	turnCtx, endTurn := session.beginTurn(ctx)
+	audio, err := session.record(turnCtx)
+	if err != nil || audio == nil {
+		endTurn()
+		if err != nil {
+			fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		}
+		return nil
+	}
+	fmt.Printf("%sTranscribing...%s\n", colorDim, colorReset)
+	transcript, err := session.apiClient.transcribe(turnCtx, provider, audio)
+	endTurn()
+	if err != nil {
+		fmt.Printf("%sFailed to transcribe recording: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	if transcript == "" {
+		fmt.Printf("%sNo speech detected, nothing sent.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	fmt.Printf("%sTranscript:%s %s\n", colorGreen, colorReset, transcript)
+	if !session.confirm(ctx, "Send this message?") {
+		fmt.Printf("%sDiscarded.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	return sendChatTurn(ctx, session, transcript, session.model)
+}
@@ -0,0 +1,266 @@
+package main
+
+import (
+	"bufio"
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"os/signal"
+	"strings"
+	"sync"
+	"syscall"
+	"time"
+
+	"golang.org/x/sync/errgroup"
+	"golang.org/x/term"
+
+	"code/aili"
+)
+
+const (
+	batchRetries      = 2
+	batchRetryBackoff = 2 * time.Second
+	maxBatchLineBytes = 1 << 20
+	batchBarWidth     = 30
+)
+
+type BatchItem struct {
+	ID     string `json:"id,omitempty"`
+	Prompt string `json:"prompt"`
+	Model  string `json:"model,omitempty"`
+	System string `json:"system,omitempty"`
+	line   int
+}
+
+type BatchResult struct {
+	Line             int    `json:"line"`
+	ID               string `json:"id,omitempty"`
+	Model            string `json:"model"`
+	Prompt           string `json:"prompt"`
+	Response         string `json:"response,omitempty"`
+	FinishReason     string `json:"finish_reason,omitempty"`
+	Error            string `json:"error,omitempty"`
+	Attempts         int    `json:"attempts"`
+	PromptTokens     int    `json:"prompt_tokens"`
+	CompletionTokens int    `json:"completion_tokens"`
+	ElapsedMs        int64  `json:"elapsed_ms"`
+}
+
+type batchProgress struct {
+	mu      sync.Mutex
+	enabled bool
+	start   time.Time
+	total   int
+	done    int
+	failed  int
+	workers []string
+	drawn   int
+}
+
+func readBatchItems(path string) ([]BatchItem, error) {
+	file, err := os.Open(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to open batch file: %w", err)
+	}
+	defer file.Close()
+
+	var items []BatchItem
+	scanner := bufio.NewScanner(file)
+	scanner.Buffer(make([]byte, 0, 64*1024), maxBatchLineBytes)
+	for line := 1; scanner.Scan(); line++ {
+		text := strings.TrimSpace(scanner.Text())
+		if text == "" {
+			continue
+		}
+		item := BatchItem{Prompt: text}
+		if strings.HasPrefix(text, "{") {
+			if err := json.Unmarshal([]byte(text), &item); err != nil {
+				return nil, fmt.Errorf("invalid JSON on line %d: %w", line, err)
+			}
+		}
+		if strings.TrimSpace(item.Prompt) == "" {
+			return nil, fmt.Errorf("line %d has no prompt", line)
+		}
+		item.line = line
+		items = append(items, item)
+	}
+	if err := scanner.Err(); err != nil {
+		return nil, fmt.Errorf("failed to read batch file: %w", err)
+	}
+	return items, nil
+}
+
+func runBatch(session *ChatSession, path, out string, concurrency int) error {
+	items, err := readBatchItems(path)
+	if err != nil {
+		return err
+	}
+	if len(items) == 0 {
+		return fmt.Errorf("no prompts in %s", path)
+	}
+
+	var output io.Writer = os.Stdout
+	if out != "" && out != "-" {
+		file, err := os.Create(out)
+		if err != nil {
+			return fmt.Errorf("failed to create output file: %w", err)
+		}
+		defer file.Close()
+		output = file
+	}
+
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	history := session.conversation.getHistory()
+	system := history[:leadingSystemMessages(history)]
+	concurrency = min(concurrency, len(items))
+	progress := &batchProgress{enabled: term.IsTerminal(int(os.Stderr.Fd())), start: time.Now(), total: len(items), workers: make([]string, concurrency)}
+	queue := make(chan BatchItem)
+	encoder := json.NewEncoder(output)
+
+	var (
+		mu       sync.Mutex
+		writeErr error
+		g        errgroup.Group
+	)
+	for worker := 0; worker < concurrency; worker++ {
+		g.Go(func() error {
+			client, err := newAPIClient(session.config)
+			if err != nil {
+				return err
+			}
+			client.Stream = false
+			for item := range queue {
+				progress.set(worker, fmt.Sprintf("line %d: %s", item.line, item.Prompt))
+				result := runBatchItem(ctx, session, client, system, item)
+				progress.set(worker, "idle")
+				progress.finish(result.Error != "")
+
+				mu.Lock()
+				if err := encoder.Encode(result); err != nil && writeErr == nil {
+					writeErr = fmt.Errorf("failed to write result: %w", err)
+				}
+				if result.Error == "" {
+					session.recordUsage(result.Model, result.PromptTokens, result.CompletionTokens, time.Duration(result.ElapsedMs)*time.Millisecond)
+				}
+				mu.Unlock()
+			}
+			return nil
+		})
+	}
+
+	for _, item := range items {
+		if ctx.Err() != nil {
+			break
+		}
+		queue <- item
+	}
+	close(queue)
+	g.Wait()
+	progress.clear()
+
+	if writeErr != nil {
+		return writeErr
+	}
+	fmt.Fprintf(os.Stderr, "%sProcessed %d of %d prompts, %d failed, %s in %v%s\n", colorDim, progress.done, len(items), progress.failed,
+		formatCost(session.conversation.totalCost()), time.Since(progress.start).Round(time.Second), colorReset)
+	if ctx.Err() != nil {
+		return errors.New("batch interrupted")
+	}
+	if progress.failed > 0 {
+		return fmt.Errorf("%d of %d prompts failed", progress.failed, len(items))
+	}
+	return nil
+}
+
+func runBatchItem(ctx context.Context, session *ChatSession, client *APIClient, system []Message, item BatchItem) BatchResult {
+	model := item.Model
+	if model == "" {
+		model = session.model
+	}
+	history := append([]Message(nil), system...)
+	if item.System != "" {
+		history = []Message{{Role: "system", Content: item.System, Timestamp: time.Now()}}
+	}
+	history = append(history, Message{Role: "user", Content: withStoredUserSuffix(session.config, item.Prompt), Timestamp: time.Now()})
+
+	result := BatchResult{Line: item.line, ID: item.ID, Model: model, Prompt: item.Prompt, PromptTokens: countTokens(client.prepareHistory(history, model))}
+	start := time.Now()
+	var completion Completion
+	var err error
+	for result.Attempts = 1; ; result.Attempts++ {
+		completion, err = Ask(ctx, client, history, model)
+		if err == nil || ctx.Err() != nil || !aili.IsTransient(err) || result.Attempts > batchRetries {
+			break
+		}
+		select {
+		case <-time.After(batchRetryBackoff * time.Duration(result.Attempts)):
+		case <-ctx.Done():
+		}
+	}
+	result.ElapsedMs = time.Since(start).Milliseconds()
+	if err != nil {
+		result.Error = err.Error()
+		return result
+	}
+
+	result.Response = session.postProcess(completion.Content)
+	result.FinishReason = completion.FinishReason
+	result.CompletionTokens = len(strings.Fields(result.Response))
+	return result
+}
+
+func (p *batchProgress) set(worker int, status string) {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	p.workers[worker] = strings.Join(strings.Fields(status), " ")
+	p.draw()
+}
+
+func (p *batchProgress) finish(failed bool) {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	p.done++
+	if failed {
+		p.failed++
+	}
+	p.draw()
+}
+
+func (p *batchProgress) draw() {
+	if !p.enabled {
+		return
+	}
+	width, _, err := term.GetSize(int(os.Stderr.Fd()))
+	if err != nil || width < minBoxWidth {
+		width = defaultBoxWidth
+	}
+	if p.drawn > 0 {
+		fmt.Fprintf(os.Stderr, "\x1b[%dA", p.drawn)
+	}
+
+	filled := p.done * batchBarWidth / p.total
+	bar := strings.Repeat("█", filled) + strings.Repeat("░", batchBarWidth-filled)
+	failed := ""
+	if p.failed > 0 {
+		failed = fmt.Sprintf(" %s%d failed%s", colorRed, p.failed, colorReset)
+	}
+	fmt.Fprintf(os.Stderr, "\r%s%s%s %d/%d%s %v\x1b[K\n", colorCyan, bar, colorReset, p.done, p.total, failed, time.Since(p.start).Round(time.Second))
+	for i, status := range p.workers {
+		fmt.Fprintf(os.Stderr, "\r%s  #%d %s%s\x1b[K\n", colorDim, i+1, truncateString(status, width-8), colorReset)
+	}
+	p.drawn = 1 + len(p.workers)
+}
+
+func (p *batchProgress) clear() {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	if p.enabled && p.drawn > 0 {
+		fmt.Fprintf(os.Stderr, "\x1b[%dA\x1b[J", p.drawn)
+		p.drawn = 0
+	}
+}
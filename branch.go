@@ -0,0 +1,140 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"strconv"
+	"strings"
+)
+
+func (s *ChatSession) branchFrom(history []Message) error {
+	if s.sessionID != "" {
+		if err := s.persist(); err != nil {
+			return err
+		}
+	}
+	parent := s.sessionID
+	s.sessionID, s.parentID, s.branchPoint = newSessionID(), parent, len(history)
+	branched := append([]Message(nil), history...)
+	s.conversation.replaceWith(&Conversation{History: branched, tokenCount: countTokens(branched)})
+	return s.persist()
+}
+
+func handleForkCommand(_ context.Context, session *ChatSession, _ string) error {
+	parent := session.sessionID
+	if err := session.branchFrom(session.conversation.getHistory()); err != nil {
+		fmt.Printf("%sFailed to fork session: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	fmt.Printf("%sForked session %s into %s, /switch %s to go back.%s\n", colorGreen, parent, session.sessionID, parent, colorReset)
+	return nil
+}
+
+func handleBranchCommand(ctx context.Context, session *ChatSession, args string) error {
+	history := session.conversation.getHistory()
+	if args == "" {
+		printNumberedHistory(history)
+		fmt.Printf("%sUsage: /branch <message-index> continues from that message in a new session, /branch <name> checkpoints the conversation.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	index, err := strconv.Atoi(args)
+	if err != nil {
+		return handleCheckpointCommand(ctx, session, args)
+	}
+	if index < 1 || index > len(history) {
+		fmt.Printf("%sMessage index must be between 1 and %d%s\n", colorRed, len(history), colorReset)
+		return nil
+	}
+
+	parent := session.sessionID
+	if err := session.branchFrom(history[:index]); err != nil {
+		fmt.Printf("%sFailed to branch session: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	fmt.Printf("%sBranched %s at message %d into %s, /switch %s to go back.%s\n", colorGreen, parent, index, session.sessionID, parent, colorReset)
+	printConversationSummary(session.conversation)
+	return nil
+}
+
+func printNumberedHistory(history []Message) {
+	for i, msg := range history {
+		fmt.Printf("%s%3d%s %s%s:%s %s\n", colorCyan, i+1, colorReset, colorYellow, msg.Role, colorReset, truncateString(strings.Join(strings.Fields(msg.Content), " "), maxSessionTitle*2))
+	}
+}
+
+func handleSwitchCommand(_ context.Context, session *ChatSession, id string) error {
+	if id == "" {
+		fmt.Printf("%sUsage: /switch <session-id>%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if id == session.sessionID {
+		fmt.Printf("%sAlready in session %s%s\n", colorYellow, id, colorReset)
+		return nil
+	}
+	if !sessionExists(id) {
+		fmt.Printf("%sUnknown session %s%s\n", colorRed, id, colorReset)
+		return nil
+	}
+	if err := session.persist(); err != nil {
+		fmt.Printf("%sFailed to save session %s: %v%s\n", colorRed, session.sessionID, err, colorReset)
+		return nil
+	}
+	if err := resumeSession(session, id); err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+	}
+	return nil
+}
+
+func handleBranchesCommand(_ context.Context, session *ChatSession, _ string) error {
+	infos, err := loadSessionInfos()
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+
+	byID := make(map[string]sessionInfo, len(infos))
+	children := make(map[string][]sessionInfo)
+	for _, info := range infos {
+		byID[info.ID] = info
+		if info.Parent != "" {
+			children[info.Parent] = append(children[info.Parent], info)
+		}
+	}
+
+	root := session.sessionID
+	for seen := map[string]bool{}; !seen[root]; {
+		seen[root] = true
+		info, ok := byID[root]
+		if !ok || info.Parent == "" {
+			break
+		}
+		root = info.Parent
+	}
+	if len(children[root]) == 0 {
+		fmt.Printf("%sNo branches yet. Use /fork or /branch to create one.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	fmt.Printf("%sBranches:%s\n", colorCyan, colorReset)
+	printBranchTree(byID[root], root, children, session.sessionID, 0, map[string]bool{})
+	return nil
+}
+
+func printBranchTree(info sessionInfo, id string, children map[string][]sessionInfo, current string, depth int, seen map[string]bool) {
+	if seen[id] {
+		return
+	}
+	seen[id] = true
+	marker := " "
+	if id == current {
+		marker = "*"
+	}
+	title := info.Title
+	if title == "" {
+		title = "(not saved)"
+	}
+	fmt.Printf("%s %s%s%s%s  %s\n", marker, strings.Repeat("  ", depth), colorCyan, id, colorReset, title)
+	for _, child := range children[id] {
+		printBranchTree(child, child.ID, children, current, depth+1, seen)
+	}
+}
@@ -0,0 +1,276 @@
+package main
+
+import (
+	"errors"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"golang.org/x/term"
+)
+
+const (
+	commandChat      = "chat"
+	commandAsk       = "ask"
+	commandConfig    = "config"
+	commandSessions  = "sessions"
+	commandModels    = "models"
+	commandIndex     = "index"
+	commandCompare   = "compare"
+	commandBatch     = "batch"
+	commandCommitMsg = "commit-msg"
+	commandReview    = "review"
+)
+
+type Options struct {
+	Command        string
+	Args           []string
+	SystemFile     string
+	Prompt         string
+	Format         string
+	PlaybackFile   string
+	PlaybackSpeed  float64
+	IgnoreSettings bool
+	Model          string
+	Temperature    float64
+	ConfigPath     string
+	Resume         string
+	Persona        string
+	Multiline      bool
+	Load           string
+	File           string
+	Workspace      string
+	RAG            string
+	Speak          bool
+	TUI            bool
+	Template       string
+	Vars           templateVars
+	Models         []string
+	Out            string
+	Concurrency    int
+	JSON           bool
+	Schema         string
+	NoColor        bool
+}
+
+func parseOptions() (*Options, error) {
+	options := &Options{Command: commandChat, Vars: templateVars{}}
+	flags := flag.NewFlagSet(filepath.Base(os.Args[0]), flag.ExitOnError)
+	systemFromStdin := flags.Bool("system-from-stdin", false, "read the system prompt from stdin")
+	flags.StringVar(&options.SystemFile, "system-file", systemPromptFile, "file to read the system prompt from, or '-' for stdin")
+	flags.StringVar(&options.Format, "format", "text", "one-shot output format: text, json or markdown")
+	flags.StringVar(&options.PlaybackFile, "playback", "", "replay a saved conversation without calling the API")
+	flags.Float64Var(&options.PlaybackSpeed, "playback-speed", 1.0, "playback speed multiplier")
+	flags.BoolVar(&options.IgnoreSettings, "ignore-session-config", false, "do not apply settings stored in loaded conversations")
+	flags.StringVar(&options.Model, "model", "", "model to use instead of the provider default")
+	flags.Float64Var(&options.Temperature, "temperature", -1, "sampling temperature, overrides the config file")
+	flags.StringVar(&options.ConfigPath, "config", "", "path to the config file, defaults to ./config.yaml or $XDG_CONFIG_HOME/aili/config.yaml")
+	flags.StringVar(&options.ConfigPath, "config-path", "", "alias for --config")
+	flags.StringVar(&options.Resume, "resume", "", "resume the saved session with this id")
+	flags.StringVar(&options.Persona, "persona", "", "start with a persona defined in the config file")
+	flags.StringVar(&options.Load, "load", "", "continue a conversation from a saved or exported transcript")
+	flags.StringVar(&options.Workspace, "context", "", "share a directory the model can read files from with tools")
+	flags.StringVar(&options.RAG, "rag", "", "add relevant chunks from a directory indexed with the index subcommand")
+	flags.StringVar(&options.Template, "template", "", "build the one-shot prompt from a named template")
+	flags.StringVar(&options.Out, "out", "", "batch results file, stdout if empty")
+	flags.IntVar(&options.Concurrency, "concurrency", 4, "number of batch prompts to run at once")
+	models := flags.String("models", "", "comma-separated models to compare")
+	flags.Var(&options.Vars, "var", "template variable as name=value, can be repeated")
+	flags.StringVar(&options.File, "file", "", "attach a file to the first message")
+	flags.BoolVar(&options.JSON, "json", false, "ask for a JSON response and print only the validated JSON")
+	flags.StringVar(&options.Schema, "schema", "", "JSON Schema file the response must satisfy, implies --json")
+	flags.BoolVar(&options.TUI, "tui", false, "use a full-screen interface with a scrollable chat pane")
+	flags.BoolVar(&options.Speak, "speak", false, "read each response aloud with the configured text-to-speech backend")
+	flags.BoolVar(&options.NoColor, "no-color", false, "disable colored output, also set by the NO_COLOR environment variable")
+	flags.BoolVar(&options.Multiline, "multiline", false, "read each message until an empty line instead of a single line")
+	flags.Usage = func() {
+		fmt.Fprintf(flags.Output(), "Usage: %s [flags] [chat | ask <prompt> | compare <prompt> --models a,b | batch <file.jsonl> --out <file> | commit-msg | review [range] | config [show | path | init | set <key> <value>] | models | index <dir> | sessions ...]\n", flags.Name())
+		flags.PrintDefaults()
+	}
+	flags.Parse(os.Args[1:])
+
+	args := flags.Args()
+	if len(args) > 0 {
+		switch args[0] {
+		case commandChat, commandAsk, commandConfig, commandSessions, commandModels, commandIndex, commandCommitMsg, commandReview:
+			options.Command = args[0]
+			flags.Parse(args[1:])
+			args = flags.Args()
+		case commandCompare, commandBatch:
+			options.Command = args[0]
+			args = parseInterspersed(flags, args[1:])
+		default:
+			options.Command = commandAsk
+		}
+	}
+	if options.Command == commandSessions && len(args) > 0 && args[0] == "resume" {
+		if len(args) != 2 {
+			return nil, errors.New("usage: sessions resume <id>")
+		}
+		options.Command, options.Resume, args = commandChat, args[1], nil
+	}
+	options.Args = args
+	for _, model := range strings.Split(*models, ",") {
+		if model = strings.TrimSpace(model); model != "" {
+			options.Models = append(options.Models, model)
+		}
+	}
+	if options.Command == commandCompare {
+		options.Prompt = strings.TrimSpace(strings.Join(args, " "))
+		if options.Prompt == "" {
+			return nil, errors.New("compare requires a prompt")
+		}
+		if len(options.Models) < 2 {
+			return nil, errors.New("compare requires at least two models in --models")
+		}
+	} else if len(options.Models) > 0 {
+		return nil, errors.New("--models only applies to compare")
+	}
+	if options.Command == commandBatch && len(args) != 1 {
+		return nil, errors.New("usage: batch <prompts.jsonl> [--out results.jsonl] [--concurrency n]")
+	}
+	configPath, err := resolveConfigPath(options.ConfigPath)
+	if err != nil {
+		return nil, err
+	}
+	options.ConfigPath = configPath
+	if options.Command == commandCommitMsg && len(args) > 0 {
+		return nil, fmt.Errorf("unexpected arguments to commit-msg: %s", strings.Join(args, " "))
+	}
+	if options.Command == commandReview && len(args) > 1 {
+		return nil, errors.New("usage: review [range]")
+	}
+	if options.Concurrency < 1 {
+		return nil, errors.New("--concurrency must be at least 1")
+	}
+
+	if *systemFromStdin {
+		if options.SystemFile != systemPromptFile && options.SystemFile != "-" {
+			return nil, errors.New("--system-from-stdin cannot be combined with --system-file")
+		}
+		options.SystemFile = "-"
+	}
+
+	if options.Resume != "" && options.Command != commandChat {
+		return nil, errors.New("--resume only applies to chat mode")
+	}
+	if options.Load != "" && options.Command != commandChat {
+		return nil, errors.New("--load only applies to chat mode")
+	}
+	if options.Load != "" && options.Resume != "" {
+		return nil, errors.New("--load cannot be combined with --resume")
+	}
+	if options.Command == commandChat && len(args) > 0 {
+		return nil, fmt.Errorf("unexpected arguments to chat: %s", strings.Join(args, " "))
+	}
+	if options.Command == commandAsk {
+		options.Prompt = strings.TrimSpace(strings.Join(args, " "))
+	}
+	if options.SystemFile != "-" && (options.Command == commandChat || options.Command == commandAsk) {
+		piped, err := readPipedInput()
+		if err != nil {
+			return nil, err
+		}
+		if piped != "" {
+			options.Command = commandAsk
+			options.Prompt = strings.TrimSpace(options.Prompt + "\n\n" + piped)
+		}
+	}
+	if options.Template != "" {
+		if options.Command != commandChat && options.Command != commandAsk {
+			return nil, errors.New("--template only applies to one-shot mode")
+		}
+		options.Command = commandAsk
+	}
+	if options.Command == commandAsk && options.Prompt == "" && options.Template == "" {
+		return nil, errors.New("ask requires a prompt")
+	}
+	if options.SystemFile == "-" && options.Prompt == "" {
+		return nil, errors.New("reading the system prompt from stdin requires the user prompt as an argument")
+	}
+
+	switch options.Format {
+	case "text", "json", "markdown":
+	default:
+		return nil, fmt.Errorf("invalid --format %q, expected text, json or markdown", options.Format)
+	}
+	if options.Format != "text" && options.Command != commandAsk {
+		return nil, errors.New("--format only applies to one-shot mode")
+	}
+
+	if options.Schema != "" {
+		options.JSON = true
+	}
+	if options.JSON && options.Format != "text" {
+		return nil, errors.New("--json cannot be combined with --format")
+	}
+
+	if options.PlaybackSpeed <= 0 {
+		return nil, errors.New("--playback-speed must be positive")
+	}
+	if options.PlaybackFile != "" && options.Prompt != "" {
+		return nil, errors.New("--playback cannot be combined with a prompt")
+	}
+
+	return options, nil
+}
+
+func parseInterspersed(flags *flag.FlagSet, args []string) []string {
+	var positional []string
+	for {
+		flags.Parse(args)
+		args = flags.Args()
+		if len(args) == 0 {
+			return positional
+		}
+		positional = append(positional, args[0])
+		args = args[1:]
+	}
+}
+
+func readPipedInput() (string, error) {
+	if term.IsTerminal(int(os.Stdin.Fd())) {
+		return "", nil
+	}
+	data, err := io.ReadAll(os.Stdin)
+	if err != nil {
+		return "", fmt.Errorf("failed to read piped input: %w", err)
+	}
+	return strings.TrimSpace(string(data)), nil
+}
+
+func (o *Options) applyOverrides(config *Config) error {
+	if o.Model != "" {
+		config.sources["model"] = "flag --model"
+	} else if model := os.Getenv(envModelVar); model != "" {
+		o.Model = model
+		config.sources["model"] = envSource(envModelVar)
+	}
+	if o.Temperature >= 0 {
+		if o.Temperature > 2 {
+			return errors.New("--temperature must be between 0 and 2")
+		}
+		config.Temperature = o.Temperature
+		config.sources["temperature"] = "flag --temperature"
+	}
+	if o.JSON {
+		config.ResponseFormat = "json_object"
+		config.sources["response_format"] = "flag --json"
+	}
+	if o.Schema != "" {
+		config.ResponseSchema = o.Schema
+		config.sources["response_schema"] = "flag --schema"
+		return config.loadResponseSchema()
+	}
+	return nil
+}
+
+func (o *Options) modelOrDefault(provider ChatProvider) string {
+	if o.Model != "" {
+		return o.Model
+	}
+	return provider.DefaultModel()
+}
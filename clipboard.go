@@ -0,0 +1,121 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"encoding/base64"
+	"errors"
+	"fmt"
+	"os"
+	"os/exec"
+	"runtime"
+	"strings"
+)
+
+const maxOSC52Bytes = 100_000
+
+type clipboardTool struct {
+	copy  []string
+	paste []string
+	ready func() bool
+}
+
+var clipboardTools = []clipboardTool{
+	{copy: []string{"pbcopy"}, paste: []string{"pbpaste"}, ready: func() bool { return runtime.GOOS == "darwin" }},
+	{copy: []string{"wl-copy"}, paste: []string{"wl-paste", "--no-newline"}, ready: func() bool { return os.Getenv("WAYLAND_DISPLAY") != "" }},
+	{copy: []string{"xclip", "-selection", "clipboard"}, paste: []string{"xclip", "-selection", "clipboard", "-o"}, ready: func() bool { return os.Getenv("DISPLAY") != "" }},
+	{copy: []string{"xsel", "--clipboard", "--input"}, paste: []string{"xsel", "--clipboard", "--output"}, ready: func() bool { return os.Getenv("DISPLAY") != "" }},
+	{copy: []string{"clip.exe"}, paste: []string{"powershell.exe", "-NoProfile", "-Command", "Get-Clipboard"}, ready: func() bool { return true }},
+}
+
+func findClipboard() (clipboardTool, bool) {
+	for _, tool := range clipboardTools {
+		if !tool.ready() {
+			continue
+		}
+		if _, err := exec.LookPath(tool.copy[0]); err == nil {
+			return tool, true
+		}
+	}
+	return clipboardTool{}, false
+}
+
+func copyToClipboard(text string) (string, error) {
+	if tool, ok := findClipboard(); ok {
+		cmd := exec.Command(tool.copy[0], tool.copy[1:]...)
+		cmd.Stdin = strings.NewReader(text)
+		if err := cmd.Run(); err == nil {
+			return tool.copy[0], nil
+		}
+	}
+	if len(text) > maxOSC52Bytes {
+		return "", errors.New("no clipboard tool found, install wl-clipboard, xclip or xsel")
+	}
+	fmt.Fprintf(outputFile(), "\033]52;c;%s\a", base64.StdEncoding.EncodeToString([]byte(text)))
+	return "the terminal (OSC 52)", nil
+}
+
+func readClipboard() (string, error) {
+	tool, ok := findClipboard()
+	if !ok {
+		return "", errors.New("no clipboard tool found, install wl-clipboard, xclip or xsel")
+	}
+	var stdout, stderr bytes.Buffer
+	cmd := exec.Command(tool.paste[0], tool.paste[1:]...)
+	cmd.Stdout, cmd.Stderr = &stdout, &stderr
+	if err := cmd.Run(); err != nil {
+		return "", fmt.Errorf("failed to read the clipboard with %s: %v %s", tool.paste[0], err, strings.TrimSpace(stderr.String()))
+	}
+	return strings.TrimRight(strings.ReplaceAll(stdout.String(), "\r\n", "\n"), "\n"), nil
+}
+
+func handleCopyCommand(_ context.Context, session *ChatSession, args string) error {
+	reply, ok := lastReply(session.conversation.getHistory())
+	if !ok {
+		fmt.Printf("%sNo response to copy yet.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	text, what := reply.Content, "the last response"
+	switch args {
+	case "":
+	case "code":
+		blocks := extractCodeBlocks(reply.Content)
+		if len(blocks) == 0 {
+			fmt.Printf("%sThe last response has no code blocks.%s\n", colorYellow, colorReset)
+			return nil
+		}
+		text, what = blocks[len(blocks)-1].Code, "the last code block"
+	default:
+		fmt.Printf("%sUsage: /copy [code]%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	target, err := copyToClipboard(text)
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	fmt.Printf("%sCopied %s to the clipboard via %s.%s\n", colorGreen, what, target, colorReset)
+	return nil
+}
+
+func handlePasteCommand(ctx context.Context, session *ChatSession, args string) error {
+	text, err := readClipboard()
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	if strings.TrimSpace(text) == "" {
+		fmt.Printf("%sThe clipboard is empty.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	if args == "" {
+		session.attachments = append(session.attachments, text)
+		fmt.Printf("%sPasted %d lines (%d tokens) into your next message.%s\n", colorGreen, strings.Count(text, "\n")+1, len(strings.Fields(text)), colorReset)
+		return nil
+	}
+	fmt.Printf("%sYou:%s %s\n%s[clipboard, %d tokens]%s\n", colorUser, colorReset, args, colorDim, len(strings.Fields(text)), colorReset)
+	return sendChatTurn(ctx, session, text+"\n\n"+args, session.model)
+}
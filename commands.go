@@ -0,0 +1,213 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"sort"
+	"strconv"
+	"strings"
+	"unicode"
+)
+
+type ChatCommand struct {
+	Name        string
+	Usage       string
+	Description string
+	Run         func(ctx context.Context, session *ChatSession, args string) error
+}
+
+func chatCommands() []ChatCommand {
+	return []ChatCommand{
+		{Name: "/help", Usage: "/help [command]", Description: "list commands or show usage for one", Run: handleHelpCommand},
+		{Name: "/save", Usage: "/save", Description: "save the conversation to a timestamped file", Run: handleSaveCommand},
+		{Name: "/load", Usage: "/load <filename>", Description: "replace the conversation with a saved one", Run: handleLoadCommand},
+		{Name: "/merge", Usage: "/merge <filename> [separator]", Description: "append messages from a saved conversation", Run: handleMergeCommand},
+		{Name: "/export", Usage: "/export md|json|html <path>", Description: "export the conversation as a readable document", Run: handleExportCommand},
+		{Name: "/export-jsonl", Usage: "/export-jsonl <path>", Description: "append the conversation as a fine-tuning record", Run: handleExportJSONLCommand},
+		{Name: "/checkpoint", Usage: "/checkpoint <name>", Description: "checkpoint the current conversation", Run: handleCheckpointCommand},
+		{Name: "/fork", Usage: "/fork", Description: "continue in a new session with a copy of the history", Run: handleForkCommand},
+		{Name: "/branch", Usage: "/branch [message-index|name]", Description: "continue in a new session from an earlier message, or checkpoint under a name", Run: handleBranchCommand},
+		{Name: "/branches", Usage: "/branches", Description: "show the sessions forked from this conversation", Run: handleBranchesCommand},
+		{Name: "/switch", Usage: "/switch <session-id>", Description: "save this session and continue another one", Run: handleSwitchCommand},
+		{Name: "/restore", Usage: "/restore [name]", Description: "restore a checkpoint or list checkpoints", Run: handleRestoreCommand},
+		{Name: "/undo", Usage: "/undo", Description: "remove the last exchange from the conversation", Run: handleUndoCommand},
+		{Name: "/clear", Usage: "/clear", Description: "clear the conversation and the screen, keeping the system prompt", Run: handleClearCommand},
+		{Name: "/retry", Usage: "/retry [temperature]", Description: "regenerate the last reply, keeping the old one", Run: handleRetryCommand},
+		{Name: "/alternatives", Usage: "/alternatives [n]", Description: "list replies replaced by /retry or switch back to one", Run: handleAlternativesCommand},
+		{Name: "/ask", Usage: "/ask <prompt>", Description: "ask without recording the exchange", Run: handleAskCommand},
+		{Name: "/system", Usage: "/system [text]", Description: "show or replace the system prompt, keeping history", Run: handleSystemCommand},
+		{Name: "/model", Usage: "/model [name]", Description: "show or switch the model for following messages", Run: handleModelCommand},
+		{Name: "/persona", Usage: "/persona [name]", Description: "switch persona or list the configured ones", Run: handlePersonaCommand},
+		{Name: "/set", Usage: "/set <temperature|top_p|max_tokens> <value>", Description: "change a sampling parameter for this session", Run: handleSetCommand},
+		{Name: "/settings", Usage: "/settings", Description: "show the current model and sampling parameters", Run: handleSettingsCommand},
+		{Name: "/attach", Usage: "/attach <path>", Description: "attach a file to your next message", Run: handleAttachCommand},
+		{Name: "/fetch", Usage: "/fetch <url>", Description: "download a web page as text and attach it to your next message", Run: handleFetchCommand},
+		{Name: "/image", Usage: "/image <path-or-url>", Description: "send an image with your next message", Run: handleImageCommand},
+		{Name: "/voice", Usage: "/voice", Description: "record a message from the microphone and send the transcript", Run: handleVoiceCommand},
+		{Name: "/edit", Usage: "/edit [text]", Description: "compose a message in $EDITOR (or press Ctrl+E, Enter)", Run: handleEditCommand},
+		{Name: "/tpl", Usage: "/tpl [name] [--var=value ...]", Description: "send a prompt template or list the saved ones", Run: handleTemplateCommand},
+		{Name: "/edit-last", Usage: "/edit-last [s/old/new/|text]", Description: "edit your last message and send it again", Run: handleEditLastCommand},
+		{Name: "/copy", Usage: "/copy [code]", Description: "copy the last response or its last code block to the clipboard", Run: handleCopyCommand},
+		{Name: "/paste", Usage: "/paste [question]", Description: "add the clipboard to your next message, or send it with a question", Run: handlePasteCommand},
+		{Name: "/apply", Usage: "/apply [path]", Description: "diff a code block from the last response against a file and write it", Run: handleApplyCommand},
+		{Name: "/note", Usage: "/note <text>", Description: "send a system note with the next message only", Run: handleNoteCommand},
+		{Name: "/stream", Usage: "/stream [on|off]", Description: "show or switch the response mode", Run: handleStreamCommand},
+		{Name: "/tokens-of", Usage: "/tokens-of [-v] <text>", Description: "count tokens in a piece of text", Run: handleTokensOfCommand},
+		{Name: "/stats", Usage: "/stats", Description: "show per-model usage for this session", Run: handleStatsCommand},
+		{Name: "/ping", Usage: "/ping", Description: "measure API latency and check authentication", Run: handlePingCommand},
+		{Name: "/limits", Usage: "/limits", Description: "show rate-limit quota reported by the provider", Run: handleLimitsCommand},
+		{Name: "/mcp", Usage: "/mcp [read <server> <uri>]", Description: "list MCP servers and tools, or attach a resource", Run: handleMCPCommand},
+		{Name: "/config", Usage: "/config", Description: "print the effective configuration", Run: handleConfigCommand},
+		{Name: "/reload", Usage: "/reload", Description: "reload the config file and system prompt", Run: handleReloadCommand},
+		{Name: "/override", Usage: "/override", Description: "lift max_requests_per_session for this session", Run: handleOverrideCommand},
+	}
+}
+
+func findCommand(name string) (ChatCommand, bool) {
+	for _, command := range chatCommands() {
+		if command.Name == name {
+			return command, true
+		}
+	}
+	return ChatCommand{}, false
+}
+
+func dispatchCommand(ctx context.Context, session *ChatSession, userInput string) error {
+	name, args := userInput, ""
+	if i := strings.IndexFunc(userInput, unicode.IsSpace); i >= 0 {
+		name, args = userInput[:i], userInput[i:]
+	}
+	command, ok := findCommand(name)
+	if !ok {
+		fmt.Printf("%sUnknown command %s, type /help for a list of commands.%s\n", colorRed, name, colorReset)
+		return nil
+	}
+	return command.Run(ctx, session, strings.TrimSpace(args))
+}
+
+func handleHelpCommand(_ context.Context, _ *ChatSession, args string) error {
+	if args != "" {
+		name := "/" + strings.TrimPrefix(args, "/")
+		command, ok := findCommand(name)
+		if !ok {
+			fmt.Printf("%sUnknown command %s%s\n", colorRed, name, colorReset)
+			return nil
+		}
+		fmt.Printf("%s%s%s\n  %s\n", colorCyan, command.Usage, colorReset, command.Description)
+		return nil
+	}
+
+	fmt.Printf("%sCommands:%s\n", colorCyan, colorReset)
+	for _, command := range chatCommands() {
+		fmt.Printf("  %-32s %s\n", command.Usage, command.Description)
+	}
+	fmt.Printf("  %-32s %s\n", "@@<alias>: <prompt>", "send one message with an aliased model")
+	fmt.Printf("  %-32s %s\n", exitCommand, "quit")
+	return nil
+}
+
+func handleSystemCommand(_ context.Context, session *ChatSession, prompt string) error {
+	if prompt == "" {
+		current := session.conversation.systemPrompt()
+		if current == "" {
+			fmt.Printf("%sNo system prompt set. Usage: /system <text>%s\n", colorYellow, colorReset)
+			return nil
+		}
+		fmt.Printf("%sSystem prompt:%s\n%s\n", colorCyan, colorReset, current)
+		return nil
+	}
+
+	if session.conversation.setSystemPrompt(prompt) {
+		fmt.Printf("%sSystem prompt updated.%s\n", colorGreen, colorReset)
+	} else {
+		fmt.Printf("%sSystem prompt unchanged.%s\n", colorYellow, colorReset)
+	}
+	return nil
+}
+
+func handleModelCommand(_ context.Context, session *ChatSession, model string) error {
+	if model == "" {
+		fmt.Printf("%sCurrent model: %s%s\n", colorCyan, session.model, colorReset)
+		return nil
+	}
+
+	if aliased, ok := session.config.ModelAliases[model]; ok {
+		model = aliased
+	} else if cached := loadModelCache(session.apiClient.Provider.Name()); len(cached) > 0 {
+		completed, candidates := completeModel(model, cached)
+		if len(candidates) > 1 {
+			fmt.Printf("%sAmbiguous model %q, matches: %s%s\n", colorYellow, model, strings.Join(candidates, ", "), colorReset)
+			return nil
+		}
+		if !hasModel(cached, completed) {
+			fmt.Printf("%sWarning: %s is not in the cached model list (run 'models' to refresh)%s\n", colorYellow, model, colorReset)
+		}
+		model = completed
+	}
+	session.model = model
+	fmt.Printf("%sSwitched to model %s%s\n", colorGreen, model, colorReset)
+	return nil
+}
+
+func handleSetCommand(_ context.Context, session *ChatSession, args string) error {
+	fields := strings.Fields(args)
+	if len(fields) != 2 {
+		fmt.Printf("%sUsage: /set <temperature|top_p|max_tokens> <value>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	temperature, topP, maxOutputTokens := session.config.Temperature, session.config.TopP, session.config.MaxTokens
+	var err error
+	switch fields[0] {
+	case "temperature":
+		temperature, err = strconv.ParseFloat(fields[1], 64)
+	case "top_p":
+		topP, err = strconv.ParseFloat(fields[1], 64)
+	case "max_tokens":
+		maxOutputTokens, err = strconv.Atoi(fields[1])
+	default:
+		fmt.Printf("%sUnknown setting %q, expected temperature, top_p or max_tokens%s\n", colorRed, fields[0], colorReset)
+		return nil
+	}
+	if err == nil {
+		err = validateSampling(temperature, topP, maxOutputTokens)
+	}
+	if err != nil {
+		fmt.Printf("%sInvalid value for %s: %v%s\n", colorRed, fields[0], err, colorReset)
+		return nil
+	}
+
+	session.config.Temperature, session.config.TopP, session.config.MaxTokens = temperature, topP, maxOutputTokens
+	fmt.Printf("%s%s set to %s%s\n", colorGreen, fields[0], fields[1], colorReset)
+	return nil
+}
+
+func handleSettingsCommand(_ context.Context, session *ChatSession, _ string) error {
+	fmt.Printf("%sSettings:%s\n", colorCyan, colorReset)
+	fmt.Printf("  model:       %s\n", session.model)
+	fmt.Printf("  temperature: %g\n", session.config.Temperature)
+	fmt.Printf("  top_p:       %g\n", session.config.TopP)
+	fmt.Printf("  max_tokens:  %d\n", session.config.MaxTokens)
+	fmt.Printf("  context:     %d tokens (%d for the prompt)\n", contextWindow(session.config, session.model), promptBudget(session.config, session.model))
+	return nil
+}
+
+func handleLimitsCommand(_ context.Context, session *ChatSession, _ string) error {
+	limits := session.apiClient.rateLimits
+	if len(limits) == 0 {
+		fmt.Printf("%sNo rate-limit information yet, send a message first.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	names := make([]string, 0, len(limits))
+	for name := range limits {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+
+	fmt.Printf("%sRate limits as of %s:%s\n", colorCyan, session.apiClient.limitsAt.Format("15:04:05"), colorReset)
+	for _, name := range names {
+		fmt.Printf("  %-40s %s\n", name, strings.Join(limits[name], ", "))
+	}
+	return nil
+}
@@ -0,0 +1,108 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"os/signal"
+	"strings"
+	"sync"
+	"syscall"
+	"text/tabwriter"
+	"time"
+
+	"golang.org/x/sync/errgroup"
+)
+
+type CompareResult struct {
+	Model            string
+	Response         string
+	Err              error
+	PromptTokens     int
+	CompletionTokens int
+	Elapsed          time.Duration
+}
+
+func runCompare(session *ChatSession, prompt string, models []string) error {
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	session.conversation.addMessage("user", withStoredUserSuffix(session.config, prompt))
+	history := session.conversation.getHistory()
+	results := make([]CompareResult, len(models))
+	fmt.Printf("%sAsking %s...%s\n", colorDim, strings.Join(models, ", "), colorReset)
+
+	var (
+		mu sync.Mutex
+		g  errgroup.Group
+	)
+	for i, model := range models {
+		g.Go(func() error {
+			client, err := newAPIClient(session.config)
+			if err != nil {
+				return err
+			}
+			client.Stream = false
+			client.OnRetry = logRetry
+			result := CompareResult{Model: model, PromptTokens: countTokens(client.prepareHistory(history, model))}
+
+			start := time.Now()
+			completion, err := Ask(ctx, client, history, model)
+			result.Elapsed, result.Err = time.Since(start), err
+			if err == nil {
+				result.Response = session.postProcess(completion.Content)
+				result.CompletionTokens = len(strings.Fields(result.Response))
+			}
+			results[i] = result
+
+			mu.Lock()
+			defer mu.Unlock()
+			printCompareSection(session.config, result)
+			return nil
+		})
+	}
+	g.Wait()
+
+	failed := 0
+	for _, result := range results {
+		if result.Err != nil {
+			failed++
+			continue
+		}
+		session.recordUsage(result.Model, result.PromptTokens, result.CompletionTokens, result.Elapsed)
+	}
+	printCompareSummary(session.config, results)
+	if failed == len(results) {
+		return errors.New("all models failed")
+	}
+	return nil
+}
+
+func printCompareSection(config *Config, result CompareResult) {
+	header := "── " + result.Model + " "
+	fmt.Printf("\n%s%s%s%s\n", colorCyan, header, strings.Repeat("─", max(0, defaultBoxWidth-len([]rune(header)))), colorReset)
+	if result.Err != nil {
+		fmt.Printf("%sFailed: %v%s\n", colorRed, result.Err, colorReset)
+		return
+	}
+	fmt.Println(renderForDisplay(config, result.Response))
+}
+
+func printCompareSummary(config *Config, results []CompareResult) {
+	fmt.Printf("\n%sSummary:%s\n", colorCyan, colorReset)
+	writer := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(writer, "MODEL\tLATENCY\tPROMPT\tCOMPLETION\tTOK/S\tCOST\tSTATUS")
+	for _, result := range results {
+		status, speed := "ok", 0.0
+		if result.Err != nil {
+			status = "failed"
+		}
+		if seconds := result.Elapsed.Seconds(); seconds > 0 {
+			speed = float64(result.CompletionTokens) / seconds
+		}
+		fmt.Fprintf(writer, "%s\t%.1fs\t%d\t%d\t%.0f\t%s\t%s\n", result.Model, result.Elapsed.Seconds(), result.PromptTokens, result.CompletionTokens, speed,
+			formatCost(estimateCost(config, result.Model, result.PromptTokens, result.CompletionTokens)), status)
+	}
+	writer.Flush()
+}
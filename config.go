@@ -0,0 +1,184 @@
+package main
+
+import (
+	"bytes"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"reflect"
+	"slices"
+	"strings"
+
+	"gopkg.in/yaml.v3"
+)
+
+const configTemplate = `# aili configuration, see "aili config set <key> <value>" to change a setting.
+provider: groq
+groq_api_key: ""
+openai_api_key: ""
+anthropic_api_key: ""
+ollama_base_url: http://localhost:11434
+ollama_model: llama3.1
+temperature: 0.7
+top_p: 0.9
+render_markdown: true
+highlight_code: true
+usage_footer: true
+`
+
+func userConfigPath() (string, error) {
+	if configHome := os.Getenv("XDG_CONFIG_HOME"); configHome != "" {
+		return filepath.Join(configHome, "aili", defaultConfigFile), nil
+	}
+	home, err := os.UserHomeDir()
+	if err != nil {
+		return "", fmt.Errorf("failed to locate home directory: %w", err)
+	}
+	return filepath.Join(home, ".config", "aili", defaultConfigFile), nil
+}
+
+func resolveConfigPath(explicit string) (string, error) {
+	if explicit != "" {
+		return explicit, nil
+	}
+	if _, err := os.Stat(defaultConfigFile); err == nil {
+		return defaultConfigFile, nil
+	}
+	return userConfigPath()
+}
+
+func configKeys() []string {
+	var keys []string
+	configType := reflect.TypeOf(Config{})
+	for i := 0; i < configType.NumField(); i++ {
+		name, _, _ := strings.Cut(configType.Field(i).Tag.Get("yaml"), ",")
+		if name != "" && name != "-" {
+			keys = append(keys, name)
+		}
+	}
+	return keys
+}
+
+func runConfigCommand(path string, args []string) error {
+	switch args[0] {
+	case "path":
+		if len(args) != 1 {
+			return errors.New("usage: config path")
+		}
+		fmt.Println(path)
+		if _, err := os.Stat(path); errors.Is(err, os.ErrNotExist) {
+			fmt.Fprintf(os.Stderr, "%s does not exist yet, create it with the config init subcommand\n", path)
+		}
+		return nil
+	case "init":
+		if len(args) != 1 {
+			return errors.New("usage: config init")
+		}
+		return initConfig(path)
+	case "set":
+		if len(args) != 3 {
+			return errors.New("usage: config set <key> <value>")
+		}
+		return setConfigValue(path, args[1], args[2])
+	case "set-key":
+		if len(args) != 2 {
+			return fmt.Errorf("usage: config set-key <%s>", strings.Join(keyringNames, "|"))
+		}
+		return setKeyringSecret(args[1])
+	}
+	return fmt.Errorf("unknown config subcommand %q, expected show, path, init, set or set-key", args[0])
+}
+
+func initConfig(path string) error {
+	if _, err := os.Stat(path); err == nil {
+		return fmt.Errorf("%s already exists", path)
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0700); err != nil {
+		return fmt.Errorf("failed to create config directory: %w", err)
+	}
+	if err := os.WriteFile(path, []byte(configTemplate), 0600); err != nil {
+		return fmt.Errorf("failed to write config file: %w", err)
+	}
+	fmt.Printf("Created %s, store your API key in the keyring with: config set-key groq\n", path)
+	return nil
+}
+
+func setConfigValue(path, key, value string) error {
+	keys := strings.Split(key, ".")
+	if !slices.Contains(configKeys(), keys[0]) {
+		return fmt.Errorf("unknown config key %q", keys[0])
+	}
+
+	original, err := os.ReadFile(path)
+	if err != nil && !errors.Is(err, os.ErrNotExist) {
+		return fmt.Errorf("failed to read config file: %w", err)
+	}
+	var document yaml.Node
+	if err := yaml.Unmarshal(original, &document); err != nil {
+		return fmt.Errorf("failed to parse config file: %w", err)
+	}
+	if document.Kind == 0 {
+		document = yaml.Node{Kind: yaml.DocumentNode, Content: []*yaml.Node{{Kind: yaml.MappingNode}}}
+	}
+	var parsed yaml.Node
+	if err := yaml.Unmarshal([]byte(value), &parsed); err != nil || len(parsed.Content) == 0 {
+		parsed = yaml.Node{Content: []*yaml.Node{{Kind: yaml.ScalarNode, Tag: "!!str", Value: value}}}
+	}
+	if err := setYAMLPath(document.Content[0], keys, parsed.Content[0]); err != nil {
+		return err
+	}
+
+	var out bytes.Buffer
+	encoder := yaml.NewEncoder(&out)
+	encoder.SetIndent(2)
+	if err := encoder.Encode(&document); err != nil {
+		return fmt.Errorf("failed to encode config file: %w", err)
+	}
+	var check Config
+	if err := yaml.Unmarshal(out.Bytes(), &check); err != nil {
+		return fmt.Errorf("invalid value for %s: %w", key, err)
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0700); err != nil {
+		return fmt.Errorf("failed to create config directory: %w", err)
+	}
+	if err := os.WriteFile(path, out.Bytes(), 0600); err != nil {
+		return fmt.Errorf("failed to write config file: %w", err)
+	}
+
+	if strings.HasSuffix(keys[len(keys)-1], "api_key") {
+		value = redactSecret(value)
+	}
+	fmt.Printf("Set %s to %s in %s\n", key, value, path)
+	if _, err := loadConfig(path); err != nil {
+		fmt.Fprintf(os.Stderr, "%sWarning: %v%s\n", colorYellow, err, colorReset)
+	}
+	return nil
+}
+
+func setYAMLPath(node *yaml.Node, keys []string, value *yaml.Node) error {
+	if node.Kind != yaml.MappingNode {
+		return fmt.Errorf("cannot set %s inside a %s value", keys[0], node.ShortTag())
+	}
+	for i := 0; i+1 < len(node.Content); i += 2 {
+		if node.Content[i].Value != keys[0] {
+			continue
+		}
+		if len(keys) == 1 {
+			value.HeadComment, value.LineComment = node.Content[i+1].HeadComment, node.Content[i+1].LineComment
+			node.Content[i+1] = value
+			return nil
+		}
+		return setYAMLPath(node.Content[i+1], keys[1:], value)
+	}
+
+	child := value
+	if len(keys) > 1 {
+		child = &yaml.Node{Kind: yaml.MappingNode}
+		if err := setYAMLPath(child, keys[1:], value); err != nil {
+			return err
+		}
+	}
+	node.Content = append(node.Content, &yaml.Node{Kind: yaml.ScalarNode, Value: keys[0]}, child)
+	return nil
+}
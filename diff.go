@@ -0,0 +1,153 @@
+package main
+
+import (
+	"fmt"
+	"strings"
+)
+
+const (
+	diffContextLines = 3
+	maxDiffCells     = 4_000_000
+)
+
+type diffLine struct {
+	kind byte
+	text string
+}
+
+func splitLines(text string) []string {
+	if text == "" {
+		return nil
+	}
+	return strings.Split(strings.TrimSuffix(text, "\n"), "\n")
+}
+
+func diffLines(old, updated []string) []diffLine {
+	prefix := 0
+	for prefix < len(old) && prefix < len(updated) && old[prefix] == updated[prefix] {
+		prefix++
+	}
+	suffix := 0
+	for suffix < len(old)-prefix && suffix < len(updated)-prefix && old[len(old)-1-suffix] == updated[len(updated)-1-suffix] {
+		suffix++
+	}
+
+	lines := make([]diffLine, 0, len(old)+len(updated))
+	for _, line := range old[:prefix] {
+		lines = append(lines, diffLine{' ', line})
+	}
+	lines = append(lines, diffMiddle(old[prefix:len(old)-suffix], updated[prefix:len(updated)-suffix])...)
+	for _, line := range old[len(old)-suffix:] {
+		lines = append(lines, diffLine{' ', line})
+	}
+	return lines
+}
+
+func diffMiddle(old, updated []string) []diffLine {
+	var lines []diffLine
+	if len(old)*len(updated) > maxDiffCells {
+		for _, line := range old {
+			lines = append(lines, diffLine{'-', line})
+		}
+		for _, line := range updated {
+			lines = append(lines, diffLine{'+', line})
+		}
+		return lines
+	}
+
+	lcs := make([][]int, len(old)+1)
+	for i := range lcs {
+		lcs[i] = make([]int, len(updated)+1)
+	}
+	for i := len(old) - 1; i >= 0; i-- {
+		for j := len(updated) - 1; j >= 0; j-- {
+			if old[i] == updated[j] {
+				lcs[i][j] = lcs[i+1][j+1] + 1
+			} else {
+				lcs[i][j] = max(lcs[i+1][j], lcs[i][j+1])
+			}
+		}
+	}
+
+	i, j := 0, 0
+	for i < len(old) || j < len(updated) {
+		switch {
+		case i < len(old) && j < len(updated) && old[i] == updated[j]:
+			lines = append(lines, diffLine{' ', old[i]})
+			i, j = i+1, j+1
+		case i < len(old) && (j == len(updated) || lcs[i+1][j] >= lcs[i][j+1]):
+			lines = append(lines, diffLine{'-', old[i]})
+			i++
+		default:
+			lines = append(lines, diffLine{'+', updated[j]})
+			j++
+		}
+	}
+	return lines
+}
+
+func printDiff(path string, lines []diffLine) int {
+	changes := 0
+	for _, line := range lines {
+		if line.kind != ' ' {
+			changes++
+		}
+	}
+	if changes == 0 {
+		return 0
+	}
+
+	fmt.Printf("%s--- %s%s\n%s+++ %s%s\n", colorBold, path, colorReset, colorBold, path, colorReset)
+	oldLine, newLine := 1, 1
+	for start := 0; start < len(lines); {
+		if lines[start].kind == ' ' {
+			start, oldLine, newLine = start+1, oldLine+1, newLine+1
+			continue
+		}
+
+		from := max(start-diffContextLines, 0)
+		end, quiet := start, 0
+		for end < len(lines) && quiet <= 2*diffContextLines {
+			if lines[end].kind == ' ' {
+				quiet++
+			} else {
+				quiet = 0
+			}
+			end++
+		}
+		end = min(end-quiet+diffContextLines, len(lines))
+
+		oldStart, newStart := oldLine-(start-from), newLine-(start-from)
+		oldCount, newCount := 0, 0
+		for _, line := range lines[from:end] {
+			if line.kind != '+' {
+				oldCount++
+			}
+			if line.kind != '-' {
+				newCount++
+			}
+		}
+		fmt.Printf("%s@@ -%d,%d +%d,%d @@%s\n", colorCyan, oldStart, oldCount, newStart, newCount, colorReset)
+		for _, line := range lines[from:end] {
+			switch line.kind {
+			case '-':
+				fmt.Printf("%s-%s%s\n", colorRed, line.text, colorReset)
+			case '+':
+				fmt.Printf("%s+%s%s\n", colorGreen, line.text, colorReset)
+			default:
+				fmt.Printf(" %s\n", line.text)
+			}
+		}
+
+		for _, line := range lines[start:end] {
+			if line.kind != '+' {
+				oldLine++
+			}
+			if line.kind != '-' {
+				newLine++
+			}
+		}
+		start = end
+	}
+	return changes
+}
@@ -0,0 +1,148 @@
+package main
+
+import (
+	"archive/zip"
+	"bytes"
+	"encoding/csv"
+	"encoding/xml"
+	"errors"
+	"fmt"
+	"io"
+	"os/exec"
+	"path/filepath"
+	"strings"
+)
+
+const maxCSVRows = 5000
+
+var documentExtractors = map[string]func(path string) (string, error){
+	".pdf":  extractPDF,
+	".docx": extractDocx,
+	".csv":  func(path string) (string, error) { return extractDelimited(path, ',') },
+	".tsv":  func(path string) (string, error) { return extractDelimited(path, '\t') },
+}
+
+func documentExtractor(path string) (func(path string) (string, error), bool) {
+	extract, ok := documentExtractors[strings.ToLower(filepath.Ext(path))]
+	return extract, ok
+}
+
+func extractPDF(path string) (string, error) {
+	if _, err := exec.LookPath("pdftotext"); err != nil {
+		return "", fmt.Errorf("attaching %s needs pdftotext (poppler-utils) on PATH", path)
+	}
+	var stdout, stderr bytes.Buffer
+	cmd := exec.Command("pdftotext", "-layout", "-enc", "UTF-8", path, "-")
+	cmd.Stdout, cmd.Stderr = &stdout, &stderr
+	if err := cmd.Run(); err != nil {
+		return "", fmt.Errorf("failed to extract text from %s: %v: %s", path, err, strings.TrimSpace(stderr.String()))
+	}
+	text := strings.TrimSpace(strings.ReplaceAll(stdout.String(), "\f", "\n\n"))
+	if text == "" {
+		return "", fmt.Errorf("%s has no extractable text, it may be a scanned document", path)
+	}
+	return text, nil
+}
+
+func extractDocx(path string) (string, error) {
+	archive, err := zip.OpenReader(path)
+	if err != nil {
+		return "", fmt.Errorf("failed to open %s: %w", path, err)
+	}
+	defer archive.Close()
+
+	for _, file := range archive.File {
+		if file.Name != "word/document.xml" {
+			continue
+		}
+		reader, err := file.Open()
+		if err != nil {
+			return "", fmt.Errorf("failed to read %s: %w", path, err)
+		}
+		defer reader.Close()
+		text, err := wordDocumentText(reader)
+		if err != nil {
+			return "", fmt.Errorf("failed to parse %s: %w", path, err)
+		}
+		return text, nil
+	}
+	return "", fmt.Errorf("%s is not a Word document", path)
+}
+
+func wordDocumentText(r io.Reader) (string, error) {
+	var b strings.Builder
+	decoder := xml.NewDecoder(r)
+	inText := false
+	for {
+		token, err := decoder.Token()
+		if errors.Is(err, io.EOF) {
+			break
+		}
+		if err != nil {
+			return "", err
+		}
+		switch t := token.(type) {
+		case xml.StartElement:
+			switch t.Name.Local {
+			case "t":
+				inText = true
+			case "tab":
+				b.WriteByte('\t')
+			case "br", "cr":
+				b.WriteByte('\n')
+			}
+		case xml.EndElement:
+			switch t.Name.Local {
+			case "t":
+				inText = false
+			case "p":
+				b.WriteByte('\n')
+			case "tc":
+				b.WriteString(" | ")
+			}
+		case xml.CharData:
+			if inText {
+				b.Write(t)
+			}
+		}
+	}
+	return strings.TrimSpace(blankRunPattern.ReplaceAllString(b.String(), "\n\n")), nil
+}
+
+func extractDelimited(path string, delimiter rune) (string, error) {
+	content, err := readTextFile(path)
+	if err != nil {
+		return "", err
+	}
+	reader := csv.NewReader(strings.NewReader(content))
+	reader.Comma = delimiter
+	reader.FieldsPerRecord = -1
+	reader.LazyQuotes = true
+	records, err := reader.ReadAll()
+	if err != nil {
+		return "", fmt.Errorf("failed to parse %s: %w", path, err)
+	}
+	if len(records) == 0 {
+		return "", fmt.Errorf("%s is empty", path)
+	}
+
+	var b strings.Builder
+	columns := len(records[0])
+	for i, record := range records {
+		if i > maxCSVRows {
+			fmt.Fprintf(&b, "\n(%d more rows omitted)", len(records)-i)
+			break
+		}
+		cells := make([]string, columns)
+		for j := range cells {
+			if j < len(record) {
+				cells[j] = strings.ReplaceAll(strings.TrimSpace(record[j]), "|", `\|`)
+			}
+		}
+		fmt.Fprintf(&b, "| %s |\n", strings.Join(cells, " | "))
+		if i == 0 {
+			fmt.Fprintf(&b, "|%s\n", strings.Repeat(" --- |", columns))
+		}
+	}
+	return strings.TrimRight(b.String(), "\n"), nil
+}
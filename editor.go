@@ -0,0 +1,131 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"os/exec"
+	"strings"
+)
+
+const (
+	editorKey      = "\x05"
+	defaultEditor  = "vi"
+	editorTempName = "aili-prompt-*.md"
+)
+
+func editorCommand() []string {
+	for _, name := range []string{"VISUAL", "EDITOR"} {
+		if fields := strings.Fields(os.Getenv(name)); len(fields) > 0 {
+			return fields
+		}
+	}
+	return []string{defaultEditor}
+}
+
+func composeInEditor(initial string) (string, error) {
+	file, err := os.CreateTemp("", editorTempName)
+	if err != nil {
+		return "", fmt.Errorf("failed to create temp file: %w", err)
+	}
+	path := file.Name()
+	defer os.Remove(path)
+
+	if _, err := file.WriteString(initial); err != nil {
+		file.Close()
+		return "", fmt.Errorf("failed to write temp file: %w", err)
+	}
+	if err := file.Close(); err != nil {
+		return "", fmt.Errorf("failed to write temp file: %w", err)
+	}
+
+	fields := editorCommand()
+	cmd := exec.Command(fields[0], append(fields[1:], path)...)
+	cmd.Stdin, cmd.Stdout, cmd.Stderr = os.Stdin, os.Stdout, os.Stderr
+	if err := cmd.Run(); err != nil {
+		return "", fmt.Errorf("failed to run editor %s: %w", fields[0], err)
+	}
+
+	content, err := os.ReadFile(path)
+	if err != nil {
+		return "", fmt.Errorf("failed to read temp file: %w", err)
+	}
+	return strings.TrimSpace(string(content)), nil
+}
+
+func handleEditCommand(ctx context.Context, session *ChatSession, initial string) error {
+	prompt, err := composeInEditor(initial)
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	if prompt == "" {
+		fmt.Printf("%sEmpty message, nothing sent.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	fmt.Printf("%sYou:%s %s\n", colorUser, colorReset, prompt)
+	return sendChatTurn(ctx, session, prompt, session.model)
+}
+
+func (c *Conversation) lastUserMessage() (Message, bool) {
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	for i := len(c.History) - 1; i >= 0; i-- {
+		if c.History[i].Role == "user" {
+			return c.History[i], true
+		}
+	}
+	return Message{}, false
+}
+
+func substitute(text, expr string) (string, bool) {
+	if len(expr) < 4 || expr[0] != 's' {
+		return "", false
+	}
+	parts := strings.Split(expr[2:], expr[1:2])
+	if len(parts) != 3 || parts[0] == "" || parts[2] != "" || !strings.Contains(text, parts[0]) {
+		return "", false
+	}
+	return strings.Replace(text, parts[0], parts[1], 1), true
+}
+
+func handleEditLastCommand(ctx context.Context, session *ChatSession, args string) error {
+	last, ok := session.conversation.lastUserMessage()
+	if !ok {
+		fmt.Printf("%sNo message to edit yet.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	original := last.Content
+	if session.config.StoreUserSuffix && session.config.UserSuffix != "" {
+		original = strings.TrimSuffix(original, "\n\n"+session.config.UserSuffix)
+	}
+
+	var prompt string
+	switch {
+	case args == "":
+		edited, err := composeInEditor(original)
+		if err != nil {
+			fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+			return nil
+		}
+		prompt = edited
+	case strings.HasPrefix(args, "s/"):
+		edited, ok := substitute(original, args)
+		if !ok {
+			fmt.Printf("%sNo match for %s in the last message.%s\n", colorRed, args, colorReset)
+			return nil
+		}
+		prompt = edited
+	default:
+		prompt = args
+	}
+	if prompt == "" || prompt == original {
+		fmt.Printf("%sMessage unchanged, nothing sent.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	session.conversation.dropLastExchange(false)
+	session.images = last.Images
+	fmt.Printf("%sYou:%s %s\n", colorUser, colorReset, prompt)
+	return sendChatTurn(ctx, session, prompt, session.model)
+}
@@ -0,0 +1,165 @@
+package main
+
+import (
+	"bufio"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"reflect"
+	"strconv"
+	"strings"
+
+	"gopkg.in/yaml.v3"
+)
+
+const (
+	envPrefix   = "AILI_"
+	envModelVar = "AILI_MODEL"
+	dotEnvFile  = ".env"
+)
+
+var envAliases = map[string]string{
+	"GROQ_API_KEY":      "groq_api_key",
+	"OPENAI_API_KEY":    "openai_api_key",
+	"ANTHROPIC_API_KEY": "anthropic_api_key",
+}
+
+var dotEnvSources = map[string]string{}
+
+func loadDotEnv(path string) error {
+	file, err := os.Open(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return nil
+	}
+	if err != nil {
+		return fmt.Errorf("failed to read %s: %w", path, err)
+	}
+	defer file.Close()
+
+	scanner := bufio.NewScanner(file)
+	for number := 1; scanner.Scan(); number++ {
+		line := strings.TrimSpace(scanner.Text())
+		if line == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+		name, value, ok := strings.Cut(strings.TrimPrefix(line, "export "), "=")
+		name = strings.TrimSpace(name)
+		if !ok || name == "" {
+			return fmt.Errorf("%s:%d: expected NAME=value", path, number)
+		}
+		value = strings.TrimSpace(value)
+		if unquoted, err := strconv.Unquote(value); err == nil && strings.HasPrefix(value, `"`) {
+			value = unquoted
+		} else if len(value) >= 2 && value[0] == '\'' && value[len(value)-1] == '\'' {
+			value = value[1 : len(value)-1]
+		} else if comment := strings.Index(value, " #"); comment >= 0 {
+			value = strings.TrimSpace(value[:comment])
+		}
+		if _, set := os.LookupEnv(name); set {
+			continue
+		}
+		os.Setenv(name, value)
+		dotEnvSources[name] = path
+	}
+	return scanner.Err()
+}
+
+func loadDotEnvFiles(configPath string) error {
+	paths := []string{dotEnvFile}
+	if dir := filepath.Dir(configPath); dir != "." {
+		paths = append(paths, filepath.Join(dir, dotEnvFile))
+	}
+	for _, path := range paths {
+		if err := loadDotEnv(path); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func envSource(name string) string {
+	if path, ok := dotEnvSources[name]; ok {
+		return fmt.Sprintf("%s from %s", name, path)
+	}
+	return "environment " + name
+}
+
+func fileConfigKeys(data []byte) []string {
+	var document map[string]yaml.Node
+	if yaml.Unmarshal(data, &document) != nil {
+		return nil
+	}
+	keys := make([]string, 0, len(document))
+	for key := range document {
+		keys = append(keys, key)
+	}
+	return keys
+}
+
+func (c *Config) applyEnvironment() error {
+	names := make(map[string]string)
+	for name, key := range envAliases {
+		names[key] = name
+	}
+
+	value := reflect.ValueOf(c).Elem()
+	for i := 0; i < value.NumField(); i++ {
+		key, _, _ := strings.Cut(value.Type().Field(i).Tag.Get("yaml"), ",")
+		if key == "" || key == "-" {
+			continue
+		}
+		name := envPrefix + strings.ToUpper(key)
+		raw, ok := os.LookupEnv(name)
+		if alias, hasAlias := names[key]; !ok && hasAlias {
+			name = alias
+			raw, ok = os.LookupEnv(alias)
+		}
+		if !ok {
+			continue
+		}
+		if err := setFromEnv(value.Field(i), raw); err != nil {
+			return fmt.Errorf("invalid %s: %w", name, err)
+		}
+		c.sources[key] = envSource(name)
+	}
+	return nil
+}
+
+func setFromEnv(field reflect.Value, raw string) error {
+	switch field.Kind() {
+	case reflect.String:
+		field.SetString(raw)
+	case reflect.Bool:
+		parsed, err := strconv.ParseBool(raw)
+		if err != nil {
+			return err
+		}
+		field.SetBool(parsed)
+	case reflect.Int:
+		parsed, err := strconv.Atoi(raw)
+		if err != nil {
+			return err
+		}
+		field.SetInt(int64(parsed))
+	case reflect.Float64:
+		parsed, err := strconv.ParseFloat(raw, 64)
+		if err != nil {
+			return err
+		}
+		field.SetFloat(parsed)
+	default:
+		return yaml.Unmarshal([]byte(raw), field.Addr().Interface())
+	}
+	return nil
+}
+
+func printConfigSources(config *Config, path string) {
+	fmt.Printf("%sSources:%s\n", colorCyan, colorReset)
+	for _, key := range append([]string{"model"}, configKeys()...) {
+		if source, ok := config.sources[key]; ok {
+			fmt.Printf("  %s: %s\n", key, source)
+		}
+	}
+	fmt.Printf("%sOther settings use built-in defaults. Precedence: flags, keyring, environment, %s, %s, defaults.%s\n", colorDim, dotEnvFile, path, colorReset)
+}
@@ -0,0 +1,33 @@
+package main
+
+import (
+	"errors"
+	"fmt"
+	"net/http"
+)
+
+func errorHint(e *APIError) string {
+	switch {
+	case e.StatusCode == http.StatusUnauthorized || e.StatusCode == http.StatusForbidden:
+		return "Check the API key for the configured provider in the config file."
+	case e.IsModelUnavailable():
+		return "Run the models subcommand to list available models, then switch with /model."
+	case e.IsContextTooLong():
+		return "The conversation is too long for this model; lower max_tokens or start a new conversation."
+	case e.StatusCode == http.StatusTooManyRequests:
+		return "You are being rate limited; /limits shows the remaining quota."
+	case e.StatusCode >= 500:
+		return "The provider is having trouble; try again shortly or set fallback_models."
+	}
+	return ""
+}
+
+func printResponseError(err error) {
+	fmt.Printf("%sFailed to get AI response: %v%s\n", colorRed, err, colorReset)
+	var apiErr *APIError
+	if errors.As(err, &apiErr) {
+		if hint := errorHint(apiErr); hint != "" {
+			fmt.Printf("%s%s%s\n", colorYellow, hint, colorReset)
+		}
+	}
+}
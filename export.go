@@ -0,0 +1,215 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"html"
+	"os"
+	"strings"
+)
+
+const exportTimeFormat = "2006-01-02 15:04"
+
+const htmlExportHeader = `<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>%s</title>
+<style>
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+.message { border-left: 4px solid #ccc; margin: 1.5rem 0; padding: 0 1rem; }
+.user { border-color: #3a7; }
+.assistant { border-color: #86c; }
+.system { border-color: #999; }
+.meta { color: #777; font-size: 0.85rem; }
+.text { white-space: pre-wrap; }
+pre { background: #f6f8fa; padding: 0.75rem; overflow-x: auto; }
+.keyword { color: #0550ae; }
+.string { color: #0a3069; }
+.number { color: #8250df; }
+.comment { color: #6e7781; font-style: italic; }
+</style>
+</head>
+<body>
+<h1>%s</h1>
+`
+
+var exportFormats = map[string]func(history []Message) ([]byte, error){
+	"md":   exportMarkdown,
+	"json": exportJSON,
+	"html": exportHTML,
+}
+
+func exportConversation(history []Message, format, path string) error {
+	export, ok := exportFormats[format]
+	if !ok {
+		return fmt.Errorf("unknown export format %q, expected md, json or html", format)
+	}
+	data, err := export(history)
+	if err != nil {
+		return err
+	}
+	if err := os.WriteFile(path, data, 0644); err != nil {
+		return fmt.Errorf("failed to write export file: %w", err)
+	}
+	return nil
+}
+
+func roleLabel(role string) string {
+	switch role {
+	case "assistant":
+		return "AI"
+	case "":
+		return ""
+	}
+	return strings.ToUpper(role[:1]) + role[1:]
+}
+
+func messageMeta(msg Message) string {
+	var parts []string
+	if !msg.Timestamp.IsZero() {
+		parts = append(parts, msg.Timestamp.Format(exportTimeFormat))
+	}
+	if msg.Model != "" {
+		parts = append(parts, msg.Model)
+	}
+	if msg.Cancelled {
+		parts = append(parts, "cancelled")
+	}
+	return strings.Join(parts, " · ")
+}
+
+func exportMarkdown(history []Message) ([]byte, error) {
+	var out strings.Builder
+	fmt.Fprintf(&out, "# %s\n", sessionTitle(history))
+	for _, msg := range history {
+		fmt.Fprintf(&out, "\n## %s\n\n", roleLabel(msg.Role))
+		if meta := messageMeta(msg); meta != "" {
+			fmt.Fprintf(&out, "_%s_\n\n", meta)
+		}
+		out.WriteString(strings.TrimSpace(msg.Content) + "\n")
+	}
+	return []byte(out.String()), nil
+}
+
+func exportJSON(history []Message) ([]byte, error) {
+	data, err := json.MarshalIndent(history, "", "  ")
+	if err != nil {
+		return nil, fmt.Errorf("failed to marshal conversation: %w", err)
+	}
+	return append(data, '\n'), nil
+}
+
+func exportHTML(history []Message) ([]byte, error) {
+	var out strings.Builder
+	title := html.EscapeString(sessionTitle(history))
+	fmt.Fprintf(&out, htmlExportHeader, title, title)
+	for _, msg := range history {
+		fmt.Fprintf(&out, "<div class=\"message %s\">\n<h3>%s</h3>\n", html.EscapeString(msg.Role), html.EscapeString(roleLabel(msg.Role)))
+		if meta := messageMeta(msg); meta != "" {
+			fmt.Fprintf(&out, "<div class=\"meta\">%s</div>\n", html.EscapeString(meta))
+		}
+		out.WriteString(renderHTMLContent(msg.Content))
+		out.WriteString("</div>\n")
+	}
+	out.WriteString("</body>\n</html>\n")
+	return []byte(out.String()), nil
+}
+
+func renderHTMLContent(content string) string {
+	var out, text strings.Builder
+	flushText := func() {
+		if trimmed := strings.TrimSpace(text.String()); trimmed != "" {
+			fmt.Fprintf(&out, "<div class=\"text\">%s</div>\n", html.EscapeString(trimmed))
+		}
+		text.Reset()
+	}
+
+	var keywords map[string]bool
+	comment := ""
+	inCodeBlock := false
+	for _, line := range strings.Split(content, "\n") {
+		trimmed := strings.TrimSpace(line)
+		if strings.HasPrefix(trimmed, "```") {
+			if inCodeBlock {
+				out.WriteString("</code></pre>\n")
+				inCodeBlock = false
+				continue
+			}
+			flushText()
+			language := strings.ToLower(strings.TrimSpace(strings.TrimPrefix(trimmed, "```")))
+			if alias, ok := languageAliases[language]; ok {
+				language = alias
+			}
+			keywords, comment = codeLanguage(language)
+			out.WriteString("<pre><code>")
+			inCodeBlock = true
+			continue
+		}
+		if !inCodeBlock {
+			text.WriteString(line + "\n")
+			continue
+		}
+		out.WriteString(styleCodeLine(line, keywords, comment, htmlCodeStyle) + "\n")
+	}
+	if inCodeBlock {
+		out.WriteString("</code></pre>\n")
+	}
+	flushText()
+	return out.String()
+}
+
+func htmlCodeStyle(class, text string) string {
+	if class == "" {
+		return html.EscapeString(text)
+	}
+	return fmt.Sprintf("<span class=\"%s\">%s</span>", class, html.EscapeString(text))
+}
+
+func handleExportCommand(_ context.Context, session *ChatSession, args string) error {
+	fields := strings.Fields(args)
+	if len(fields) != 2 {
+		fmt.Printf("%sUsage: /export md|json|html <path>%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if err := exportConversation(session.conversation.getHistory(), fields[0], fields[1]); err != nil {
+		fmt.Printf("%sError exporting conversation: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	fmt.Printf("%sConversation exported to %s%s\n", colorGreen, fields[1], colorReset)
+	return nil
+}
+
+func exportSession(id, format, path string) error {
+	sessionFile, err := sessionPath(id)
+	if err != nil {
+		return err
+	}
+	conversation, _, err := loadConversation(sessionFile)
+	if err != nil {
+		return err
+	}
+	if path == "" {
+		path = id + "." + format
+	}
+	if err := exportConversation(conversation.History, format, path); err != nil {
+		return err
+	}
+	fmt.Printf("Exported session %s to %s\n", id, path)
+	return nil
+}
+
+func importTranscript(session *ChatSession, path string) error {
+	imported, settings, err := loadConversation(path)
+	if err != nil {
+		return fmt.Errorf("failed to load transcript %s: %w", path, err)
+	}
+	session.conversation.replaceWith(imported)
+	if settings != nil && !session.ignoreSettings {
+		session.applySettings(settings)
+	}
+	fmt.Printf("%sLoaded transcript %s%s\n", colorGreen, path, colorReset)
+	printConversationSummary(session.conversation)
+	return nil
+}
@@ -0,0 +1,156 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"os/exec"
+	"os/signal"
+	"strings"
+	"syscall"
+	"time"
+)
+
+const (
+	diffReserveTokens = 500
+	commitMsgPrompt   = "You write git commit messages in the Conventional Commits format: type(scope): summary, where type is one of feat, fix, docs, style, refactor, perf, test, build, ci or chore. Keep the summary line under 72 characters and in the imperative mood. When the reason for the change is not obvious, add a blank line and a short body explaining what changed and why. Reply with the commit message only, without code fences."
+	diffSummaryPrompt = "You summarize one part of a larger git diff. List the changes as terse bullet points that name the files and the behavior that changed. Reply with the bullet points only."
+	reviewPrompt      = "You are a careful code reviewer. Review the diff for bugs, security issues, race conditions, missing error handling and confusing code. Report each finding as `file:line - severity - problem` followed by a suggested fix, most severe first. Skip praise and formatting nits. If nothing needs to change, say so in one sentence."
+)
+
+func runGit(args ...string) (string, error) {
+	var stdout, stderr bytes.Buffer
+	cmd := exec.Command("git", args...)
+	cmd.Stdout, cmd.Stderr = &stdout, &stderr
+	if err := cmd.Run(); err != nil {
+		if message := strings.TrimSpace(stderr.String()); message != "" {
+			return "", fmt.Errorf("git %s failed: %s", args[0], message)
+		}
+		return "", fmt.Errorf("failed to run git %s: %w", args[0], err)
+	}
+	return stdout.String(), nil
+}
+
+func splitDiff(diff string, budget int) []string {
+	var files []string
+	for _, section := range strings.Split(diff, "\ndiff --git ") {
+		if len(files) > 0 {
+			section = "diff --git " + section
+		}
+		if strings.TrimSpace(section) == "" {
+			continue
+		}
+		if len(strings.Fields(section)) > budget {
+			files = append(files, chunkByTokens(section, budget)...)
+			continue
+		}
+		files = append(files, section)
+	}
+
+	var chunks []string
+	current, tokens := "", 0
+	for _, file := range files {
+		fileTokens := len(strings.Fields(file))
+		if tokens+fileTokens > budget && current != "" {
+			chunks = append(chunks, current)
+			current, tokens = "", 0
+		}
+		current += strings.TrimRight(file, "\n") + "\n"
+		tokens += fileTokens
+	}
+	if current != "" {
+		chunks = append(chunks, current)
+	}
+	return chunks
+}
+
+func (s *ChatSession) diffBudget(systemPrompt string) int {
+	return max(promptBudget(s.config, s.model)-len(strings.Fields(systemPrompt))-diffReserveTokens, minPromptBudget)
+}
+
+func (s *ChatSession) askGit(ctx context.Context, systemPrompt, content string) (string, error) {
+	history := []Message{
+		{Role: "system", Content: systemPrompt, Timestamp: time.Now()},
+		{Role: "user", Content: content, Timestamp: time.Now()},
+	}
+	promptTokens := countTokens(s.apiClient.prepareHistory(history, s.model))
+	start := time.Now()
+	completion, model, _, err := s.askWithFallback(ctx, history, s.model)
+	if err != nil {
+		return "", fmt.Errorf("failed to get AI response: %w", err)
+	}
+	reply := strings.TrimSpace(s.postProcess(completion.Content))
+	s.recordUsage(model, promptTokens, len(strings.Fields(reply)), time.Since(start))
+	return reply, nil
+}
+
+func runCommitMsg(session *ChatSession) error {
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	diff, err := runGit("diff", "--cached", "--no-color", "--no-ext-diff")
+	if err != nil {
+		return err
+	}
+	if strings.TrimSpace(diff) == "" {
+		return errors.New("no staged changes, stage them with git add first")
+	}
+
+	chunks := splitDiff(diff, session.diffBudget(diffSummaryPrompt))
+	content := "Staged diff:\n\n" + diff
+	if len(chunks) > 1 {
+		summaries := make([]string, 0, len(chunks))
+		for i, chunk := range chunks {
+			fmt.Fprintf(os.Stderr, "%sSummarizing part %d/%d of the staged diff...%s\n", colorDim, i+1, len(chunks), colorReset)
+			summary, err := session.askGit(ctx, diffSummaryPrompt, chunk)
+			if err != nil {
+				return err
+			}
+			summaries = append(summaries, summary)
+		}
+		content = "Summaries of the staged changes:\n\n" + strings.Join(summaries, "\n")
+	}
+
+	message, err := session.askGit(ctx, commitMsgPrompt, content)
+	if err != nil {
+		return err
+	}
+	if strings.HasPrefix(message, "```") {
+		message = strings.TrimPrefix(message[strings.IndexByte(message+"\n", '\n'):], "\n")
+		message = strings.TrimSpace(strings.TrimSuffix(strings.TrimSpace(message), "```"))
+	}
+	fmt.Println(message)
+	return nil
+}
+
+func runReview(session *ChatSession, revisions []string) error {
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	args := []string{"diff", "--no-color", "--no-ext-diff"}
+	if len(revisions) == 0 {
+		revisions = []string{"HEAD"}
+	}
+	diff, err := runGit(append(args, revisions...)...)
+	if err != nil {
+		return err
+	}
+	if strings.TrimSpace(diff) == "" {
+		return fmt.Errorf("no changes to review in %s", strings.Join(revisions, " "))
+	}
+
+	chunks := splitDiff(diff, session.diffBudget(reviewPrompt))
+	for i, chunk := range chunks {
+		if len(chunks) > 1 {
+			fmt.Printf("%s── Part %d/%d ──%s\n", colorCyan, i+1, len(chunks), colorReset)
+		}
+		findings, err := session.askGit(ctx, reviewPrompt, "Diff to review:\n\n"+chunk)
+		if err != nil {
+			return err
+		}
+		fmt.Println(renderForDisplay(session.config, findings))
+	}
+	return nil
+}
@@ -0,0 +1,60 @@
+package main
+
+import (
+	"context"
+	"fmt"
+)
+
+func (c *Conversation) clear() int {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	keep := leadingSystemMessages(c.History)
+	removed := len(c.History) - keep
+	c.tokenCount -= countTokens(c.History[keep:])
+	c.History = c.History[:keep]
+	return removed
+}
+
+func (s *ChatSession) saveChanges() {
+	if s.sessionID == "" {
+		return
+	}
+	if err := s.persist(); err != nil {
+		fmt.Printf("%sFailed to save session %s: %v%s\n", colorRed, s.sessionID, err, colorReset)
+	}
+}
+
+func handleUndoCommand(ctx context.Context, session *ChatSession, _ string) error {
+	last, ok := session.conversation.lastUserMessage()
+	if !ok {
+		fmt.Printf("%sNothing to undo.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if !session.confirm(ctx, fmt.Sprintf("Remove the last exchange (%q)?", truncateString(last.Content, maxSessionTitle))) {
+		return nil
+	}
+
+	removed, _ := session.conversation.dropLastExchange(false)
+	session.alternatives = nil
+	session.saveChanges()
+	fmt.Printf("%sRemoved %d messages.%s\n", colorGreen, len(removed), colorReset)
+	return nil
+}
+
+func handleClearCommand(ctx context.Context, session *ChatSession, _ string) error {
+	history := session.conversation.getHistory()
+	if len(history) == leadingSystemMessages(history) {
+		clearScreen()
+		return nil
+	}
+	if !session.confirm(ctx, fmt.Sprintf("Clear %d messages from the conversation?", len(history)-leadingSystemMessages(history))) {
+		return nil
+	}
+
+	removed := session.conversation.clear()
+	session.alternatives = nil
+	session.saveChanges()
+	clearScreen()
+	fmt.Printf("%sCleared %d messages, the system prompt is kept.%s\n", colorGreen, removed, colorReset)
+	return nil
+}
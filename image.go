@@ -0,0 +1,62 @@
+package main
+
+import (
+	"context"
+	"encoding/base64"
+	"fmt"
+	"mime"
+	"net/http"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"code/aili"
+)
+
+const maxImageBytes = 4 * 1024 * 1024
+
+func loadImage(source string) (string, error) {
+	if strings.HasPrefix(source, "http://") || strings.HasPrefix(source, "https://") {
+		return source, nil
+	}
+	data, err := os.ReadFile(source)
+	if err != nil {
+		return "", fmt.Errorf("failed to read image: %w", err)
+	}
+	if len(data) > maxImageBytes {
+		return "", fmt.Errorf("%s is %d bytes, images are limited to %d", source, len(data), maxImageBytes)
+	}
+	mediaType := mime.TypeByExtension(strings.ToLower(filepath.Ext(source)))
+	if mediaType == "" {
+		mediaType = http.DetectContentType(data)
+	}
+	if !strings.HasPrefix(mediaType, "image/") {
+		return "", fmt.Errorf("%s does not look like an image (%s)", source, mediaType)
+	}
+	return "data:" + mediaType + ";base64," + base64.StdEncoding.EncodeToString(data), nil
+}
+
+func (s *ChatSession) takeImages() []string {
+	images := s.images
+	s.images = nil
+	return images
+}
+
+func handleImageCommand(_ context.Context, session *ChatSession, source string) error {
+	if source == "" {
+		fmt.Printf("%sUsage: /image <path-or-url>%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if _, ok := session.apiClient.Provider.(*aili.OpenAIProvider); !ok {
+		fmt.Printf("%sThe %s provider does not support image input.%s\n", colorRed, session.apiClient.Provider.Name(), colorReset)
+		return nil
+	}
+	image, err := loadImage(source)
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	session.images = append(session.images, image)
+	fmt.Printf("%sImage %s will be sent with your next message.%s\n", colorGreen, source, colorReset)
+	return nil
+}
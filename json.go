@@ -0,0 +1,83 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"fmt"
+	"log"
+	"strings"
+	"time"
+)
+
+const (
+	maxJSONRepairs  = 2
+	jsonInstruction = "Respond with a single valid JSON value and nothing else: no prose, no code fences."
+)
+
+func jsonMode(config *Config) bool {
+	return config.ResponseFormat == "json_object" || config.schema != nil
+}
+
+func jsonNote(config *Config) string {
+	if config.schema == nil {
+		return jsonInstruction
+	}
+	schema, _ := json.Marshal(config.schema)
+	return jsonInstruction + " The JSON must satisfy this JSON Schema: " + string(schema)
+}
+
+func extractJSON(text string) (json.RawMessage, error) {
+	text = strings.TrimSpace(text)
+	if strings.HasPrefix(text, "```") {
+		text = strings.TrimPrefix(text[strings.IndexByte(text+"\n", '\n'):], "\n")
+		text = strings.TrimSpace(strings.TrimSuffix(strings.TrimSpace(text), "```"))
+	}
+	var value interface{}
+	if err := json.Unmarshal([]byte(text), &value); err != nil {
+		return nil, err
+	}
+	return json.RawMessage(text), nil
+}
+
+func (s *ChatSession) askJSON(ctx context.Context, history []Message, model string) (json.RawMessage, string, error) {
+	history = withTurnNote(history, jsonNote(s.config))
+	for attempt := 0; ; attempt++ {
+		completion, usedModel, _, err := s.askWithFallback(ctx, history, model)
+		if err != nil {
+			return nil, usedModel, err
+		}
+		reply := s.postProcess(completion.Content)
+		value, err := extractJSON(reply)
+		problem := ""
+		if err != nil {
+			problem = fmt.Sprintf("That was not valid JSON (%v).", err)
+		} else if violations := s.config.validateResponse(value); len(violations) > 0 {
+			problem = "That JSON does not match the schema: " + strings.Join(violations, "; ") + "."
+		} else {
+			return value, usedModel, nil
+		}
+		if attempt == maxJSONRepairs {
+			return nil, usedModel, fmt.Errorf("response still invalid after %d attempts: %s", attempt+1, problem)
+		}
+
+		log.Printf("%s Asking %s to correct it.", problem, usedModel)
+		history = append(history,
+			Message{Role: "assistant", Content: reply, Timestamp: time.Now()},
+			Message{Role: "user", Content: problem + " Reply again with only the corrected JSON.", Timestamp: time.Now()},
+		)
+	}
+}
+
+func (s *ChatSession) runJSONOneShot(ctx context.Context, history []Message) error {
+	value, _, err := s.askJSON(ctx, history, s.model)
+	if err != nil {
+		return fmt.Errorf("failed to get AI response: %w", err)
+	}
+	var out bytes.Buffer
+	if err := json.Indent(&out, value, "", "  "); err != nil {
+		return fmt.Errorf("failed to format JSON: %w", err)
+	}
+	fmt.Println(out.String())
+	return nil
+}
@@ -0,0 +1,132 @@
+package main
+
+import (
+	"bufio"
+	"errors"
+	"fmt"
+	"os"
+	"os/exec"
+	"runtime"
+	"strings"
+
+	"golang.org/x/term"
+)
+
+const keyringService = "aili"
+
+var keyringNames = []string{"groq", "openai", "anthropic", "search"}
+
+func (c *Config) secretFields() map[string]*string {
+	return map[string]*string{
+		"groq_api_key":      &c.GroqAPIKey,
+		"openai_api_key":    &c.OpenAIAPIKey,
+		"anthropic_api_key": &c.AnthropicAPIKey,
+		"search_api_key":    &c.SearchAPIKey,
+	}
+}
+
+func keyringAvailable() bool {
+	if runtime.GOOS == "darwin" {
+		return true
+	}
+	_, err := exec.LookPath("secret-tool")
+	return err == nil
+}
+
+func keyringLookup(account string) (string, bool) {
+	var cmd *exec.Cmd
+	switch {
+	case runtime.GOOS == "darwin":
+		cmd = exec.Command("security", "find-generic-password", "-s", keyringService, "-a", account, "-w")
+	case keyringAvailable():
+		cmd = exec.Command("secret-tool", "lookup", "service", keyringService, "account", account)
+	default:
+		return "", false
+	}
+	out, err := cmd.Output()
+	if err != nil {
+		return "", false
+	}
+	secret := strings.TrimRight(string(out), "\r\n")
+	return secret, secret != ""
+}
+
+func keyringStore(account, secret string) error {
+	var cmd *exec.Cmd
+	switch {
+	case runtime.GOOS == "darwin":
+		quote := strings.NewReplacer(`\`, `\\`, `"`, `\"`)
+		cmd = exec.Command("security", "-i")
+		cmd.Stdin = strings.NewReader(fmt.Sprintf("add-generic-password -U -s %s -a %s -w \"%s\"\n", keyringService, account, quote.Replace(secret)))
+	case keyringAvailable():
+		cmd = exec.Command("secret-tool", "store", "--label=aili "+account, "service", keyringService, "account", account)
+		cmd.Stdin = strings.NewReader(secret)
+	default:
+		return errors.New("no keyring found, install secret-tool (libsecret) or keep the key in the config file")
+	}
+	if out, err := cmd.CombinedOutput(); err != nil {
+		return fmt.Errorf("failed to store %s in the keyring: %v %s", account, err, strings.TrimSpace(string(out)))
+	}
+	return nil
+}
+
+func (c *Config) applyKeyring() {
+	var accounts []string
+	if c.Provider != "ollama" {
+		accounts = append(accounts, c.Provider+"_api_key")
+	}
+	if c.SearchProvider != "" && c.SearchProvider != "searxng" {
+		accounts = append(accounts, "search_api_key")
+	}
+	if len(accounts) == 0 || !keyringAvailable() {
+		return
+	}
+
+	fields := c.secretFields()
+	for _, account := range accounts {
+		field, ok := fields[account]
+		if !ok {
+			continue
+		}
+		if secret, ok := keyringLookup(account); ok {
+			*field = secret
+			c.sources[account] = "keyring"
+		}
+	}
+}
+
+func readSecret(prompt string) (string, error) {
+	if !term.IsTerminal(int(os.Stdin.Fd())) {
+		line, err := bufio.NewReader(os.Stdin).ReadString('\n')
+		if err != nil && line == "" {
+			return "", fmt.Errorf("failed to read the key: %w", err)
+		}
+		return strings.TrimSpace(line), nil
+	}
+	fmt.Fprint(os.Stderr, prompt)
+	secret, err := term.ReadPassword(int(os.Stdin.Fd()))
+	fmt.Fprintln(os.Stderr)
+	if err != nil {
+		return "", fmt.Errorf("failed to read the key: %w", err)
+	}
+	return strings.TrimSpace(string(secret)), nil
+}
+
+func setKeyringSecret(name string) error {
+	account := name + "_api_key"
+	if _, ok := (&Config{}).secretFields()[account]; !ok {
+		return fmt.Errorf("unknown key %q, expected one of %s", name, strings.Join(keyringNames, ", "))
+	}
+	secret, err := readSecret(account + ": ")
+	if err != nil {
+		return err
+	}
+	if secret == "" {
+		return errors.New("the key must not be empty")
+	}
+	if err := keyringStore(account, secret); err != nil {
+		return err
+	}
+	fmt.Printf("Stored %s in the keyring, it takes precedence over the config file and environment.\n", account)
+	return nil
+}
@@ -0,0 +1,173 @@
+package main
+
+import (
+	"errors"
+	"fmt"
+	"net/http"
+	"os"
+	"sync"
+	"time"
+
+	"code/aili"
+)
+
+type APIKeyConfig struct {
+	Provider string `yaml:"provider"`
+	Key      string `yaml:"key"`
+	Model    string `yaml:"model,omitempty"`
+}
+
+type pooledKey struct {
+	APIKeyConfig
+	coolUntil time.Time
+}
+
+type keyPool struct {
+	mu      sync.Mutex
+	keys    []*pooledKey
+	current int
+}
+
+func (c *Config) providerKey(provider string) string {
+	switch provider {
+	case "groq":
+		return c.GroqAPIKey
+	case "openai":
+		return c.OpenAIAPIKey
+	case "anthropic":
+		return c.AnthropicAPIKey
+	}
+	return ""
+}
+
+func (c *Config) hasPooledKey(provider string) bool {
+	for _, entry := range c.APIKeys {
+		if entry.Provider == provider || entry.Provider == "" && provider == c.Provider {
+			return true
+		}
+	}
+	return false
+}
+
+func validateAPIKeys(keys []APIKeyConfig) error {
+	for i, entry := range keys {
+		switch entry.Provider {
+		case "", "groq", "openai", "anthropic":
+		default:
+			return fmt.Errorf("api_keys[%d]: unknown provider %q, expected \"groq\", \"openai\" or \"anthropic\"", i, entry.Provider)
+		}
+		if entry.Key == "" {
+			return fmt.Errorf("api_keys[%d]: key must not be empty", i)
+		}
+	}
+	return nil
+}
+
+func newKeyPool(config *Config) *keyPool {
+	pool := &keyPool{}
+	primary := APIKeyConfig{Provider: config.Provider, Key: config.providerKey(config.Provider)}
+	if primary.Key != "" || config.Provider == "ollama" {
+		pool.keys = append(pool.keys, &pooledKey{APIKeyConfig: primary})
+	}
+	for _, entry := range config.APIKeys {
+		if entry.Provider == "" {
+			entry.Provider = config.Provider
+		}
+		if entry.Provider == primary.Provider && entry.Key == primary.Key {
+			continue
+		}
+		pool.keys = append(pool.keys, &pooledKey{APIKeyConfig: entry})
+	}
+	return pool
+}
+
+func (p *keyPool) active() (APIKeyConfig, bool) {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	if len(p.keys) == 0 {
+		return APIKeyConfig{}, false
+	}
+	return p.keys[p.current].APIKeyConfig, true
+}
+
+func (p *keyPool) size() int {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	return len(p.keys)
+}
+
+func (p *keyPool) rotate(cooldown time.Duration) (APIKeyConfig, bool) {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	if len(p.keys) < 2 {
+		return APIKeyConfig{}, false
+	}
+	now := time.Now()
+	p.keys[p.current].coolUntil = now.Add(cooldown)
+	for i := 1; i < len(p.keys); i++ {
+		next := (p.current + i) % len(p.keys)
+		if now.After(p.keys[next].coolUntil) {
+			p.current = next
+			return p.keys[next].APIKeyConfig, true
+		}
+	}
+	return APIKeyConfig{}, false
+}
+
+func (c *APIClient) activeKey() APIKeyConfig {
+	if key, ok := c.keys.active(); ok {
+		return key
+	}
+	return APIKeyConfig{Provider: c.config.Provider, Key: c.config.providerKey(c.config.Provider)}
+}
+
+func (c *APIClient) settings() aili.Config {
+	key := c.activeKey()
+	return c.config.clientConfig(key.Provider, key.Key)
+}
+
+func (c *APIClient) activeProvider() ChatProvider {
+	key := c.activeKey()
+	return newKeyedProvider(c.config, key.Provider, key.Key)
+}
+
+func (c *APIClient) modelFor(model string) string {
+	key, ok := c.keys.active()
+	if !ok || key.Provider == c.config.Provider {
+		return model
+	}
+	if key.Model != "" {
+		return key.Model
+	}
+	return c.Provider.DefaultModel()
+}
+
+func (c *APIClient) failover(err error) bool {
+	var apiErr *APIError
+	if !errors.As(err, &apiErr) {
+		return false
+	}
+	switch apiErr.StatusCode {
+	case http.StatusUnauthorized, http.StatusForbidden, http.StatusTooManyRequests:
+	default:
+		return false
+	}
+	if c.failovers >= c.keys.size()-1 {
+		return false
+	}
+
+	cooldown := time.Duration(c.config.KeyCooldown) * time.Second
+	if apiErr.RetryAfter > cooldown {
+		cooldown = apiErr.RetryAfter
+	}
+	failed, _ := c.keys.active()
+	next, ok := c.keys.rotate(cooldown)
+	if !ok {
+		return false
+	}
+	c.failovers++
+	c.Provider = newKeyedProvider(c.config, next.Provider, next.Key)
+	fmt.Fprintf(os.Stderr, "%s%s key %s failed with HTTP %d, cooling it down for %s and switching to %s key %s.%s\n",
+		colorYellow, failed.Provider, redactSecret(failed.Key), apiErr.StatusCode, cooldown.Round(time.Second), next.Provider, redactSecret(next.Key), colorReset)
+	return true
+}
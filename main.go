@@ -10,43 +10,143 @@ import (
 	"fmt"
 	"io"
 	"log"
-	"math/rand"
+	"maps"
+	"net"
 	"net/http"
+	"net/http/httptrace"
+	"net/url"
 	"os"
+	"os/exec"
 	"os/signal"
+	"path/filepath"
+	"regexp"
+	"slices"
+	"sort"
 	"strings"
 	"sync"
 	"syscall"
 	"time"
+	"unicode"
+	"unicode/utf8"
 
 	"golang.org/x/sync/errgroup"
 	"golang.org/x/term"
 	"gopkg.in/yaml.v3"
+
+	"code/aili"
 )
 
 const (
 	maxTokens              = 8000
-	apiURL                 = "https://api.groq.com/openai/v1/chat/completions"
 	initialHistoryCapacity = 10
-	configFile             = "config.yaml"
+	defaultConfigFile      = "config.yaml"
 	timeoutSeconds         = 30
 	exitCommand            = "exit"
 	maxRetries             = 3
-	backoffFactor          = 2
 	initialBackoff         = 1 * time.Second
-	maxConversationTokens  = 4000
 	systemPromptFile       = "system_prompt.txt"
-	requestsPerSecond      = 10
+	truncationNoteWords    = 8
+	maxDisplayURLLength    = 48
+	tpsUpdateInterval      = 250 * time.Millisecond
+	terminalTitle          = "AI Chat"
+	typewriterDelay        = 50 * time.Millisecond
+	minEchoWords           = 8
+	formatterTimeout       = 5 * time.Second
+	defaultBoxWidth        = 80
+	minBoxWidth            = 20
+	saveFormatVersion      = 2
+	autosaveName           = "session"
+	multilineDelimiter     = `"""`
+	pasteStart             = "\x1b[200~"
+	pasteEnd               = "\x1b[201~"
+	enableBracketedPaste   = "\x1b[?2004h"
+	disableBracketedPaste  = "\x1b[?2004l"
 )
 
+var errInterrupted = errors.New("interrupted")
+
+var urlPattern = regexp.MustCompile(`https?://[^\s<>()\[\]"']+`)
+
+var wordPattern = regexp.MustCompile(`\S+`)
+
+var ansiPattern = regexp.MustCompile(`\x1b\[[0-9;]*m`)
+
 type Config struct {
-	GroqAPIKey string `yaml:"groq_api_key"`
+	Provider            string                     `yaml:"provider"`
+	SystemPrompt        string                     `yaml:"system_prompt"`
+	Personas            map[string]Persona         `yaml:"personas"`
+	GroqAPIKey          string                     `yaml:"groq_api_key"`
+	OpenAIAPIKey        string                     `yaml:"openai_api_key"`
+	AnthropicAPIKey     string                     `yaml:"anthropic_api_key"`
+	APIKeys             []APIKeyConfig             `yaml:"api_keys"`
+	KeyCooldown         int                        `yaml:"key_cooldown_seconds"`
+	OllamaURL           string                     `yaml:"ollama_base_url"`
+	OllamaModel         string                     `yaml:"ollama_model"`
+	Temperature         float64                    `yaml:"temperature"`
+	TopP                float64                    `yaml:"top_p"`
+	MaxTokens           int                        `yaml:"max_tokens"`
+	ContextWindow       int                        `yaml:"context_window"`
+	MaxAttempts         int                        `yaml:"max_attempts"`
+	RetryBackoffMs      int                        `yaml:"retry_backoff_ms"`
+	NormalizeWhitespace bool                       `yaml:"normalize_whitespace"`
+	TruncationStrategy  string                     `yaml:"truncation_strategy"`
+	SummarizeThreshold  int                        `yaml:"summarize_threshold_tokens"`
+	ModelAliases        map[string]string          `yaml:"model_aliases"`
+	ExportTokenCounts   bool                       `yaml:"export_token_counts"`
+	ConnectTimeout      int                        `yaml:"connect_timeout_seconds"`
+	TruncationIndicator IndicatorStyle             `yaml:"truncation_indicator"`
+	ShortenURLs         bool                       `yaml:"shorten_urls"`
+	Candidates          int                        `yaml:"candidates"`
+	CandidateSelection  string                     `yaml:"candidate_selection"`
+	MaxHistoryMessages  int                        `yaml:"max_history_messages"`
+	AutosavePruned      bool                       `yaml:"autosave_pruned"`
+	SentenceShading     bool                       `yaml:"experimental_sentence_shading"`
+	ContextFiles        []string                   `yaml:"context_files"`
+	ContextTokenBudget  int                        `yaml:"context_token_budget"`
+	ShowLiveTPS         bool                       `yaml:"show_live_tps"`
+	UserSuffix          string                     `yaml:"user_suffix"`
+	StoreUserSuffix     bool                       `yaml:"store_user_suffix"`
+	StripEchoedSystem   bool                       `yaml:"strip_echoed_system"`
+	SaveSettings        bool                       `yaml:"save_session_settings"`
+	BatchWindowMs       int                        `yaml:"batch_window_ms"`
+	FormatCode          map[string]string          `yaml:"format_code"`
+	MaxRequests         int                        `yaml:"max_requests_per_session"`
+	StreamReconnect     bool                       `yaml:"stream_reconnect"`
+	FallbackModel       string                     `yaml:"fallback_model"`
+	FallbackModels      []string                   `yaml:"fallback_models"`
+	BoxedOutput         bool                       `yaml:"boxed_output"`
+	RenderMarkdown      bool                       `yaml:"render_markdown"`
+	HighlightCode       bool                       `yaml:"highlight_code"`
+	AutosaveBackups     int                        `yaml:"autosave_backups"`
+	UsageFooter         bool                       `yaml:"usage_footer"`
+	EnableTools         bool                       `yaml:"enable_tools"`
+	ShellTool           bool                       `yaml:"shell_tool"`
+	EmbeddingModel      string                     `yaml:"embedding_model"`
+	RAGTopK             int                        `yaml:"rag_top_k"`
+	TemplatesDir        string                     `yaml:"templates_dir"`
+	ResponseFormat      string                     `yaml:"response_format"`
+	ResponseSchema      string                     `yaml:"response_schema"`
+	TTSCommand          string                     `yaml:"tts_command"`
+	TTSModel            string                     `yaml:"tts_model"`
+	TTSVoice            string                     `yaml:"tts_voice"`
+	AudioPlayer         string                     `yaml:"audio_player"`
+	AudioRecorder       string                     `yaml:"audio_recorder"`
+	TranscriptionModel  string                     `yaml:"transcription_model"`
+	SearchProvider      string                     `yaml:"search_provider"`
+	SearchURL           string                     `yaml:"search_url"`
+	SearchAPIKey        string                     `yaml:"search_api_key"`
+	SearchResults       int                        `yaml:"search_results"`
+	MCPServers          map[string]MCPServerConfig `yaml:"mcp_servers"`
+	Theme               ThemeConfig                `yaml:"theme"`
+	Pricing             map[string]ModelPrice      `yaml:"pricing"`
+
+	schema  map[string]interface{}
+	sources map[string]string
 }
 
-type Message struct {
-	Role      string    `json:"role"`
-	Content   string    `json:"content"`
-	Timestamp time.Time `json:"-"`
+type IndicatorStyle struct {
+	Text  string `yaml:"text"`
+	Color string `yaml:"color"`
 }
 
 type APIMessage struct {
@@ -54,269 +154,1928 @@ type APIMessage struct {
 	Content string `json:"content"`
 }
 
+type SavedConversation struct {
+	Version  int              `json:"version"`
+	Settings *SessionSettings `json:"settings,omitempty"`
+	Messages []Message        `json:"messages"`
+}
+
+type SessionSettings struct {
+	Model        string `json:"model"`
+	SystemPrompt string `json:"system_prompt"`
+	Parent       string `json:"parent,omitempty"`
+	BranchPoint  int    `json:"branch_point,omitempty"`
+}
+
+type FineTuningRecord struct {
+	Messages []APIMessage `json:"messages"`
+}
+
 type Conversation struct {
-	History    []Message
-	mu         sync.RWMutex
-	tokenCount int
+	History     []Message
+	mu          sync.RWMutex
+	tokenCount  int
+	maxMessages int
+	onPrune     func(pruned []Message)
+	cost        float64
 }
 
 type APIClient struct {
-	httpClient  *http.Client
-	apiKey      string
-	rateLimiter *time.Ticker
+	*aili.Client
+	config     *Config
+	tools      []Tool
+	rateLimits http.Header
+	limitsAt   time.Time
+	keys       *keyPool
+	failovers  int
+}
+
+type ChatSession struct {
+	config           *Config
+	apiClient        *APIClient
+	conversation     *Conversation
+	model            string
+	options          *Options
+	sessionID        string
+	parentID         string
+	branchPoint      int
+	systemPromptPath string
+	ignoreSettings   bool
+	usage            map[string]*ModelUsage
+	checkpoints      map[string][]Message
+	persona          string
+	pendingNote      string
+	requestCount     int
+	budgetOverride   bool
+	turnMu           sync.Mutex
+	cancelTurn       context.CancelFunc
+	input            *InputReader
+	attachments      []string
+	attachmentChunks []string
+	workspace        *Workspace
+	rag              *RAGIndex
+	images           []string
+	alternatives     []Message
+	mcp              []*MCPClient
+}
+
+type OneShotResult struct {
+	Model            string `json:"model"`
+	Response         string `json:"response"`
+	FinishReason     string `json:"finish_reason,omitempty"`
+	PromptTokens     int    `json:"prompt_tokens"`
+	CompletionTokens int    `json:"completion_tokens"`
+	Cancelled        bool   `json:"cancelled,omitempty"`
+}
+
+type InputReader struct {
+	lines   chan string
+	ready   chan struct{}
+	pending bool
+	closed  bool
+}
+
+type PingResult struct {
+	Connect    time.Duration
+	Total      time.Duration
+	Reused     bool
+	StatusCode int
+	Authorized bool
+}
+
+type ModelUsage struct {
+	Turns            int
+	PromptTokens     int
+	CompletionTokens int
+	Duration         time.Duration
+	Cost             float64
 }
 
 func main() {
 	if err := run(); err != nil {
+		if !term.IsTerminal(int(os.Stderr.Fd())) {
+			log.Fatalf("Error: %v\n", err)
+		}
 		log.Fatalf("%sError: %v%s\n", colorRed, err, colorReset)
 	}
 }
 
 func run() error {
-	config, err := loadConfig()
+	options, err := parseOptions()
+	if err != nil {
+		return err
+	}
+	detectTheme(options.NoColor).apply()
+	if options.Command == commandConfig && len(options.Args) > 0 && options.Args[0] != "show" {
+		return runConfigCommand(options.ConfigPath, options.Args)
+	}
+	if err := loadDotEnvFiles(options.ConfigPath); err != nil {
+		return err
+	}
+
+	config, err := loadConfig(options.ConfigPath)
 	if err != nil {
 		return fmt.Errorf("failed to load configuration: %w", err)
 	}
+	if err := options.applyOverrides(config); err != nil {
+		return err
+	}
+	if err := applyThemeConfig(config.Theme); err != nil {
+		return err
+	}
+
+	switch options.Command {
+	case commandConfig:
+		if err := printEffectiveConfig(config, options.modelOrDefault(newProvider(config))); err != nil {
+			return err
+		}
+		printConfigSources(config, options.ConfigPath)
+		return nil
+	case commandSessions:
+		return runSessionsCommand(options.Args)
+	case commandModels:
+		apiClient, err := newAPIClient(config)
+		if err != nil {
+			return err
+		}
+		return runModelsCommand(apiClient, options.modelOrDefault(apiClient.Provider))
+	case commandIndex:
+		apiClient, err := newAPIClient(config)
+		if err != nil {
+			return err
+		}
+		return runIndexCommand(apiClient, options.Args)
+	}
+
+	if options.PlaybackFile != "" {
+		return runPlayback(config, options.PlaybackFile, options.PlaybackSpeed)
+	}
 
-	apiClient := newAPIClient(config.GroqAPIKey)
-	conversation, err := newConversation()
+	apiClient, err := newAPIClient(config)
+	if err != nil {
+		return err
+	}
+	apiClient.OnRetry = logRetry
+	conversation, err := newConversation(config, options.SystemFile)
 	if err != nil {
 		return fmt.Errorf("failed to create conversation: %w", err)
 	}
 
-	printWelcomeMessage()
-	return runChatLoop(apiClient, conversation)
+	conversation.applyRetention(config)
+	if len(config.ContextFiles) > 0 {
+		contextMessage, err := buildContextMessage(config.ContextFiles, config.ContextTokenBudget)
+		if err != nil {
+			return fmt.Errorf("failed to load context files: %w", err)
+		}
+		if contextMessage != "" {
+			conversation.addMessage("system", contextMessage)
+		}
+	}
+
+	session := &ChatSession{
+		config:           config,
+		apiClient:        apiClient,
+		conversation:     conversation,
+		model:            options.modelOrDefault(apiClient.Provider),
+		options:          options,
+		systemPromptPath: options.SystemFile,
+		ignoreSettings:   options.IgnoreSettings,
+		usage:            make(map[string]*ModelUsage),
+		checkpoints:      make(map[string][]Message),
+	}
+
+	if options.Workspace != "" {
+		workspace, err := openWorkspace(options.Workspace)
+		if err != nil {
+			return err
+		}
+		session.workspace = workspace
+	}
+	if options.RAG != "" {
+		index, err := loadRAGIndex(options.RAG)
+		if err != nil {
+			return err
+		}
+		session.rag = index
+	}
+	if len(config.MCPServers) > 0 && (options.Command == commandChat || (options.Command == commandAsk && !jsonMode(config))) {
+		session.connectMCPServers(context.Background())
+		defer session.closeMCPServers()
+	}
+
+	if options.Persona != "" {
+		if err := session.applyPersona(options.Persona); err != nil {
+			return err
+		}
+	}
+
+	if options.Command == commandCompare {
+		return runCompare(session, options.Prompt, options.Models)
+	}
+	if options.Command == commandBatch {
+		return runBatch(session, options.Args[0], options.Out, options.Concurrency)
+	}
+	if options.Command == commandCommitMsg {
+		return runCommitMsg(session)
+	}
+	if options.Command == commandReview {
+		return runReview(session, options.Args)
+	}
+	if options.Command == commandAsk {
+		if options.Template != "" {
+			prompt, err := session.expandTemplate(context.Background(), options)
+			if err != nil {
+				return err
+			}
+			options.Prompt = prompt
+		}
+		if options.File != "" {
+			content, err := readAttachment(options.File)
+			if err != nil {
+				return err
+			}
+			if tokens := len(strings.Fields(content)); tokens > promptBudget(config, session.model) {
+				log.Printf("%s is %d tokens, which exceeds the prompt budget of %d", options.File, tokens, promptBudget(config, session.model))
+			}
+			options.Prompt = formatAttachment(options.File, content, "") + "\n\n" + options.Prompt
+		}
+		session.addWorkspaceIndex()
+		return runOneShot(session, options.Prompt, options.Format)
+	}
+
+	if options.TUI {
+		if err := runTUI(session); err != nil {
+			return err
+		}
+		if len(session.usage) > 0 {
+			printSpendSummary(session.conversation)
+		}
+		return nil
+	}
+	if err := startChat(session); err != nil {
+		return err
+	}
+	return runChatLoop(session)
+}
+
+func startChat(session *ChatSession) error {
+	options := session.options
+	printWelcomeMessage(session.model)
+	if options.Resume != "" {
+		if err := resumeSession(session, options.Resume); err != nil {
+			return err
+		}
+	} else if options.Load != "" {
+		session.sessionID = newSessionID()
+		if err := importTranscript(session, options.Load); err != nil {
+			return err
+		}
+	} else {
+		session.sessionID = newSessionID()
+		if session.config.AutosaveBackups > 0 {
+			restoreAutosave(session)
+		}
+	}
+	session.addWorkspaceIndex()
+	return nil
+}
+
+func runOneShot(session *ChatSession, prompt, format string) error {
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	session.conversation.addMessage("user", withStoredUserSuffix(session.config, prompt))
+	history := session.conversation.getHistory()
+	if jsonMode(session.config) {
+		return session.runJSONOneShot(ctx, history)
+	}
+	promptTokens := countTokens(session.apiClient.prepareHistory(history, session.model))
+	completion, model, _, err := session.completeTurn(ctx, history, session.model)
+	cancelled := errors.Is(err, errStreamCancelled) && completion.Content != ""
+	if err != nil && !cancelled {
+		return fmt.Errorf("failed to get AI response: %w", err)
+	}
+
+	aiResponse := session.postProcess(completion.Content)
+
+	if err := printOneShotResult(session.config, format, OneShotResult{
+		Model:            model,
+		Response:         aiResponse,
+		FinishReason:     completion.FinishReason,
+		PromptTokens:     promptTokens,
+		CompletionTokens: len(strings.Fields(aiResponse)),
+		Cancelled:        cancelled,
+	}); err != nil {
+		return err
+	}
+	if cancelled {
+		return errStreamCancelled
+	}
+	return nil
 }
 
-func loadConfig() (*Config, error) {
-	data, err := os.ReadFile(configFile)
+func runPlayback(config *Config, path string, speed float64) error {
+	conversation, _, err := loadConversation(path)
 	if err != nil {
+		return fmt.Errorf("failed to load conversation for playback: %w", err)
+	}
+
+	delay := time.Duration(float64(typewriterDelay) / speed)
+	clearScreen()
+	for _, msg := range conversation.History {
+		switch msg.Role {
+		case "system":
+			continue
+		case "user":
+			printTypewriter("You", colorUser, msg.Content, delay)
+		case "tool":
+			printTypewriter(roleLabel(msg.Role), colorDim, msg.Content, delay)
+		default:
+			printTypewriter("AI", colorAssistant, renderForDisplay(config, msg.Content), delay)
+		}
+		if msg.Cancelled {
+			printTruncationIndicator(config.TruncationIndicator)
+		}
+		fmt.Println()
+	}
+	return nil
+}
+
+func printOneShotResult(config *Config, format string, result OneShotResult) error {
+	switch format {
+	case "json":
+		data, err := json.MarshalIndent(result, "", "  ")
+		if err != nil {
+			return fmt.Errorf("failed to marshal result: %w", err)
+		}
+		fmt.Println(string(data))
+	case "markdown":
+		fmt.Println(renderForDisplay(config, result.Response))
+		if result.Cancelled {
+			printTruncationIndicator(config.TruncationIndicator)
+		}
+	default:
+		fmt.Println(result.Response)
+		if result.Cancelled {
+			fmt.Println(config.TruncationIndicator.Text)
+		}
+	}
+	return nil
+}
+
+func loadConfig(path string) (*Config, error) {
+	data, err := os.ReadFile(path)
+	if err != nil && !errors.Is(err, os.ErrNotExist) {
 		return nil, fmt.Errorf("failed to read config file: %w", err)
 	}
 
-	var config Config
+	config := Config{
+		Provider:            "groq",
+		OllamaURL:           "http://localhost:11434",
+		OllamaModel:         "llama3.1",
+		Temperature:         0.7,
+		TopP:                0.9,
+		MaxTokens:           maxTokens,
+		MaxAttempts:         maxRetries,
+		RetryBackoffMs:      int(initialBackoff / time.Millisecond),
+		KeyCooldown:         60,
+		RenderMarkdown:      true,
+		HighlightCode:       true,
+		UsageFooter:         true,
+		RAGTopK:             4,
+		SearchResults:       5,
+		TTSModel:            "tts-1",
+		TTSVoice:            "alloy",
+		TruncationStrategy:  "drop",
+		TruncationIndicator: IndicatorStyle{Text: "(truncated)", Color: "dim"},
+		Candidates:          1,
+		CandidateSelection:  "first",
+		ContextTokenBudget:  2000,
+	}
 	if err := yaml.Unmarshal(data, &config); err != nil {
 		return nil, fmt.Errorf("failed to parse config file: %w", err)
 	}
+	config.sources = make(map[string]string)
+	for _, key := range fileConfigKeys(data) {
+		config.sources[key] = path
+	}
+	if err := config.applyEnvironment(); err != nil {
+		return nil, err
+	}
+	config.applyKeyring()
+
+	switch config.Provider {
+	case "groq":
+		if config.GroqAPIKey == "" && !config.hasPooledKey("groq") {
+			return nil, fmt.Errorf("groq_api_key is missing, set it in %s, GROQ_API_KEY or with config set-key groq", path)
+		}
+	case "openai":
+		if config.OpenAIAPIKey == "" && !config.hasPooledKey("openai") {
+			return nil, fmt.Errorf("openai_api_key is missing, set it in %s, OPENAI_API_KEY or with config set-key openai", path)
+		}
+	case "anthropic":
+		if config.AnthropicAPIKey == "" && !config.hasPooledKey("anthropic") {
+			return nil, fmt.Errorf("anthropic_api_key is missing, set it in %s, ANTHROPIC_API_KEY or with config set-key anthropic", path)
+		}
+	case "ollama":
+		if parsed, err := url.Parse(config.OllamaURL); err != nil || parsed.Host == "" {
+			return nil, fmt.Errorf("invalid ollama_base_url %q", config.OllamaURL)
+		}
+		if config.OllamaModel == "" {
+			return nil, errors.New("ollama_model must not be empty")
+		}
+	default:
+		return nil, fmt.Errorf("unknown provider %q, expected \"groq\", \"openai\", \"anthropic\" or \"ollama\"", config.Provider)
+	}
+
+	if err := validateSampling(config.Temperature, config.TopP, config.MaxTokens); err != nil {
+		return nil, err
+	}
+	if config.ContextWindow < 0 {
+		return nil, errors.New("context_window must not be negative")
+	}
+	if config.ContextWindow > 0 && config.ContextWindow <= config.MaxTokens {
+		return nil, errors.New("context_window must be larger than max_tokens")
+	}
+
+	for name, persona := range config.Personas {
+		if persona.Temperature != nil && (*persona.Temperature < 0 || *persona.Temperature > 2) {
+			return nil, fmt.Errorf("persona %q temperature must be between 0 and 2", name)
+		}
+	}
+
+	if config.MaxAttempts < 1 {
+		return nil, errors.New("max_attempts must be at least 1")
+	}
+
+	if config.RetryBackoffMs <= 0 {
+		return nil, errors.New("retry_backoff_ms must be positive")
+	}
+
+	if config.ConnectTimeout < 0 {
+		return nil, errors.New("connect_timeout_seconds must not be negative")
+	}
+
+	for i, fallback := range config.FallbackModels {
+		if strings.TrimSpace(fallback) == "" {
+			return nil, fmt.Errorf("fallback_models[%d] must not be empty", i)
+		}
+	}
+
+	if err := validateAPIKeys(config.APIKeys); err != nil {
+		return nil, err
+	}
+	if config.KeyCooldown < 0 {
+		return nil, errors.New("key_cooldown_seconds must not be negative")
+	}
+
+	if _, ok := colorCodes[config.TruncationIndicator.Color]; !ok {
+		return nil, fmt.Errorf("unknown truncation_indicator color %q", config.TruncationIndicator.Color)
+	}
+	if _, err := config.Theme.styles(); err != nil {
+		return nil, err
+	}
+
+	for _, pattern := range config.ContextFiles {
+		if _, err := filepath.Match(pattern, ""); err != nil {
+			return nil, fmt.Errorf("invalid context_files pattern %q: %w", pattern, err)
+		}
+	}
+
+	for language, command := range config.FormatCode {
+		if strings.TrimSpace(command) == "" {
+			return nil, fmt.Errorf("format_code command for %q must not be empty", language)
+		}
+	}
+
+	for model, price := range config.Pricing {
+		if price.Input < 0 || price.Output < 0 {
+			return nil, fmt.Errorf("pricing for %q must not be negative", model)
+		}
+	}
+
+	if config.RAGTopK <= 0 {
+		return nil, errors.New("rag_top_k must be positive")
+	}
+
+	if config.MaxRequests < 0 {
+		return nil, errors.New("max_requests_per_session must not be negative")
+	}
+
+	if config.AutosaveBackups < 0 {
+		return nil, errors.New("autosave_backups must not be negative")
+	}
+
+	if config.BatchWindowMs < 0 {
+		return nil, errors.New("batch_window_ms must not be negative")
+	}
+
+	if config.MaxHistoryMessages < 0 {
+		return nil, errors.New("max_history_messages must not be negative")
+	}
+
+	if config.Candidates < 1 || config.Candidates > aili.MaxCandidates {
+		return nil, fmt.Errorf("candidates must be between 1 and %d", aili.MaxCandidates)
+	}
+
+	if err := validateCandidateSelection(config.CandidateSelection); err != nil {
+		return nil, err
+	}
 
-	if config.GroqAPIKey == "" {
-		return nil, errors.New("GroqAPIKey is missing in the config file")
+	switch config.TruncationStrategy {
+	case "drop", "note", "summarize":
+	default:
+		return nil, fmt.Errorf("invalid truncation_strategy %q, expected \"drop\", \"note\" or \"summarize\"", config.TruncationStrategy)
+	}
+	switch config.ResponseFormat {
+	case "", "text", "json_object":
+	default:
+		return nil, fmt.Errorf("invalid response_format %q, expected \"text\" or \"json_object\"", config.ResponseFormat)
+	}
+	if config.SummarizeThreshold < 0 {
+		return nil, errors.New("summarize_threshold_tokens must not be negative")
+	}
+	if config.SearchProvider != "" {
+		if !slices.Contains(searchProviders, config.SearchProvider) {
+			return nil, fmt.Errorf("invalid search_provider %q, expected \"searxng\", \"brave\" or \"bing\"", config.SearchProvider)
+		}
+		if config.SearchProvider == "searxng" && config.SearchURL == "" {
+			return nil, errors.New("search_provider searxng requires search_url")
+		}
+		if config.SearchProvider != "searxng" && config.SearchAPIKey == "" {
+			return nil, fmt.Errorf("search_provider %s requires search_api_key", config.SearchProvider)
+		}
+	}
+	if config.SearchResults <= 0 || config.SearchResults > maxSearchResults {
+		return nil, fmt.Errorf("search_results must be between 1 and %d", maxSearchResults)
+	}
+	if err := config.loadResponseSchema(); err != nil {
+		return nil, err
 	}
 
 	return &config, nil
 }
 
-func newAPIClient(apiKey string) *APIClient {
-	return &APIClient{
-		httpClient: &http.Client{
-			Timeout: time.Second * timeoutSeconds,
-			Transport: &http.Transport{
-				TLSClientConfig:     &tls.Config{MinVersion: tls.VersionTLS12},
-				MaxIdleConns:        100,
-				MaxConnsPerHost:     100,
-				IdleConnTimeout:     90 * time.Second,
-				DisableCompression:  true,
-				ForceAttemptHTTP2:   true,
-				MaxIdleConnsPerHost: 100,
-			},
-		},
-		apiKey:      apiKey,
-		rateLimiter: time.NewTicker(time.Second / requestsPerSecond),
+func validateSampling(temperature, topP float64, maxOutputTokens int) error {
+	if temperature < 0 || temperature > 2 {
+		return errors.New("temperature must be between 0 and 2")
+	}
+	if topP <= 0 || topP > 1 {
+		return errors.New("top_p must be greater than 0 and at most 1")
+	}
+	if maxOutputTokens <= 0 {
+		return errors.New("max_tokens must be positive")
+	}
+	return nil
+}
+
+func validateCandidateSelection(criterion string) error {
+	switch criterion {
+	case "first", "longest", "shortest":
+		return nil
+	}
+	if pattern, found := strings.CutPrefix(criterion, "regex:"); found {
+		if _, err := regexp.Compile(pattern); err != nil {
+			return fmt.Errorf("invalid candidate_selection regex: %w", err)
+		}
+		return nil
+	}
+	return fmt.Errorf("invalid candidate_selection %q, expected first, longest, shortest or regex:<pattern>", criterion)
+}
+
+func newAPIClient(config *Config) (*APIClient, error) {
+	apiClient := &APIClient{config: config, keys: newKeyPool(config)}
+	client, err := aili.NewClient(apiClient.settings())
+	if err != nil {
+		return nil, err
 	}
+	apiClient.Client = client
+	client.Prepare = apiClient.prepareHistory
+	client.ResolveModel = apiClient.modelFor
+	client.OnResponse = apiClient.recordRateLimits
+	client.Failover = apiClient.failover
+	client.WaitRateLimit = func(ctx context.Context, wait time.Duration) error {
+		return waitWithCountdown(ctx, "Rate limited, resuming", wait)
+	}
+	return apiClient, nil
 }
 
-func newConversation() (*Conversation, error) {
-	systemPrompt, err := loadSystemPrompt()
+func newConversation(config *Config, systemPromptPath string) (*Conversation, error) {
+	systemPrompt, err := resolveSystemPrompt(config, systemPromptPath)
 	if err != nil {
 		return nil, fmt.Errorf("failed to load system prompt: %w", err)
 	}
 
+	tokens := len(strings.Fields(systemPrompt))
 	return &Conversation{
-		History:    []Message{{Role: "system", Content: systemPrompt, Timestamp: time.Now()}},
-		tokenCount: len(strings.Fields(systemPrompt)),
+		History:    []Message{{Role: "system", Content: systemPrompt, Timestamp: time.Now(), Tokens: tokens}},
+		tokenCount: tokens,
 	}, nil
 }
 
-func loadSystemPrompt() (string, error) {
-	data, err := os.ReadFile(systemPromptFile)
+func buildContextMessage(patterns []string, budget int) (string, error) {
+	var builder strings.Builder
+	seen := make(map[string]bool)
+	remaining := budget
+
+	for _, pattern := range patterns {
+		matches, err := filepath.Glob(pattern)
+		if err != nil {
+			return "", fmt.Errorf("invalid pattern %q: %w", pattern, err)
+		}
+
+		for _, path := range matches {
+			if seen[path] {
+				continue
+			}
+			seen[path] = true
+
+			info, err := os.Stat(path)
+			if err != nil || info.IsDir() {
+				continue
+			}
+			data, err := os.ReadFile(path)
+			if err != nil {
+				log.Printf("Skipping context file %s: %v", path, err)
+				continue
+			}
+
+			tokens := len(strings.Fields(string(data)))
+			if tokens > remaining {
+				log.Printf("Skipping context file %s: %d tokens exceeds the remaining budget of %d", path, tokens, remaining)
+				continue
+			}
+			remaining -= tokens
+			fmt.Fprintf(&builder, "File: %s\n```\n%s\n```\n\n", path, strings.TrimRight(string(data), "\n"))
+		}
+	}
+
+	if builder.Len() == 0 {
+		return "", nil
+	}
+	return "Project context files:\n\n" + strings.TrimSpace(builder.String()), nil
+}
+
+func resolveSystemPrompt(config *Config, path string) (string, error) {
+	if config.SystemPrompt != "" && path == systemPromptFile {
+		return config.SystemPrompt, nil
+	}
+	return loadSystemPrompt(path)
+}
+
+func loadSystemPrompt(path string) (string, error) {
+	if path == "-" {
+		data, err := io.ReadAll(os.Stdin)
+		if err != nil {
+			return "", fmt.Errorf("failed to read system prompt from stdin: %w", err)
+		}
+		return strings.TrimSpace(string(data)), nil
+	}
+
+	data, err := os.ReadFile(path)
 	if err != nil {
 		return "", fmt.Errorf("failed to read system prompt file: %w", err)
 	}
 	return string(data), nil
 }
 
-func printWelcomeMessage() {
+func printWelcomeMessage(model string) {
+	if !theme.Interactive {
+		fmt.Printf("Model: %s\n", model)
+		return
+	}
 	clearScreen()
-	width, _, _ := term.GetSize(int(os.Stdout.Fd()))
+	width, _, err := term.GetSize(int(outputFile().Fd()))
 	welcomeMsg := "Welcome to the AI Chat!"
+	if err != nil || width < len(welcomeMsg)+4 {
+		width = defaultBoxWidth
+	}
 	border := strings.Repeat("─", width-4)
 
 	fmt.Printf("%s┌%s┐\n", colorCyan, border)
 	fmt.Printf("│%s%s%s│\n", strings.Repeat(" ", (width-len(welcomeMsg)-2)/2), welcomeMsg, strings.Repeat(" ", (width-len(welcomeMsg)-1)/2))
 	fmt.Printf("└%s┘%s\n", border, colorReset)
-	fmt.Printf("%sType '%s' to exit the program.%s\n\n", colorBlue, exitCommand, colorReset)
+	fmt.Printf("%sModel: %s%s\n", colorCyan, model, colorReset)
+	fmt.Printf("%sType '/help' for commands, %s for multi-line input or '%s' to exit the program.%s\n\n", colorBlue, multilineDelimiter, exitCommand, colorReset)
 }
 
-func runChatLoop(apiClient *APIClient, conversation *Conversation) error {
+func runChatLoop(session *ChatSession) error {
 	ctx, cancel := context.WithCancel(context.Background())
 	defer cancel()
 
 	g, ctx := errgroup.WithContext(ctx)
 
 	g.Go(func() error {
-		return handleInterrupt(ctx)
+		return handleInterrupt(ctx, session)
 	})
 
 	g.Go(func() error {
-		return processChatInputLoop(ctx, apiClient, conversation)
+		if err := processChatInputLoop(ctx, session, newInputReader(os.Stdin)); err != nil {
+			return err
+		}
+		return errInterrupted
 	})
 
-	return g.Wait()
+	if term.IsTerminal(int(os.Stdin.Fd())) && theme.Interactive {
+		fmt.Print(enableBracketedPaste)
+		defer fmt.Print(disableBracketedPaste)
+	}
+
+	err := g.Wait()
+	if len(session.usage) > 0 {
+		printSpendSummary(session.conversation)
+	}
+	if err != nil && !errors.Is(err, errInterrupted) {
+		return err
+	}
+	return nil
 }
 
-func handleInterrupt(ctx context.Context) error {
+func handleInterrupt(ctx context.Context, session *ChatSession) error {
 	sigChan := make(chan os.Signal, 1)
 	signal.Notify(sigChan, os.Interrupt, syscall.SIGTERM)
+	defer signal.Stop(sigChan)
 
-	select {
-	case <-sigChan:
-		fmt.Printf("\n%sReceived interrupt signal. Exiting...%s\n", colorYellow, colorReset)
+	for {
+		select {
+		case sig := <-sigChan:
+			if sig == os.Interrupt && session.cancelInFlight() {
+				fmt.Printf("\n%sCancelling response...%s\n", colorYellow, colorReset)
+				continue
+			}
+			fmt.Printf("\n%sReceived interrupt signal. Exiting...%s\n", colorYellow, colorReset)
+			return errInterrupted
+		case <-ctx.Done():
+			return ctx.Err()
+		}
+	}
+}
+
+func (s *ChatSession) beginTurn(ctx context.Context) (context.Context, func()) {
+	turnCtx, cancel := context.WithCancel(ctx)
+	s.turnMu.Lock()
+	s.cancelTurn = cancel
+	s.turnMu.Unlock()
+
+	return turnCtx, func() {
+		s.turnMu.Lock()
+		s.cancelTurn = nil
+		s.turnMu.Unlock()
+		cancel()
+	}
+}
+
+func (s *ChatSession) cancelInFlight() bool {
+	s.turnMu.Lock()
+	defer s.turnMu.Unlock()
+	if s.cancelTurn == nil {
+		return false
+	}
+	s.cancelTurn()
+	s.cancelTurn = nil
+	return true
+}
+
+func processChatInputLoop(ctx context.Context, session *ChatSession, reader *InputReader) error {
+	session.input = reader
+	if session.options.File != "" {
+		if err := session.attachFile(ctx, session.options.File); err != nil {
+			fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		}
+	}
+	for {
+		select {
+		case <-ctx.Done():
+			return ctx.Err()
+		default:
+			if err := processChatInput(ctx, reader, session); err != nil {
+				if errors.Is(err, io.EOF) {
+					return nil
+				}
+				return err
+			}
+		}
+	}
+}
+
+func processChatInput(ctx context.Context, reader *InputReader, session *ChatSession) error {
+	userInput := getUserInput(ctx, reader, time.Duration(session.config.BatchWindowMs)*time.Millisecond, session.options.Multiline)
+	if userInput == "" {
 		return nil
-	case <-ctx.Done():
-		return ctx.Err()
 	}
+	if strings.EqualFold(userInput, exitCommand) {
+		fmt.Printf("%sGoodbye!%s\n", colorYellow, colorReset)
+		return io.EOF
+	}
+
+	if strings.HasPrefix(userInput, "/") {
+		return dispatchCommand(ctx, session, userInput)
+	}
+
+	if strings.HasPrefix(userInput, "@@scratch:") {
+		return handleAskCommand(ctx, session, strings.TrimSpace(strings.TrimPrefix(userInput, "@@scratch:")))
+	}
+
+	model := session.model
+	if strings.HasPrefix(userInput, "@@") {
+		overrideModel, prompt, err := parseModelOverride(userInput, session.config.ModelAliases)
+		if err != nil {
+			fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+			return nil
+		}
+		model, userInput = overrideModel, prompt
+	}
+
+	return sendChatTurn(ctx, session, userInput, model)
+}
+
+func sendChatTurn(ctx context.Context, session *ChatSession, userInput, model string) error {
+	if !session.allowRequest() {
+		return nil
+	}
+	userInput = session.withAttachments(userInput)
+	session.alternatives = nil
+	session.conversation.appendMessage(Message{Role: "user", Content: withStoredUserSuffix(session.config, userInput), Images: session.takeImages(), Timestamp: time.Now()})
+	return respond(ctx, session, userInput, model)
+}
+
+func respond(ctx context.Context, session *ChatSession, userInput, model string) error {
+	apiClient, conversation := session.apiClient, session.conversation
+	session.summarizeHistory(ctx)
+
+	history := conversation.getHistory()
+	if session.pendingNote != "" {
+		history = withTurnNote(history, session.pendingNote)
+		session.pendingNote = ""
+	}
+	if note := session.retrieve(ctx, userInput); note != "" {
+		history = withTurnNote(history, note)
+	}
+	promptTokens := countTokens(apiClient.prepareHistory(history, model))
+	startTime := time.Now()
+	turnCtx, endTurn := session.beginTurn(ctx)
+	completion, model, fallback, err := session.completeTurn(turnCtx, history, model)
+	endTurn()
+	elapsed := time.Since(startTime)
+	aiResponse := completion.Content
+	cancelled := errors.Is(err, errStreamCancelled)
+	if err != nil && !cancelled {
+		printResponseError(err)
+		return nil
+	}
+
+	if cancelled {
+		if aiResponse != "" {
+			session.printResponse("AI", model, fallback, aiResponse)
+			conversation.appendMessage(Message{Role: "assistant", Content: aiResponse, Timestamp: time.Now(), Model: model, Cancelled: true})
+		} else {
+			conversation.dropLastExchange(false)
+		}
+		printTruncationIndicator(session.config.TruncationIndicator)
+		session.recordUsage(model, promptTokens, len(strings.Fields(aiResponse)), elapsed)
+	} else {
+		aiResponse = session.postProcess(aiResponse)
+
+		completionTokens := len(strings.Fields(aiResponse))
+		session.printResponse("AI", model, fallback, renderForDisplay(session.config, aiResponse))
+		if session.config.UsageFooter {
+			printUsageFooter(promptTokens, completionTokens, elapsed)
+		}
+		conversation.appendMessage(Message{Role: "assistant", Content: aiResponse, Timestamp: time.Now(), Model: model})
+		session.recordUsage(model, promptTokens, completionTokens, elapsed)
+		session.offerAnnotatedBlocks(ctx, aiResponse)
+		if session.options.Speak {
+			session.speakResponse(ctx, aiResponse)
+		}
+	}
+	if session.config.AutosaveBackups > 0 {
+		if err := session.autosave(); err != nil {
+			log.Printf("Failed to autosave conversation: %v", err)
+		}
+	}
+	if session.sessionID != "" {
+		if err := session.persist(); err != nil {
+			log.Printf("Failed to save session %s: %v", session.sessionID, err)
+		}
+	}
+
+	fmt.Println()
+	return nil
+}
+
+func handleOverrideCommand(_ context.Context, session *ChatSession, _ string) error {
+	session.budgetOverride = true
+	fmt.Printf("%sRequest limit overridden for the rest of this session.%s\n", colorYellow, colorReset)
+	return nil
+}
+
+func handleStreamCommand(_ context.Context, session *ChatSession, mode string) error {
+	switch mode {
+	case "on":
+		session.apiClient.Stream = true
+	case "off":
+		session.apiClient.Stream = false
+	case "":
+	default:
+		fmt.Printf("%sUsage: /stream on|off%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	mode = "buffered"
+	if session.apiClient.Stream {
+		mode = "streaming"
+	}
+	fmt.Printf("%sResponse mode: %s%s\n", colorGreen, mode, colorReset)
+	return nil
+}
+
+func handleNoteCommand(_ context.Context, session *ChatSession, note string) error {
+	if note == "" {
+		if session.pendingNote != "" {
+			fmt.Printf("%sPending note: %s%s\n", colorCyan, session.pendingNote, colorReset)
+		}
+		fmt.Printf("%sUsage: /note <text>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	session.pendingNote = note
+	fmt.Printf("%sNote will be sent with the next message only.%s\n", colorGreen, colorReset)
+	return nil
+}
+
+func withTurnNote(history []Message, note string) []Message {
+	last := len(history) - 1
+	noted := make([]Message, 0, len(history)+1)
+	noted = append(noted, history[:last]...)
+	noted = append(noted, Message{Role: "system", Content: note, Timestamp: time.Now()})
+	return append(noted, history[last])
+}
+
+func handleAskCommand(ctx context.Context, session *ChatSession, prompt string) error {
+	if prompt == "" {
+		fmt.Printf("%sUsage: /ask <prompt>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	if !session.allowRequest() {
+		return nil
+	}
+
+	history := append(session.conversation.getHistory(), Message{Role: "user", Content: withStoredUserSuffix(session.config, prompt), Timestamp: time.Now()})
+	promptTokens := countTokens(session.apiClient.prepareHistory(history, session.model))
+	startTime := time.Now()
+	turnCtx, endTurn := session.beginTurn(ctx)
+	completion, model, fallback, err := session.askWithFallback(turnCtx, history, session.model)
+	endTurn()
+	aiResponse := completion.Content
+	if errors.Is(err, errStreamCancelled) {
+		printTruncationIndicator(session.config.TruncationIndicator)
+		return nil
+	}
+	if err != nil {
+		printResponseError(err)
+		return nil
+	}
+
+	aiResponse = session.postProcess(aiResponse)
+
+	session.printResponse("AI (scratch)", model, fallback, renderForDisplay(session.config, aiResponse))
+	session.recordUsage(model, promptTokens, len(strings.Fields(aiResponse)), time.Since(startTime))
+
+	fmt.Println()
+	return nil
+}
+
+func parseModelOverride(userInput string, aliases map[string]string) (string, string, error) {
+	alias, prompt, found := strings.Cut(strings.TrimPrefix(userInput, "@@"), ":")
+	if !found {
+		return "", "", errors.New("usage: @@<alias>: <prompt>")
+	}
+
+	alias = strings.TrimSpace(alias)
+	model, ok := aliases[alias]
+	if !ok {
+		return "", "", fmt.Errorf("unknown model alias %q, message not sent", alias)
+	}
+
+	prompt = strings.TrimSpace(prompt)
+	if prompt == "" {
+		return "", "", errors.New("usage: @@<alias>: <prompt>")
+	}
+
+	return model, prompt, nil
+}
+
+func (s *ChatSession) allowRequest() bool {
+	limit := s.config.MaxRequests
+	if limit == 0 || s.budgetOverride {
+		s.requestCount++
+		return true
+	}
+	if s.requestCount >= limit {
+		fmt.Printf("%sRequest limit of %d reached for this session. Use /override to continue or restart.%s\n", colorRed, limit, colorReset)
+		return false
+	}
+
+	s.requestCount++
+	fmt.Printf("%sRequests remaining this session: %d/%d%s\n", colorDim, limit-s.requestCount, limit, colorReset)
+	return true
+}
+
+func (s *ChatSession) postProcess(aiResponse string) string {
+	if s.config.StripEchoedSystem {
+		aiResponse = stripEchoedSystemPrompt(aiResponse, s.conversation.systemPrompt())
+	}
+	if s.config.NormalizeWhitespace {
+		aiResponse = normalizeWhitespace(aiResponse)
+	}
+	return aiResponse
+}
+
+func (s *ChatSession) recordUsage(model string, promptTokens, completionTokens int, duration time.Duration) {
+	usage, ok := s.usage[model]
+	if !ok {
+		usage = &ModelUsage{}
+		s.usage[model] = usage
+	}
+	cost := estimateCost(s.config, model, promptTokens, completionTokens)
+	usage.Turns++
+	usage.PromptTokens += promptTokens
+	usage.CompletionTokens += completionTokens
+	usage.Duration += duration
+	usage.Cost += cost
+	s.conversation.addCost(cost)
+	if err := recordSpend(model, promptTokens, completionTokens, cost); err != nil {
+		log.Printf("Failed to update cost ledger: %v", err)
+	}
+}
+
+func handlePingCommand(ctx context.Context, session *ChatSession, _ string) error {
+	result, err := session.apiClient.ping(ctx)
+	if err != nil {
+		fmt.Printf("%sPing failed: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+
+	connect := result.Connect.Round(time.Millisecond).String()
+	if result.Reused {
+		connect = "reused connection"
+	}
+	auth := colorGreen + "ok" + colorReset
+	if !result.Authorized {
+		auth = fmt.Sprintf("%sfailed (status %d)%s", colorRed, result.StatusCode, colorReset)
+	}
+	fmt.Printf("%sPing:%s connect %s, total %v, auth %s\n", colorCyan, colorReset, connect, result.Total.Round(time.Millisecond), auth)
+	return nil
+}
+
+func handleStatsCommand(_ context.Context, session *ChatSession, _ string) error {
+	if len(session.usage) == 0 {
+		fmt.Printf("%sNo requests made this session yet.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	models := make([]string, 0, len(session.usage))
+	for model := range session.usage {
+		models = append(models, model)
+	}
+	sort.Strings(models)
+
+	fmt.Printf("%sSession Usage:%s\n", colorCyan, colorReset)
+	for _, model := range models {
+		usage := session.usage[model]
+		fmt.Printf("%s%s:%s %d turns, %d prompt tokens, %d completion tokens, %v total time, ~%s\n",
+			colorYellow, model, colorReset, usage.Turns, usage.PromptTokens, usage.CompletionTokens, usage.Duration.Round(time.Millisecond), formatCost(usage.Cost))
+	}
+	printSpendSummary(session.conversation)
+	return nil
+}
+
+func autosavePath(index int) string {
+	return fmt.Sprintf("%s.%d.json", autosaveName, index)
+}
+
+func (s *ChatSession) autosave() error {
+	backups := s.config.AutosaveBackups
+	if err := os.Remove(autosavePath(backups)); err != nil && !errors.Is(err, os.ErrNotExist) {
+		return fmt.Errorf("failed to remove oldest backup: %w", err)
+	}
+	for i := backups - 1; i >= 1; i-- {
+		if err := os.Rename(autosavePath(i), autosavePath(i+1)); err != nil && !errors.Is(err, os.ErrNotExist) {
+			return fmt.Errorf("failed to rotate backup %s: %w", autosavePath(i), err)
+		}
+	}
+
+	var settings *SessionSettings
+	if s.config.SaveSettings {
+		settings = s.currentSettings()
+	}
+	return writeHistoryFile(autosavePath(1), s.conversation.getHistory(), settings)
+}
+
+func restoreAutosave(session *ChatSession) {
+	for i := 1; i <= session.config.AutosaveBackups; i++ {
+		path := autosavePath(i)
+		restored, settings, err := loadConversation(path)
+		if errors.Is(err, os.ErrNotExist) {
+			continue
+		}
+		if err != nil {
+			log.Printf("Skipping invalid backup %s: %v", path, err)
+			continue
+		}
+
+		session.conversation.replaceWith(restored)
+		if settings != nil && !session.ignoreSettings {
+			session.applySettings(settings)
+		}
+		fmt.Printf("%sRestored session from %s%s\n", colorGreen, path, colorReset)
+		printConversationSummary(session.conversation)
+		return
+	}
+}
+
+func handleSaveCommand(_ context.Context, session *ChatSession, _ string) error {
+	var settings *SessionSettings
+	if session.config.SaveSettings {
+		settings = session.currentSettings()
+	}
+	if err := saveConversation(session.conversation, session.config.ExportTokenCounts, settings); err != nil {
+		fmt.Printf("%sError saving conversation: %v%s\n", colorRed, err, colorReset)
+	}
+	return nil
+}
+
+func handleExportJSONLCommand(_ context.Context, session *ChatSession, path string) error {
+	if path == "" {
+		fmt.Printf("%sUsage: /export-jsonl <path>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	if err := appendFineTuningRecord(path, session.conversation.getHistory()); err != nil {
+		fmt.Printf("%sError exporting conversation: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	fmt.Printf("%sConversation appended to %s%s\n", colorGreen, path, colorReset)
+	return nil
+}
+
+func handleLoadCommand(_ context.Context, session *ChatSession, filename string) error {
+	if filename == "" {
+		fmt.Printf("%sUsage: /load <filename>%s\n", colorYellow, colorReset)
+		return nil
+	}
+	loadedConversation, settings, err := loadConversation(filename)
+	if err != nil {
+		fmt.Printf("%sError loading conversation: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	session.conversation.replaceWith(loadedConversation)
+	if settings != nil && !session.ignoreSettings {
+		session.applySettings(settings)
+	}
+	printConversationSummary(session.conversation)
+	return nil
+}
+
+func handleMergeCommand(_ context.Context, session *ChatSession, args string) error {
+	filename, separator, _ := strings.Cut(args, " ")
+	if filename == "" {
+		fmt.Printf("%sUsage: /merge <filename> [separator]%s\n", colorYellow, colorReset)
+		return nil
+	}
+	merged, _, err := loadConversation(filename)
+	if err != nil {
+		fmt.Printf("%sError merging conversation: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+
+	count := session.conversation.merge(merged.History, strings.TrimSpace(separator))
+	fmt.Printf("%sMerged %d messages from %s%s\n", colorGreen, count, filename, colorReset)
+	printConversationSummary(session.conversation)
+	return nil
+}
+
+func (s *ChatSession) currentSettings() *SessionSettings {
+	return &SessionSettings{Model: s.model, SystemPrompt: s.conversation.systemPrompt(), Parent: s.parentID, BranchPoint: s.branchPoint}
+}
+
+func (s *ChatSession) applySettings(settings *SessionSettings) {
+	if settings.Model != "" && settings.Model != s.model {
+		s.model = settings.Model
+		fmt.Printf("%sRestored model: %s%s\n", colorGreen, s.model, colorReset)
+	}
+	if settings.SystemPrompt != "" && s.conversation.setSystemPrompt(settings.SystemPrompt) {
+		fmt.Printf("%sRestored system prompt from saved session%s\n", colorGreen, colorReset)
+	}
+}
+
+func handleCheckpointCommand(_ context.Context, session *ChatSession, name string) error {
+	if name == "" {
+		fmt.Printf("%sUsage: /checkpoint <name>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	history := session.conversation.getHistory()
+	session.checkpoints[name] = history
+	fmt.Printf("%sSaved checkpoint '%s' with %d messages%s\n", colorGreen, name, len(history), colorReset)
+	return nil
+}
+
+func handleRestoreCommand(_ context.Context, session *ChatSession, name string) error {
+	if name == "" {
+		printCheckpoints(session)
+		return nil
+	}
+
+	history, ok := session.checkpoints[name]
+	if !ok {
+		fmt.Printf("%sUnknown checkpoint '%s'%s\n", colorRed, name, colorReset)
+		printCheckpoints(session)
+		return nil
+	}
+
+	restored := append([]Message(nil), history...)
+	session.conversation.replaceWith(&Conversation{History: restored, tokenCount: countTokens(restored)})
+	fmt.Printf("%sRestored checkpoint '%s'%s\n", colorGreen, name, colorReset)
+	printConversationSummary(session.conversation)
+	return nil
+}
+
+func printCheckpoints(session *ChatSession) {
+	if len(session.checkpoints) == 0 {
+		fmt.Printf("%sNo checkpoints saved. Use /checkpoint <name> to create one.%s\n", colorYellow, colorReset)
+		return
+	}
+
+	names := make([]string, 0, len(session.checkpoints))
+	for name := range session.checkpoints {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+
+	fmt.Printf("%sCheckpoints:%s\n", colorCyan, colorReset)
+	for _, name := range names {
+		fmt.Printf("  %s (%d messages)\n", name, len(session.checkpoints[name]))
+	}
+}
+
+func handleTokensOfCommand(_ context.Context, _ *ChatSession, text string) error {
+	verbose := false
+	if rest, found := strings.CutPrefix(text, "-v "); found {
+		verbose, text = true, strings.TrimSpace(rest)
+	}
+	if text == "" {
+		fmt.Printf("%sUsage: /tokens-of [-v] <text>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	tokens := strings.Fields(text)
+	fmt.Printf("%s%d tokens%s\n", colorCyan, len(tokens), colorReset)
+	if verbose {
+		for i, token := range tokens {
+			fmt.Printf("  %d: %q\n", i, token)
+		}
+	}
+	return nil
+}
+
+func handleConfigCommand(_ context.Context, session *ChatSession, _ string) error {
+	return printEffectiveConfig(session.config, session.model)
+}
+
+func printEffectiveConfig(config *Config, model string) error {
+	effective := *config
+	effective.GroqAPIKey = redactSecret(effective.GroqAPIKey)
+	effective.OpenAIAPIKey = redactSecret(effective.OpenAIAPIKey)
+	effective.AnthropicAPIKey = redactSecret(effective.AnthropicAPIKey)
+	effective.SearchAPIKey = redactSecret(effective.SearchAPIKey)
+	effective.APIKeys = make([]APIKeyConfig, len(config.APIKeys))
+	for i, entry := range config.APIKeys {
+		entry.Key = redactSecret(entry.Key)
+		effective.APIKeys[i] = entry
+	}
+	effective.MCPServers = make(map[string]MCPServerConfig, len(config.MCPServers))
+	for name, server := range config.MCPServers {
+		env := make(map[string]string, len(server.Env))
+		for key, value := range server.Env {
+			env[key] = redactSecret(value)
+		}
+		server.Env = env
+		effective.MCPServers[name] = server
+	}
+	data, err := yaml.Marshal(&effective)
+	if err != nil {
+		return fmt.Errorf("failed to marshal configuration: %w", err)
+	}
+
+	fmt.Printf("%sEffective configuration:%s\n", colorCyan, colorReset)
+	fmt.Printf("model: %s\n", model)
+	fmt.Print(string(data))
+	return nil
+}
+
+func redactSecret(secret string) string {
+	if len(secret) <= 12 {
+		return strings.Repeat("*", len(secret))
+	}
+	return strings.Repeat("*", len(secret)-4) + secret[len(secret)-4:]
+}
+
+func handleReloadCommand(_ context.Context, session *ChatSession, _ string) error {
+	newConfig, err := loadConfig(session.options.ConfigPath)
+	if err == nil {
+		err = session.options.applyOverrides(newConfig)
+	}
+	if err != nil {
+		fmt.Printf("%sReload failed, keeping current settings: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	systemPrompt, err := resolveSystemPrompt(newConfig, session.systemPromptPath)
+	if err != nil {
+		fmt.Printf("%sReload failed, keeping current settings: %v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+
+	changes := describeConfigChanges(session.config, newConfig)
+	if session.conversation.setSystemPrompt(systemPrompt) {
+		changes = append(changes, "system prompt updated")
+	}
+
+	if session.config.ConnectTimeout != newConfig.ConnectTimeout {
+		session.apiClient.HTTPClient = aili.NewHTTPClient(time.Duration(newConfig.ConnectTimeout) * time.Second)
+	}
+	if session.config.Provider != newConfig.Provider || session.config.OllamaModel != newConfig.OllamaModel {
+		session.model = ""
+	}
+	session.config = newConfig
+	session.apiClient.config = newConfig
+	session.apiClient.keys = newKeyPool(newConfig)
+	session.apiClient.Provider = session.apiClient.activeProvider()
+	if session.model == "" {
+		session.model = session.apiClient.Provider.DefaultModel()
+	}
+	if session.persona != "" {
+		if err := session.applyPersona(session.persona); err != nil {
+			changes = append(changes, fmt.Sprintf("persona %s no longer configured", session.persona))
+			session.persona = ""
+		}
+	}
+	session.conversation.applyRetention(newConfig)
+
+	if len(changes) == 0 {
+		fmt.Printf("%sConfiguration reloaded, no changes.%s\n", colorGreen, colorReset)
+		return nil
+	}
+	fmt.Printf("%sConfiguration reloaded:%s\n", colorGreen, colorReset)
+	for _, change := range changes {
+		fmt.Printf("  - %s\n", change)
+	}
+	return nil
+}
+
+func describeConfigChanges(oldConfig, newConfig *Config) []string {
+	var changes []string
+	if oldConfig.Provider != newConfig.Provider {
+		changes = append(changes, fmt.Sprintf("provider: %s -> %s", oldConfig.Provider, newConfig.Provider))
+	}
+	if oldConfig.OllamaURL != newConfig.OllamaURL || oldConfig.OllamaModel != newConfig.OllamaModel {
+		changes = append(changes, fmt.Sprintf("ollama: %s (%s) -> %s (%s)", oldConfig.OllamaURL, oldConfig.OllamaModel, newConfig.OllamaURL, newConfig.OllamaModel))
+	}
+	if oldConfig.GroqAPIKey != newConfig.GroqAPIKey {
+		changes = append(changes, "groq_api_key updated")
+	}
+	if oldConfig.OpenAIAPIKey != newConfig.OpenAIAPIKey {
+		changes = append(changes, "openai_api_key updated")
+	}
+	if oldConfig.AnthropicAPIKey != newConfig.AnthropicAPIKey {
+		changes = append(changes, "anthropic_api_key updated")
+	}
+	if !slices.Equal(oldConfig.APIKeys, newConfig.APIKeys) || oldConfig.KeyCooldown != newConfig.KeyCooldown {
+		changes = append(changes, fmt.Sprintf("api_keys: %d keys, %ds cooldown", len(newConfig.APIKeys), newConfig.KeyCooldown))
+	}
+	if oldConfig.Temperature != newConfig.Temperature {
+		changes = append(changes, fmt.Sprintf("temperature: %g -> %g", oldConfig.Temperature, newConfig.Temperature))
+	}
+	if oldConfig.TopP != newConfig.TopP {
+		changes = append(changes, fmt.Sprintf("top_p: %g -> %g", oldConfig.TopP, newConfig.TopP))
+	}
+	if oldConfig.MaxTokens != newConfig.MaxTokens {
+		changes = append(changes, fmt.Sprintf("max_tokens: %d -> %d", oldConfig.MaxTokens, newConfig.MaxTokens))
+	}
+	if oldConfig.ContextWindow != newConfig.ContextWindow {
+		changes = append(changes, fmt.Sprintf("context_window: %d -> %d", oldConfig.ContextWindow, newConfig.ContextWindow))
+	}
+	if !maps.EqualFunc(oldConfig.Personas, newConfig.Personas, Persona.equal) {
+		changes = append(changes, "personas updated")
+	}
+	if oldConfig.MaxAttempts != newConfig.MaxAttempts || oldConfig.RetryBackoffMs != newConfig.RetryBackoffMs {
+		changes = append(changes, fmt.Sprintf("retries: %d attempts, %dms backoff -> %d attempts, %dms backoff",
+			oldConfig.MaxAttempts, oldConfig.RetryBackoffMs, newConfig.MaxAttempts, newConfig.RetryBackoffMs))
+	}
+	if oldConfig.ConnectTimeout != newConfig.ConnectTimeout {
+		changes = append(changes, fmt.Sprintf("connect_timeout_seconds: %d -> %d", oldConfig.ConnectTimeout, newConfig.ConnectTimeout))
+	}
+	if oldConfig.TruncationIndicator != newConfig.TruncationIndicator {
+		changes = append(changes, fmt.Sprintf("truncation_indicator: %q (%s) -> %q (%s)",
+			oldConfig.TruncationIndicator.Text, oldConfig.TruncationIndicator.Color, newConfig.TruncationIndicator.Text, newConfig.TruncationIndicator.Color))
+	}
+	if oldConfig.ShortenURLs != newConfig.ShortenURLs {
+		changes = append(changes, fmt.Sprintf("shorten_urls: %t -> %t", oldConfig.ShortenURLs, newConfig.ShortenURLs))
+	}
+	if oldConfig.Candidates != newConfig.Candidates || oldConfig.CandidateSelection != newConfig.CandidateSelection {
+		changes = append(changes, fmt.Sprintf("candidates: %d (%s) -> %d (%s)",
+			oldConfig.Candidates, oldConfig.CandidateSelection, newConfig.Candidates, newConfig.CandidateSelection))
+	}
+	if oldConfig.MaxHistoryMessages != newConfig.MaxHistoryMessages || oldConfig.AutosavePruned != newConfig.AutosavePruned {
+		changes = append(changes, fmt.Sprintf("max_history_messages: %d (autosave %t) -> %d (autosave %t)",
+			oldConfig.MaxHistoryMessages, oldConfig.AutosavePruned, newConfig.MaxHistoryMessages, newConfig.AutosavePruned))
+	}
+	if oldConfig.RenderMarkdown != newConfig.RenderMarkdown {
+		changes = append(changes, fmt.Sprintf("render_markdown: %t -> %t", oldConfig.RenderMarkdown, newConfig.RenderMarkdown))
+	}
+	if oldConfig.HighlightCode != newConfig.HighlightCode {
+		changes = append(changes, fmt.Sprintf("highlight_code: %t -> %t", oldConfig.HighlightCode, newConfig.HighlightCode))
+	}
+	if oldConfig.EnableTools != newConfig.EnableTools {
+		changes = append(changes, fmt.Sprintf("enable_tools: %t -> %t", oldConfig.EnableTools, newConfig.EnableTools))
+	}
+	if oldConfig.ShellTool != newConfig.ShellTool {
+		changes = append(changes, fmt.Sprintf("shell_tool: %t -> %t", oldConfig.ShellTool, newConfig.ShellTool))
+	}
+	if oldConfig.EmbeddingModel != newConfig.EmbeddingModel || oldConfig.RAGTopK != newConfig.RAGTopK {
+		changes = append(changes, fmt.Sprintf("embeddings: %s (top %d) -> %s (top %d)",
+			oldConfig.EmbeddingModel, oldConfig.RAGTopK, newConfig.EmbeddingModel, newConfig.RAGTopK))
+	}
+	if oldConfig.TTSCommand != newConfig.TTSCommand || oldConfig.TTSModel != newConfig.TTSModel || oldConfig.TTSVoice != newConfig.TTSVoice {
+		changes = append(changes, fmt.Sprintf("speech: %s -> %s", speechBackend(oldConfig), speechBackend(newConfig)))
+	}
+	if oldConfig.TranscriptionModel != newConfig.TranscriptionModel {
+		changes = append(changes, fmt.Sprintf("transcription_model: %q -> %q", oldConfig.TranscriptionModel, newConfig.TranscriptionModel))
+	}
+	if oldConfig.ResponseFormat != newConfig.ResponseFormat || oldConfig.ResponseSchema != newConfig.ResponseSchema {
+		changes = append(changes, fmt.Sprintf("response_format: %q (schema %q) -> %q (schema %q)",
+			oldConfig.ResponseFormat, oldConfig.ResponseSchema, newConfig.ResponseFormat, newConfig.ResponseSchema))
+	}
+	if oldConfig.SearchProvider != newConfig.SearchProvider || oldConfig.SearchURL != newConfig.SearchURL || oldConfig.SearchResults != newConfig.SearchResults {
+		changes = append(changes, fmt.Sprintf("search: %s %q (%d results) -> %s %q (%d results)",
+			oldConfig.SearchProvider, oldConfig.SearchURL, oldConfig.SearchResults, newConfig.SearchProvider, newConfig.SearchURL, newConfig.SearchResults))
+	}
+	if oldConfig.SearchAPIKey != newConfig.SearchAPIKey {
+		changes = append(changes, "search_api_key updated")
+	}
+	if oldConfig.TemplatesDir != newConfig.TemplatesDir {
+		changes = append(changes, fmt.Sprintf("templates_dir: %q -> %q", oldConfig.TemplatesDir, newConfig.TemplatesDir))
+	}
+	if !maps.EqualFunc(oldConfig.MCPServers, newConfig.MCPServers, MCPServerConfig.equal) {
+		changes = append(changes, "mcp_servers updated, restart to reconnect")
+	}
+	if oldConfig.Theme.Preset != newConfig.Theme.Preset || !maps.Equal(oldConfig.Theme.Styles, newConfig.Theme.Styles) {
+		changes = append(changes, "theme updated, restart to apply")
+	}
+	if oldConfig.UsageFooter != newConfig.UsageFooter {
+		changes = append(changes, fmt.Sprintf("usage_footer: %t -> %t", oldConfig.UsageFooter, newConfig.UsageFooter))
+	}
+	if oldConfig.BoxedOutput != newConfig.BoxedOutput {
+		changes = append(changes, fmt.Sprintf("boxed_output: %t -> %t", oldConfig.BoxedOutput, newConfig.BoxedOutput))
+	}
+	if oldConfig.SentenceShading != newConfig.SentenceShading {
+		changes = append(changes, fmt.Sprintf("experimental_sentence_shading: %t -> %t", oldConfig.SentenceShading, newConfig.SentenceShading))
+	}
+	if oldConfig.ShowLiveTPS != newConfig.ShowLiveTPS {
+		changes = append(changes, fmt.Sprintf("show_live_tps: %t -> %t", oldConfig.ShowLiveTPS, newConfig.ShowLiveTPS))
+	}
+	if oldConfig.UserSuffix != newConfig.UserSuffix || oldConfig.StoreUserSuffix != newConfig.StoreUserSuffix {
+		changes = append(changes, fmt.Sprintf("user_suffix: %q -> %q (stored: %t)", oldConfig.UserSuffix, newConfig.UserSuffix, newConfig.StoreUserSuffix))
+	}
+	if oldConfig.StripEchoedSystem != newConfig.StripEchoedSystem {
+		changes = append(changes, fmt.Sprintf("strip_echoed_system: %t -> %t", oldConfig.StripEchoedSystem, newConfig.StripEchoedSystem))
+	}
+	if oldConfig.MaxRequests != newConfig.MaxRequests {
+		changes = append(changes, fmt.Sprintf("max_requests_per_session: %d -> %d", oldConfig.MaxRequests, newConfig.MaxRequests))
+	}
+	if oldConfig.FallbackModel != newConfig.FallbackModel {
+		changes = append(changes, fmt.Sprintf("fallback_model: %q -> %q", oldConfig.FallbackModel, newConfig.FallbackModel))
+	}
+	if !slices.Equal(oldConfig.FallbackModels, newConfig.FallbackModels) {
+		changes = append(changes, fmt.Sprintf("fallback_models: %v -> %v", oldConfig.FallbackModels, newConfig.FallbackModels))
+	}
+	if oldConfig.StreamReconnect != newConfig.StreamReconnect {
+		changes = append(changes, fmt.Sprintf("stream_reconnect: %t -> %t", oldConfig.StreamReconnect, newConfig.StreamReconnect))
+	}
+	if oldConfig.BatchWindowMs != newConfig.BatchWindowMs {
+		changes = append(changes, fmt.Sprintf("batch_window_ms: %d -> %d", oldConfig.BatchWindowMs, newConfig.BatchWindowMs))
+	}
+	if oldConfig.AutosaveBackups != newConfig.AutosaveBackups {
+		changes = append(changes, fmt.Sprintf("autosave_backups: %d -> %d", oldConfig.AutosaveBackups, newConfig.AutosaveBackups))
+	}
+	if oldConfig.SaveSettings != newConfig.SaveSettings {
+		changes = append(changes, fmt.Sprintf("save_session_settings: %t -> %t", oldConfig.SaveSettings, newConfig.SaveSettings))
+	}
+	if oldConfig.ExportTokenCounts != newConfig.ExportTokenCounts {
+		changes = append(changes, fmt.Sprintf("export_token_counts: %t -> %t", oldConfig.ExportTokenCounts, newConfig.ExportTokenCounts))
+	}
+	if oldConfig.NormalizeWhitespace != newConfig.NormalizeWhitespace {
+		changes = append(changes, fmt.Sprintf("normalize_whitespace: %t -> %t", oldConfig.NormalizeWhitespace, newConfig.NormalizeWhitespace))
+	}
+	if oldConfig.TruncationStrategy != newConfig.TruncationStrategy {
+		changes = append(changes, fmt.Sprintf("truncation_strategy: %s -> %s", oldConfig.TruncationStrategy, newConfig.TruncationStrategy))
+	}
+	if oldConfig.SummarizeThreshold != newConfig.SummarizeThreshold {
+		changes = append(changes, fmt.Sprintf("summarize_threshold_tokens: %d -> %d", oldConfig.SummarizeThreshold, newConfig.SummarizeThreshold))
+	}
+	if !maps.Equal(oldConfig.ModelAliases, newConfig.ModelAliases) {
+		changes = append(changes, "model_aliases updated")
+	}
+	if !maps.Equal(oldConfig.FormatCode, newConfig.FormatCode) {
+		changes = append(changes, "format_code updated")
+	}
+	if !maps.Equal(oldConfig.Pricing, newConfig.Pricing) {
+		changes = append(changes, "pricing updated")
+	}
+	return changes
+}
+
+func Ask(ctx context.Context, apiClient *APIClient, history []Message, model string) (Completion, error) {
+	apiClient.Config = apiClient.settings()
+	apiClient.Tools = toolDefinitions(apiClient.tools)
+	apiClient.failovers = 0
+	apiClient.NewProgress = nil
+	if apiClient.Stream && apiClient.config.ShowLiveTPS && term.IsTerminal(int(outputFile().Fd())) {
+		apiClient.NewProgress = newTPSReporter
+		defer setTerminalTitle(terminalTitle)
+	}
+	return aili.Ask(ctx, apiClient.Client, history, model)
+}
+
+func (c *Config) fallbackChain() []string {
+	chain := slices.Clone(c.FallbackModels)
+	if c.FallbackModel != "" && !slices.Contains(chain, c.FallbackModel) {
+		chain = append(chain, c.FallbackModel)
+	}
+	return chain
+}
+
+func (s *ChatSession) askWithFallback(ctx context.Context, history []Message, model string) (Completion, string, bool, error) {
+	completion, err := Ask(ctx, s.apiClient, history, model)
+	tried := []string{model}
+	for _, fallback := range s.config.fallbackChain() {
+		if err == nil || ctx.Err() != nil || !shouldFallback(err) {
+			break
+		}
+		if slices.Contains(tried, fallback) {
+			continue
+		}
+		log.Printf("Model %s failed (%v), falling back to %s", model, err, fallback)
+		model = fallback
+		tried = append(tried, model)
+		completion, err = Ask(ctx, s.apiClient, history, model)
+	}
+	return completion, s.apiClient.modelFor(model), len(tried) > 1, err
+}
+
+func shouldFallback(err error) bool {
+	var apiErr *APIError
+	if errors.As(err, &apiErr) {
+		switch apiErr.StatusCode {
+		case http.StatusUnauthorized, http.StatusForbidden:
+			return false
+		case http.StatusNotFound, http.StatusServiceUnavailable:
+			return true
+		}
+		return apiErr.IsModelUnavailable() || apiErr.Type == "overloaded_error" || strings.Contains(strings.ToLower(apiErr.Message), "overloaded")
+	}
+
+	var netErr net.Error
+	return errors.As(err, &netErr) && netErr.Timeout()
+}
+
+func waitWithCountdown(ctx context.Context, message string, wait time.Duration) error {
+	deadline := time.Now().Add(wait)
+	ticker := time.NewTicker(time.Second)
+	defer ticker.Stop()
+	if !term.IsTerminal(int(os.Stderr.Fd())) {
+		fmt.Fprintf(os.Stderr, "%s in %ds...\n", message, int(wait.Round(time.Second)/time.Second))
+		select {
+		case <-time.After(wait):
+			return nil
+		case <-ctx.Done():
+			return ctx.Err()
+		}
+	}
+
+	for remaining := time.Until(deadline); remaining > 0; remaining = time.Until(deadline) {
+		fmt.Fprintf(os.Stderr, "\r%s%s in %ds...%s ", colorYellow, message, int(remaining.Round(time.Second)/time.Second), colorReset)
+		select {
+		case <-ticker.C:
+		case <-time.After(remaining):
+		case <-ctx.Done():
+			fmt.Fprintln(os.Stderr)
+			return ctx.Err()
+		}
+	}
+	fmt.Fprintf(os.Stderr, "\r%s%s now.%s\x1b[K\n", colorYellow, message, colorReset)
+	return nil
+}
+
+func (c *APIClient) recordRateLimits(header http.Header) {
+	limits := http.Header{}
+	for name, values := range header {
+		lower := strings.ToLower(name)
+		if strings.HasPrefix(lower, "x-ratelimit-") || strings.HasPrefix(lower, "anthropic-ratelimit-") {
+			limits[lower] = values
+		}
+	}
+	if len(limits) > 0 {
+		c.rateLimits, c.limitsAt = limits, time.Now()
+	}
+}
+
+func logRetry(attempt, maxAttempts int, err error, wait time.Duration) {
+	fmt.Fprintf(os.Stderr, "%sRequest failed: %v%s\n", colorYellow, err, colorReset)
+	fmt.Fprintf(os.Stderr, "%sRetrying (%d/%d) in %v...%s\n", colorYellow, attempt, maxAttempts, wait.Round(time.Millisecond), colorReset)
+}
+
+func newInputReader(r io.Reader) *InputReader {
+	scanner := bufio.NewScanner(r)
+	return newLineReader(func() (string, bool) {
+		if !scanner.Scan() {
+			return "", false
+		}
+		return scanner.Text(), true
+	})
+}
+
+func newLineReader(next func() (string, bool)) *InputReader {
+	reader := &InputReader{lines: make(chan string), ready: make(chan struct{}, 1)}
+	go func() {
+		for range reader.ready {
+			line, ok := next()
+			if !ok {
+				break
+			}
+			reader.lines <- line
+		}
+		close(reader.lines)
+	}()
+	return reader
+}
+
+func (r *InputReader) next(ctx context.Context) (string, bool) {
+	return r.nextBefore(ctx, nil)
+}
+
+func (r *InputReader) nextBefore(ctx context.Context, deadline <-chan time.Time) (string, bool) {
+	if r.closed {
+		return "", false
+	}
+	if !r.pending {
+		r.pending = true
+		r.ready <- struct{}{}
+	}
+	select {
+	case line, ok := <-r.lines:
+		r.pending, r.closed = false, !ok
+		return line, ok
+	case <-ctx.Done():
+	case <-deadline:
+	}
+	return "", false
+}
+
+func getUserInput(ctx context.Context, reader *InputReader, batchWindow time.Duration, multiline bool) string {
+	fmt.Printf("%sYou:%s ", colorUser, colorReset)
+	line, ok := reader.next(ctx)
+	if !ok {
+		return exitCommand
+	}
+	if strings.Contains(line, pasteStart) {
+		return readPastedInput(ctx, reader, line)
+	}
+
+	line = strings.TrimSpace(line)
+	if line == editorKey {
+		return "/edit"
+	}
+	if line == multilineDelimiter {
+		return readMultilineInput(ctx, reader, nil, func(next string) bool { return next == multilineDelimiter })
+	}
+	if line == "" || strings.HasPrefix(line, "/") {
+		return line
+	}
+	if multiline {
+		return readMultilineInput(ctx, reader, []string{line}, func(next string) bool { return next == "" })
+	}
+	if batchWindow <= 0 {
+		return line
+	}
+	return batchUserInput(ctx, reader, line, batchWindow)
+}
+
+func (s *ChatSession) prompt(ctx context.Context, question string) (string, bool) {
+	if s.input == nil {
+		return "", false
+	}
+	fmt.Printf("%s%s%s ", colorYellow, question, colorReset)
+	answer, ok := s.input.next(ctx)
+	if !ok {
+		fmt.Println()
+	}
+	return answer, ok
+}
+
+func readPastedInput(ctx context.Context, reader *InputReader, line string) string {
+	lines := []string{strings.Replace(line, pasteStart, "", 1)}
+	for !strings.Contains(lines[len(lines)-1], pasteEnd) {
+		next, ok := reader.next(ctx)
+		if !ok {
+			break
+		}
+		lines = append(lines, next)
+	}
+	lines[len(lines)-1] = strings.Replace(lines[len(lines)-1], pasteEnd, "", 1)
+	return strings.TrimSpace(strings.Join(lines, "\n"))
+}
+
+func readMultilineInput(ctx context.Context, reader *InputReader, lines []string, done func(string) bool) string {
+	for {
+		fmt.Printf("%s...%s ", colorDim, colorReset)
+		next, ok := reader.next(ctx)
+		if !ok {
+			fmt.Println()
+			break
+		}
+		if strings.Contains(next, pasteStart) {
+			lines = append(lines, readPastedInput(ctx, reader, next))
+			continue
+		}
+		if done(strings.TrimSpace(next)) {
+			break
+		}
+		lines = append(lines, next)
+	}
+	return strings.TrimSpace(strings.Join(lines, "\n"))
 }
 
-func processChatInputLoop(ctx context.Context, apiClient *APIClient, conversation *Conversation) error {
-	scanner := bufio.NewScanner(os.Stdin)
+func batchUserInput(ctx context.Context, reader *InputReader, first string, batchWindow time.Duration) string {
+	batched := []string{first}
+	timer := time.NewTimer(batchWindow)
+	defer timer.Stop()
+
 	for {
-		select {
-		case <-ctx.Done():
-			return ctx.Err()
-		default:
-			if err := processChatInput(ctx, scanner, apiClient, conversation); err != nil {
-				if errors.Is(err, io.EOF) {
-					return nil
-				}
-				return err
-			}
+		next, ok := reader.nextBefore(ctx, timer.C)
+		if !ok {
+			return joinBatchedInput(batched)
 		}
+		if next = strings.TrimSpace(next); next != "" {
+			batched = append(batched, next)
+		}
+		timer.Reset(batchWindow)
 	}
 }
 
-func processChatInput(ctx context.Context, scanner *bufio.Scanner, apiClient *APIClient, conversation *Conversation) error {
-	userInput := getUserInput(scanner)
-	if userInput == "" {
-		return nil
-	}
-	if strings.EqualFold(userInput, exitCommand) {
-		fmt.Printf("%sGoodbye!%s\n", colorYellow, colorReset)
-		return io.EOF
+func joinBatchedInput(batched []string) string {
+	if len(batched) > 1 {
+		fmt.Printf("%s(combined %d messages)%s\n", colorDim, len(batched), colorReset)
 	}
+	return strings.Join(batched, "\n")
+}
 
-	if strings.HasPrefix(userInput, "/save") {
-		return handleSaveCommand(conversation)
-	}
+func (c *Conversation) addMessage(role, content string) {
+	c.appendMessage(Message{Role: role, Content: content, Timestamp: time.Now()})
+}
 
-	if strings.HasPrefix(userInput, "/load") {
-		return handleLoadCommand(userInput, conversation)
+func (c *Conversation) appendMessage(msg Message) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	msg.Tokens = len(strings.Fields(msg.Content))
+	c.tokenCount += msg.Tokens
+	c.History = append(c.History, msg)
+	if pruned := c.pruneHistory(); len(pruned) > 0 && c.onPrune != nil {
+		c.onPrune(pruned)
 	}
+}
 
-	conversation.addMessage("user", userInput)
+func (c *Conversation) merge(messages []Message, separator string) int {
+	c.mu.Lock()
+	defer c.mu.Unlock()
 
-	aiResponse, err := getAIResponseWithRetry(ctx, apiClient, conversation)
-	if err != nil {
-		fmt.Printf("%sFailed to get AI response: %v%s\n", colorRed, err, colorReset)
-		return nil
+	systemPrompt := ""
+	if len(c.History) > 0 && c.History[0].Role == "system" {
+		systemPrompt = c.History[0].Content
+	}
+	if separator != "" {
+		c.mergeMessage(Message{Role: "system", Content: separator, Timestamp: time.Now()})
 	}
 
-	fmt.Printf("%sAI:%s ", colorPurple, colorReset)
-	printStreamingResponse(aiResponse)
-	conversation.addMessage("assistant", aiResponse)
-
-	fmt.Println()
-	return nil
-}
+	count := 0
+	for _, msg := range messages {
+		if msg.Role == "system" && msg.Content == systemPrompt {
+			continue
+		}
+		c.mergeMessage(msg)
+		count++
+	}
 
-func handleSaveCommand(conversation *Conversation) error {
-	if err := saveConversation(conversation); err != nil {
-		fmt.Printf("%sError saving conversation: %v%s\n", colorRed, err, colorReset)
+	if pruned := c.pruneHistory(); len(pruned) > 0 && c.onPrune != nil {
+		c.onPrune(pruned)
 	}
-	return nil
+	return count
 }
 
-func handleLoadCommand(userInput string, conversation *Conversation) error {
-	parts := strings.SplitN(userInput, " ", 2)
-	if len(parts) != 2 {
-		fmt.Printf("%sUsage: /load <filename>%s\n", colorYellow, colorReset)
-		return nil
-	}
-	loadedConversation, err := loadConversation(parts[1])
-	if err != nil {
-		fmt.Printf("%sError loading conversation: %v%s\n", colorRed, err, colorReset)
-		return nil
+func (c *Conversation) mergeMessage(msg Message) {
+	msg.Tokens = len(strings.Fields(msg.Content))
+	c.tokenCount += msg.Tokens
+	if last := len(c.History) - 1; last >= 0 && c.History[last].Role == msg.Role && mergeable(c.History[last]) && mergeable(msg) {
+		c.History[last].Content += "\n\n" + msg.Content
+		c.History[last].Tokens += msg.Tokens
+		return
 	}
-	conversation.replaceWith(loadedConversation)
-	printConversationSummary(conversation)
-	return nil
+	c.History = append(c.History, msg)
 }
 
-func getAIResponseWithRetry(ctx context.Context, apiClient *APIClient, conversation *Conversation) (string, error) {
-	var (
-		aiResponse string
-		err        error
-		backoff    = initialBackoff
-	)
-
-	for attempt := 0; attempt < maxRetries; attempt++ {
-		select {
-		case <-apiClient.rateLimiter.C:
-		case <-ctx.Done():
-			return "", ctx.Err()
-		}
+func mergeable(msg Message) bool {
+	return (msg.Role == "user" || msg.Role == "assistant") && len(msg.ToolCalls) == 0 && msg.ToolCallID == "" && len(msg.Images) == 0
+}
 
-		aiResponse, err = getAIResponse(ctx, apiClient, conversation)
-		if err == nil {
-			return aiResponse, nil
-		}
+func (c *Conversation) applyRetention(config *Config) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.maxMessages = config.MaxHistoryMessages
+	c.onPrune = nil
+	if config.AutosavePruned {
+		c.onPrune = autosavePruned
+	}
+}
 
-		log.Printf("Attempt %d failed: %v", attempt+1, err)
+func (c *Conversation) pruneHistory() []Message {
+	if c.maxMessages <= 0 || len(c.History) <= c.maxMessages {
+		return nil
+	}
 
-		if attempt < maxRetries-1 {
-			jitter := time.Duration(rand.Int63n(int64(backoff)))
-			sleepTime := backoff + jitter
-			log.Printf("Retrying in %v", sleepTime)
-			time.Sleep(sleepTime)
-			backoff *= time.Duration(backoffFactor)
+	start := 0
+	if c.History[0].Role == "system" {
+		start = 1
+	}
+	excess := len(c.History) - c.maxMessages
+	if excess > len(c.History)-start {
+		excess = len(c.History) - start
+	}
+	cut := start + excess
+	for cut < len(c.History) && c.History[cut].Role != "user" {
+		cut++
+	}
+	if cut == len(c.History) {
+		cut = start + excess
+		for last := len(c.History) - 1; last >= start; last-- {
+			if c.History[last].Role == "user" {
+				cut = last
+				break
+			}
 		}
 	}
+	excess = cut - start
+	if excess == 0 {
+		return nil
+	}
 
-	return "", fmt.Errorf("failed after %d attempts, last error: %w", maxRetries, err)
+	pruned := append([]Message(nil), c.History[start:start+excess]...)
+	c.History = append(c.History[:start], c.History[start+excess:]...)
+	c.tokenCount -= countTokens(pruned)
+	return pruned
 }
 
-func getUserInput(scanner *bufio.Scanner) string {
-	fmt.Printf("%sYou:%s ", colorGreen, colorReset)
-	if !scanner.Scan() {
-		return exitCommand
+func autosavePruned(pruned []Message) {
+	filename := fmt.Sprintf("pruned_%s.json", time.Now().Format("20060102_150405.000"))
+	if err := writeHistoryFile(filename, pruned, nil); err != nil {
+		log.Printf("Failed to autosave pruned messages: %v", err)
 	}
-	return strings.TrimSpace(scanner.Text())
 }
 
-func (c *Conversation) addMessage(role, content string) {
+func (c *Conversation) setSystemPrompt(prompt string) bool {
 	c.mu.Lock()
 	defer c.mu.Unlock()
-	tokens := len(strings.Fields(content))
-	c.tokenCount += tokens
-	c.History = append(c.History, Message{Role: role, Content: content, Timestamp: time.Now()})
-	c.truncateHistory()
+	if len(c.History) > 0 && c.History[0].Role == "system" {
+		if c.History[0].Content == prompt {
+			return false
+		}
+		c.tokenCount -= c.History[0].Tokens
+		c.History[0].Content = prompt
+		c.History[0].Tokens = len(strings.Fields(prompt))
+	} else {
+		c.History = append([]Message{{Role: "system", Content: prompt, Timestamp: time.Now(), Tokens: len(strings.Fields(prompt))}}, c.History...)
+	}
+	c.tokenCount += c.History[0].Tokens
+	return true
 }
 
-func (c *Conversation) truncateHistory() {
-	for c.tokenCount > maxConversationTokens && len(c.History) > 1 {
-		removedTokens := len(strings.Fields(c.History[1].Content))
-		c.tokenCount -= removedTokens
-		c.History = c.History[2:]
+func (c *Conversation) systemPrompt() string {
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	if len(c.History) > 0 && c.History[0].Role == "system" {
+		return c.History[0].Content
 	}
+	return ""
 }
 
 func (c *Conversation) getHistory() []Message {
@@ -325,169 +2084,523 @@ func (c *Conversation) getHistory() []Message {
 	return append([]Message(nil), c.History...)
 }
 
-func getAIResponse(ctx context.Context, apiClient *APIClient, conversation *Conversation) (string, error) {
-	ctx, cancel := context.WithTimeout(ctx, time.Second*30)
+func (c *APIClient) ping(ctx context.Context) (*PingResult, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
 	defer cancel()
 
-	response, err := apiClient.sendRequest(ctx, conversation)
+	result := &PingResult{}
+	var connectStart time.Time
+	trace := &httptrace.ClientTrace{
+		ConnectStart: func(_, _ string) {
+			connectStart = time.Now()
+		},
+		ConnectDone: func(_, _ string, _ error) {
+			result.Connect = time.Since(connectStart)
+		},
+		TLSHandshakeDone: func(tls.ConnectionState, error) {
+			result.Connect = time.Since(connectStart)
+		},
+		GotConn: func(info httptrace.GotConnInfo) {
+			result.Reused = info.Reused
+		},
+	}
+
+	req, err := c.Provider.NewModelsRequest(httptrace.WithClientTrace(ctx, trace))
 	if err != nil {
-		return "", fmt.Errorf("failed to send request: %w", err)
+		return nil, err
 	}
-	defer response.Body.Close()
 
-	if response.StatusCode != http.StatusOK {
-		body, _ := io.ReadAll(response.Body)
-		return "", fmt.Errorf("API request failed with status %d: %s", response.StatusCode, string(body))
+	start := time.Now()
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return nil, err
 	}
+	io.Copy(io.Discard, response.Body)
+	response.Body.Close()
 
-	return processStreamResponse(response.Body)
+	result.Total = time.Since(start)
+	result.StatusCode = response.StatusCode
+	result.Authorized = response.StatusCode != http.StatusUnauthorized && response.StatusCode != http.StatusForbidden
+	return result, nil
 }
 
-func (c *APIClient) sendRequest(ctx context.Context, conversation *Conversation) (*http.Response, error) {
-	truncatedHistory := truncateConversation(conversation.getHistory(), maxTokens)
-	requestBody, err := createRequestBody(truncatedHistory)
-	if err != nil {
-		return nil, fmt.Errorf("failed to create request body: %w", err)
+func (c *APIClient) prepareHistory(history []Message, model string) []Message {
+	prepared, dropped := truncateConversation(history, promptBudget(c.config, model))
+	if c.config.TruncationStrategy == "note" && len(dropped) > 0 {
+		note := fmt.Sprintf("[earlier context omitted: %d messages about %s]", len(dropped), droppedTopic(dropped))
+		prepared = slices.Insert(prepared, leadingSystemMessages(prepared), Message{Role: "system", Content: note, Timestamp: time.Now()})
 	}
 
-	req, err := http.NewRequestWithContext(ctx, http.MethodPost, apiURL, bytes.NewReader(requestBody))
-	if err != nil {
-		return nil, fmt.Errorf("failed to create request: %w", err)
+	if c.config.UserSuffix != "" && !c.config.StoreUserSuffix {
+		for i := range prepared {
+			if prepared[i].Role == "user" {
+				prepared[i].Content += "\n\n" + c.config.UserSuffix
+			}
+		}
 	}
+	return prepared
+}
 
-	req.Header.Set("Content-Type", "application/json")
-	req.Header.Set("Authorization", "Bearer "+c.apiKey)
-	req.Header.Set("User-Agent", "AIChat/1.0")
+func withStoredUserSuffix(config *Config, content string) string {
+	if config.UserSuffix == "" || !config.StoreUserSuffix {
+		return content
+	}
+	return content + "\n\n" + config.UserSuffix
+}
 
-	return c.httpClient.Do(req)
+func droppedTopic(dropped []Message) string {
+	for _, msg := range dropped {
+		if msg.Role == "system" {
+			continue
+		}
+		words := strings.Fields(msg.Content)
+		if len(words) > truncationNoteWords {
+			return strings.Join(words[:truncationNoteWords], " ") + "..."
+		}
+		return strings.Join(words, " ")
+	}
+	return "earlier instructions"
 }
 
-func truncateConversation(history []Message, maxTokens int) []Message {
-	var truncated []Message
-	totalTokens := 0
+func truncateConversation(history []Message, maxTokens int) ([]Message, []Message) {
+	if countTokens(history) <= maxTokens {
+		return append([]Message(nil), history...), nil
+	}
 
-	for i := len(history) - 1; i >= 0; i-- {
-		message := history[i]
-		tokens := len(strings.Fields(message.Content))
-		if totalTokens+tokens > maxTokens {
+	prelude := leadingSystemMessages(history)
+	budget := maxTokens - countTokens(history[:prelude])
+	start := len(history)
+	for i := len(history) - 1; i >= prelude; i-- {
+		if history[i].Role != "user" {
+			continue
+		}
+		tokens := countTokens(history[i:start])
+		if tokens > budget && start < len(history) {
 			break
 		}
-		totalTokens += tokens
-		truncated = append([]Message{message}, truncated...)
+		budget -= tokens
+		start = i
+	}
+
+	kept := append(append([]Message(nil), history[:prelude]...), history[start:]...)
+	return kept, append([]Message(nil), history[prelude:start]...)
+}
+
+func leadingSystemMessages(history []Message) int {
+	count := 0
+	for count < len(history) && history[count].Role == "system" {
+		count++
+	}
+	return count
+}
+
+func newTPSReporter() func(deltas int) {
+	start := time.Now()
+	var lastUpdate time.Time
+	return func(deltas int) {
+		now := time.Now()
+		if now.Sub(lastUpdate) < tpsUpdateInterval {
+			return
+		}
+		lastUpdate = now
+		if elapsed := now.Sub(start).Seconds(); elapsed > 0 {
+			setTerminalTitle(fmt.Sprintf("%s - %.1f tok/s", terminalTitle, float64(deltas)/elapsed))
+		}
 	}
+}
 
-	return truncated
+func setTerminalTitle(title string) {
+	if theme.Interactive {
+		fmt.Printf("\033]0;%s\007", title)
+	}
 }
 
-func createRequestBody(truncatedHistory []Message) ([]byte, error) {
-	currentTime := time.Now()
-	systemMessage := fmt.Sprintf("Current date and time: %s", currentTime.Format(time.RFC3339))
+func stripEchoedSystemPrompt(response, systemPrompt string) string {
+	promptWords := strings.Fields(systemPrompt)
+	responseWords := wordPattern.FindAllStringIndex(response, -1)
+
+	matched, mismatches, echoed := 0, 0, 0
+	for i := 0; i < len(responseWords) && i < len(promptWords); i++ {
+		word := response[responseWords[i][0]:responseWords[i][1]]
+		if normalizeEchoWord(word) == normalizeEchoWord(promptWords[i]) {
+			matched++
+			echoed = i + 1
+			continue
+		}
+		mismatches++
+		if mismatches*10 > i+1 {
+			break
+		}
+	}
 
-	apiMessages := []APIMessage{
-		{Role: "system", Content: systemMessage},
+	if matched < minEchoWords {
+		return response
 	}
+	return strings.TrimLeft(response[responseWords[echoed-1][1]:], " \t\r\n")
+}
+
+func normalizeEchoWord(word string) string {
+	return strings.ToLower(strings.TrimFunc(word, unicode.IsPunct))
+}
+
+func normalizeWhitespace(text string) string {
+	lines := strings.Split(text, "\n")
+	result := make([]string, 0, len(lines))
+	inCodeBlock := false
+	blankRun := 0
+
+	for _, line := range lines {
+		isFence := strings.HasPrefix(strings.TrimSpace(line), "```")
+		if inCodeBlock && !isFence {
+			result = append(result, line)
+			continue
+		}
+		if isFence {
+			inCodeBlock = !inCodeBlock
+		}
 
-	for _, msg := range truncatedHistory {
-		apiMessages = append(apiMessages, APIMessage{
-			Role:    msg.Role,
-			Content: msg.Content,
-		})
+		line = strings.TrimRight(line, " \t")
+		if line == "" {
+			blankRun++
+			continue
+		}
+		result = appendBlankLines(result, blankRun)
+		blankRun = 0
+		result = append(result, line)
 	}
 
-	body := map[string]interface{}{
-		"messages":    apiMessages,
-		"model":       "llama-3.1-70b-versatile",
-		"temperature": 0.7,
-		"max_tokens":  maxTokens,
-		"top_p":       0.9,
-		"stream":      true,
-		"stop":        []string{"\n\nHuman:", "\n\nAssistant:"},
+	result = appendBlankLines(result, blankRun)
+	return strings.Join(result, "\n")
+}
+
+func appendBlankLines(lines []string, count int) []string {
+	if count >= 3 {
+		count = 1
 	}
+	for i := 0; i < count; i++ {
+		lines = append(lines, "")
+	}
+	return lines
+}
 
-	return json.Marshal(body)
+func renderForDisplay(config *Config, text string) string {
+	if len(config.FormatCode) > 0 {
+		text = formatCodeBlocks(text, config.FormatCode)
+	}
+	if config.ShortenURLs {
+		text = shortenURLs(text)
+	}
+	if config.RenderMarkdown && term.IsTerminal(int(outputFile().Fd())) {
+		text = renderMarkdown(text)
+	}
+	if config.SentenceShading {
+		text = shadeSentences(text)
+	}
+	if config.HighlightCode && term.IsTerminal(int(outputFile().Fd())) {
+		text = highlightCodeBlocks(text)
+	}
+	return text
 }
 
-func processStreamResponse(body io.Reader) (string, error) {
-	scanner := bufio.NewScanner(body)
-	var buffer strings.Builder
-	var lastError error
+func formatCodeBlocks(text string, formatters map[string]string) string {
+	lines := strings.Split(text, "\n")
+	result := make([]string, 0, len(lines))
 
-	for scanner.Scan() {
-		line := scanner.Text()
-		if !strings.HasPrefix(line, "data: ") {
+	for i := 0; i < len(lines); i++ {
+		result = append(result, lines[i])
+		fence := strings.TrimSpace(lines[i])
+		if !strings.HasPrefix(fence, "```") {
 			continue
 		}
 
-		data := strings.TrimPrefix(line, "data: ")
-		if data == "[DONE]" {
+		end := i + 1
+		for end < len(lines) && !strings.HasPrefix(strings.TrimSpace(lines[end]), "```") {
+			end++
+		}
+		if end == len(lines) {
+			result = append(result, lines[i+1:]...)
 			break
 		}
 
-		var jsonResponse map[string]interface{}
-		if err := json.Unmarshal([]byte(data), &jsonResponse); err != nil {
-			lastError = err
+		code := strings.Join(lines[i+1:end], "\n")
+		if command, ok := formatters[strings.ToLower(strings.TrimSpace(strings.TrimPrefix(fence, "```")))]; ok {
+			if formatted, err := runFormatter(command, code); err == nil {
+				code = formatted
+			} else {
+				log.Printf("Formatter %q failed, showing unformatted code: %v", command, err)
+			}
+		}
+		if end > i+1 {
+			result = append(result, code)
+		}
+		result = append(result, lines[end])
+		i = end
+	}
+
+	return strings.Join(result, "\n")
+}
+
+func runFormatter(command, code string) (string, error) {
+	ctx, cancel := context.WithTimeout(context.Background(), formatterTimeout)
+	defer cancel()
+
+	fields := strings.Fields(command)
+	cmd := exec.CommandContext(ctx, fields[0], fields[1:]...)
+	cmd.Stdin = strings.NewReader(code + "\n")
+	var stderr bytes.Buffer
+	cmd.Stderr = &stderr
+	output, err := cmd.Output()
+	if err != nil {
+		if message := strings.TrimSpace(stderr.String()); message != "" {
+			return "", fmt.Errorf("%w: %s", err, message)
+		}
+		return "", err
+	}
+	return strings.TrimRight(string(output), "\n"), nil
+}
+
+func shadeSentences(text string) string {
+	lines := strings.Split(text, "\n")
+	shades := [2]string{colorReset, colorWhite}
+	shade := 0
+	inCodeBlock := false
+
+	for i, line := range lines {
+		if strings.HasPrefix(strings.TrimSpace(line), "```") {
+			inCodeBlock = !inCodeBlock
+			continue
+		}
+		if inCodeBlock || strings.TrimSpace(line) == "" {
 			continue
 		}
 
-		if content := extractContent(jsonResponse); content != "" {
-			buffer.WriteString(content)
+		var builder strings.Builder
+		current := -1
+		for j, word := range strings.Split(line, " ") {
+			if j > 0 {
+				builder.WriteByte(' ')
+			}
+			if current != shade {
+				builder.WriteString(shades[shade])
+				current = shade
+			}
+			builder.WriteString(word)
+			if endsSentence(word) {
+				shade = 1 - shade
+			}
 		}
+		builder.WriteString(colorReset)
+		lines[i] = builder.String()
 	}
 
-	if err := scanner.Err(); err != nil {
-		return "", fmt.Errorf("failed to read stream: %w", err)
+	return strings.Join(lines, "\n")
+}
+
+func endsSentence(word string) bool {
+	word = strings.TrimRight(word, `"')]*`)
+	return strings.HasSuffix(word, ".") || strings.HasSuffix(word, "!") || strings.HasSuffix(word, "?")
+}
+
+func shortenURLs(text string) string {
+	lines := strings.Split(text, "\n")
+	inCodeBlock := false
+	for i, line := range lines {
+		if strings.HasPrefix(strings.TrimSpace(line), "```") {
+			inCodeBlock = !inCodeBlock
+			continue
+		}
+		if inCodeBlock {
+			continue
+		}
+		lines[i] = urlPattern.ReplaceAllStringFunc(line, shortenURL)
 	}
+	return strings.Join(lines, "\n")
+}
 
-	if lastError != nil {
-		return "", fmt.Errorf("error processing stream: %w", lastError)
+func shortenURL(rawURL string) string {
+	if len(rawURL) <= maxDisplayURLLength {
+		return rawURL
+	}
+	parsed, err := url.Parse(rawURL)
+	if err != nil || parsed.Host == "" {
+		return rawURL[:maxDisplayURLLength-3] + "..."
 	}
+	return parsed.Scheme + "://" + parsed.Host + "/..."
+}
 
-	return strings.TrimSpace(buffer.String()), nil
+func printTruncationIndicator(style IndicatorStyle) {
+	fmt.Printf("%s%s%s\n", colorCodes[style.Color], style.Text, colorReset)
 }
 
-func extractContent(jsonResponse map[string]interface{}) string {
-	choices, ok := jsonResponse["choices"].([]interface{})
-	if !ok || len(choices) == 0 {
-		return ""
+func clearScreen() {
+	if theme.Interactive {
+		fmt.Print("\033[2J\033[H")
 	}
+}
 
-	choice, ok := choices[0].(map[string]interface{})
-	if !ok {
-		return ""
+func (s *ChatSession) printResponse(label, model string, fallback bool, response string) {
+	delay := typewriterDelay
+	if !s.apiClient.Stream || !theme.Interactive {
+		delay = 0
 	}
+	if s.config.BoxedOutput {
+		title := label + " · " + model
+		if fallback {
+			title += " (fallback)"
+		}
+		printBoxed(title, colorAssistant, response, delay)
+		return
+	}
+	if fallback {
+		label += " (" + model + ")"
+	}
+	printTypewriter(label, colorAssistant, response, delay)
+}
 
-	delta, ok := choice["delta"].(map[string]interface{})
-	if !ok {
-		return ""
+func printUsageFooter(promptTokens, completionTokens int, elapsed time.Duration) {
+	speed := 0.0
+	if elapsed > 0 {
+		speed = float64(completionTokens) / elapsed.Seconds()
 	}
+	fmt.Printf("%s↑ %d tok ↓ %d tok · %.0f tok/s · %.1fs%s\n", colorDim, promptTokens, completionTokens, speed, elapsed.Seconds(), colorReset)
+}
 
-	content, ok := delta["content"].(string)
-	if !ok {
-		return ""
+func printBoxed(title, color, text string, delay time.Duration) {
+	width, _, err := term.GetSize(int(outputFile().Fd()))
+	if err != nil || width < minBoxWidth {
+		width = defaultBoxWidth
+	}
+	inner := width - 4
+
+	header := "─ " + truncateString(title, inner-2) + " "
+	fmt.Printf("%s┌%s%s┐%s\n", color, header, strings.Repeat("─", max(0, inner+2-utf8.RuneCountInString(header))), colorReset)
+	for _, line := range wrapLines(text, inner) {
+		fmt.Printf("%s│%s ", color, colorReset)
+		for i, word := range strings.Split(line, " ") {
+			if i > 0 {
+				fmt.Print(" ")
+			}
+			fmt.Print(word)
+			time.Sleep(delay)
+		}
+		fmt.Printf("%s %s│%s\n", strings.Repeat(" ", max(0, inner-visibleLength(line))), color, colorReset)
 	}
+	fmt.Printf("%s└%s┘%s\n", color, strings.Repeat("─", inner+2), colorReset)
+}
 
-	return content
+func wrapLines(text string, width int) []string {
+	width = max(width, 1)
+	var wrapped []string
+	for _, paragraph := range strings.Split(strings.TrimRight(text, "\n"), "\n") {
+		paragraph = strings.ReplaceAll(paragraph, "\t", "    ")
+		rest := strings.TrimLeft(paragraph, " ")
+		indent := paragraph[:len(paragraph)-len(rest)]
+		if visibleLength(indent) >= width {
+			indent = ""
+		}
+		line, spaces := indent, ""
+		for rest != "" {
+			word := rest[:len(rest)-len(strings.TrimLeft(rest, " "))]
+			if word == "" {
+				if end := strings.IndexByte(rest, ' '); end >= 0 {
+					word = rest[:end]
+				} else {
+					word = rest
+				}
+			}
+			rest = rest[len(word):]
+			if word[0] == ' ' {
+				spaces = word
+				continue
+			}
+			if line == indent || visibleLength(line)+visibleLength(spaces)+visibleLength(word) <= width {
+				if line != indent {
+					line += spaces
+				}
+			} else {
+				wrapped = append(wrapped, line)
+				line = indent
+			}
+			spaces = ""
+			for visibleLength(line)+visibleLength(word) > width {
+				head, tail := splitVisible(word, width-visibleLength(line))
+				wrapped = append(wrapped, line+head)
+				line, word = indent, tail
+			}
+			line += word
+		}
+		wrapped = append(wrapped, line)
+	}
+	return wrapped
 }
 
-func clearScreen() {
-	fmt.Print("\033[2J\033[H")
+func splitVisible(text string, n int) (string, string) {
+	count := 0
+	for i := 0; i < len(text); {
+		if text[i] == '\x1b' {
+			if loc := ansiPattern.FindStringIndex(text[i:]); loc != nil && loc[0] == 0 {
+				i += loc[1]
+				continue
+			}
+		}
+		if count == n {
+			return text[:i], text[i:]
+		}
+		_, size := utf8.DecodeRuneInString(text[i:])
+		i += size
+		count++
+	}
+	return text, ""
+}
+
+func visibleLength(text string) int {
+	return utf8.RuneCountInString(ansiPattern.ReplaceAllString(text, ""))
 }
 
-func printStreamingResponse(response string) {
-	words := strings.Fields(response)
-	for i, word := range words {
-		fmt.Print(word)
-		if i < len(words)-1 {
-			fmt.Print(" ")
+func printTypewriter(label, color, text string, delay time.Duration) {
+	if strings.TrimSpace(text) == "" {
+		return
+	}
+
+	fmt.Printf("%s%s:%s ", color, label, colorReset)
+	for i, line := range strings.Split(strings.TrimSpace(text), "\n") {
+		if i > 0 {
+			fmt.Println()
+		}
+		fmt.Print(colorBody)
+		for j, word := range strings.Split(line, " ") {
+			if j > 0 {
+				fmt.Print(" ")
+			}
+			if word == "" {
+				continue
+			}
+			fmt.Print(word)
+			time.Sleep(delay)
 		}
-		time.Sleep(50 * time.Millisecond)
 	}
-	fmt.Println()
+	fmt.Println(colorReset)
 }
 
-func saveConversation(conversation *Conversation) error {
+func saveConversation(conversation *Conversation, includeTokens bool, settings *SessionSettings) error {
 	filename := fmt.Sprintf("conversation_%s.json", time.Now().Format("20060102_150405"))
-	data, err := json.MarshalIndent(conversation.getHistory(), "", "  ")
+	history := conversation.getHistory()
+	if !includeTokens {
+		for i := range history {
+			history[i].Tokens = 0
+		}
+	}
+
+	if err := writeHistoryFile(filename, history, settings); err != nil {
+		return err
+	}
+
+	fmt.Printf("%sConversation saved to %s%s\n", colorGreen, filename, colorReset)
+	return nil
+}
+
+func writeHistoryFile(filename string, history []Message, settings *SessionSettings) error {
+	data, err := json.MarshalIndent(SavedConversation{Version: saveFormatVersion, Settings: settings, Messages: history}, "", "  ")
 	if err != nil {
 		return fmt.Errorf("failed to marshal conversation: %w", err)
 	}
@@ -495,25 +2608,114 @@ func saveConversation(conversation *Conversation) error {
 	if err := os.WriteFile(filename, data, 0644); err != nil {
 		return fmt.Errorf("failed to write conversation file: %w", err)
 	}
-
-	fmt.Printf("%sConversation saved to %s%s\n", colorGreen, filename, colorReset)
 	return nil
 }
 
-func loadConversation(filename string) (*Conversation, error) {
+func appendFineTuningRecord(path string, history []Message) error {
+	var record FineTuningRecord
+	hasAssistant := false
+	for _, msg := range history {
+		switch msg.Role {
+		case "system", "user":
+		case "assistant":
+			hasAssistant = true
+		default:
+			return fmt.Errorf("role %q is not supported by the fine-tuning format", msg.Role)
+		}
+		if msg.Cancelled {
+			return errors.New("conversation contains a cancelled response")
+		}
+		record.Messages = append(record.Messages, APIMessage{Role: msg.Role, Content: msg.Content})
+	}
+	if !hasAssistant {
+		return errors.New("conversation has no assistant messages to export")
+	}
+
+	data, err := json.Marshal(record)
+	if err != nil {
+		return fmt.Errorf("failed to marshal fine-tuning record: %w", err)
+	}
+
+	file, err := os.OpenFile(path, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0644)
+	if err != nil {
+		return fmt.Errorf("failed to open export file: %w", err)
+	}
+	if _, err := file.Write(append(data, '\n')); err != nil {
+		file.Close()
+		return fmt.Errorf("failed to write export file: %w", err)
+	}
+	return file.Close()
+}
+
+func loadConversation(filename string) (*Conversation, *SessionSettings, error) {
 	data, err := os.ReadFile(filename)
 	if err != nil {
-		return nil, fmt.Errorf("failed to read conversation file: %w", err)
+		return nil, nil, fmt.Errorf("failed to read conversation file: %w", err)
 	}
 
-	var history []Message
-	if err := json.Unmarshal(data, &history); err != nil {
-		return nil, fmt.Errorf("failed to unmarshal conversation: %w", err)
+	saved, err := decodeSavedConversation(data)
+	if err != nil {
+		return nil, nil, err
+	}
+
+	history := make([]Message, 0, len(saved.Messages))
+	for i, msg := range saved.Messages {
+		switch msg.Role {
+		case "system", "user", "assistant", "tool":
+		case "developer":
+			msg.Role = "system"
+		case "function":
+			continue
+		default:
+			return nil, nil, fmt.Errorf("invalid role %q in message %d", msg.Role, i+1)
+		}
+		if msg.Role == "assistant" && strings.TrimSpace(msg.Content) == "" && len(msg.ToolCalls) == 0 {
+			continue
+		}
+		msg.Tokens = len(strings.Fields(msg.Content))
+		history = append(history, msg)
 	}
 
 	conversation := &Conversation{History: history}
 	conversation.tokenCount = countTokens(history)
-	return conversation, nil
+	return conversation, saved.Settings, nil
+}
+
+func decodeSavedConversation(data []byte) (*SavedConversation, error) {
+	var saved SavedConversation
+	if bytes.HasPrefix(bytes.TrimSpace(data), []byte("[")) {
+		if err := json.Unmarshal(data, &saved.Messages); err != nil {
+			return nil, fmt.Errorf("failed to unmarshal conversation: %w", err)
+		}
+		return &saved, migrateSavedConversation(&saved, 0)
+	}
+
+	if err := json.Unmarshal(data, &saved); err != nil {
+		return nil, fmt.Errorf("failed to unmarshal conversation: %w", err)
+	}
+	version := saved.Version
+	if version == 0 {
+		version = 1
+	}
+	if version > saveFormatVersion {
+		return nil, fmt.Errorf("conversation file is version %d, which is newer than this client (supports up to %d)", version, saveFormatVersion)
+	}
+	return &saved, migrateSavedConversation(&saved, version)
+}
+
+func migrateSavedConversation(saved *SavedConversation, version int) error {
+	for ; version < saveFormatVersion; version++ {
+		switch version {
+		case 0, 1:
+			if saved.Messages == nil {
+				saved.Messages = []Message{}
+			}
+		default:
+			return fmt.Errorf("no migration from conversation file version %d", version)
+		}
+	}
+	saved.Version = saveFormatVersion
+	return nil
 }
 
 func countTokens(messages []Message) int {
@@ -531,7 +2733,11 @@ func printConversationSummary(conversation *Conversation) {
 	fmt.Println("Last 3 exchanges:")
 	for i := max(0, len(conversation.History)-6); i < len(conversation.History); i++ {
 		msg := conversation.History[i]
-		fmt.Printf("%s%s:%s %s\n", colorYellow, msg.Role, colorReset, truncateString(msg.Content, 50))
+		marker := ""
+		if msg.Cancelled {
+			marker = " [cancelled]"
+		}
+		fmt.Printf("%s%s:%s %s%s\n", colorYellow, msg.Role, colorReset, truncateString(msg.Content, 50), marker)
 	}
 }
 
@@ -555,13 +2761,3 @@ func (c *Conversation) replaceWith(other *Conversation) {
 	c.History = other.History
 	c.tokenCount = other.tokenCount
 }
-
-const (
-	colorReset  = "\033[0m"
-	colorRed    = "\033[31m"
-	colorGreen  = "\033[32m"
-	colorYellow = "\033[33m"
-	colorBlue   = "\033[34m"
-	colorPurple = "\033[35m"
-	colorCyan   = "\033[36m"
-)
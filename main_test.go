@@ -0,0 +1,248 @@
+package main
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+	"testing"
+)
+
+func testMessages(roles ...string) []Message {
+	messages := make([]Message, 0, len(roles))
+	for i, role := range roles {
+		messages = append(messages, Message{Role: role, Content: role + " " + string(rune('a'+i))})
+	}
+	return messages
+}
+
+func messageRoles(messages []Message) []string {
+	roles := make([]string, 0, len(messages))
+	for _, msg := range messages {
+		roles = append(roles, msg.Role)
+	}
+	return roles
+}
+
+func TestPruneHistory(t *testing.T) {
+	tests := []struct {
+		name        string
+		history     []string
+		maxMessages int
+		kept        []string
+		pruned      []string
+	}{
+		{
+			name:        "disabled",
+			history:     []string{"system", "user", "assistant", "user", "assistant"},
+			maxMessages: 0,
+			kept:        []string{"system", "user", "assistant", "user", "assistant"},
+		},
+		{
+			name:        "under the limit",
+			history:     []string{"system", "user", "assistant"},
+			maxMessages: 3,
+			kept:        []string{"system", "user", "assistant"},
+		},
+		{
+			name:        "keeps the system prompt",
+			history:     []string{"system", "user", "assistant", "user", "assistant"},
+			maxMessages: 3,
+			kept:        []string{"system", "user", "assistant"},
+			pruned:      []string{"user", "assistant"},
+		},
+		{
+			name:        "without a system prompt",
+			history:     []string{"user", "assistant", "user", "assistant"},
+			maxMessages: 2,
+			kept:        []string{"user", "assistant"},
+			pruned:      []string{"user", "assistant"},
+		},
+		{
+			name:        "cut inside a turn drops the whole turn",
+			history:     []string{"system", "user", "assistant", "tool", "assistant", "user", "assistant"},
+			maxMessages: 4,
+			kept:        []string{"system", "user", "assistant"},
+			pruned:      []string{"user", "assistant", "tool", "assistant"},
+		},
+		{
+			name:        "cut inside the last turn keeps that turn",
+			history:     []string{"system", "user", "assistant", "tool", "assistant"},
+			maxMessages: 2,
+			kept:        []string{"system", "user", "assistant", "tool", "assistant"},
+		},
+		{
+			name:        "cut inside the last turn drops earlier turns",
+			history:     []string{"system", "user", "assistant", "user", "assistant", "tool", "assistant"},
+			maxMessages: 3,
+			kept:        []string{"system", "user", "assistant", "tool", "assistant"},
+			pruned:      []string{"user", "assistant"},
+		},
+	}
+
+	for _, test := range tests {
+		t.Run(test.name, func(t *testing.T) {
+			history := testMessages(test.history...)
+			conversation := &Conversation{History: history, tokenCount: countTokens(history), maxMessages: test.maxMessages}
+
+			pruned := conversation.pruneHistory()
+			if got := messageRoles(conversation.History); !slices.Equal(got, test.kept) {
+				t.Errorf("kept %v, want %v", got, test.kept)
+			}
+			if got := messageRoles(pruned); !slices.Equal(got, test.pruned) {
+				t.Errorf("pruned %v, want %v", got, test.pruned)
+			}
+			if conversation.tokenCount != countTokens(conversation.History) {
+				t.Errorf("token count %d, want %d", conversation.tokenCount, countTokens(conversation.History))
+			}
+		})
+	}
+}
+
+func captureStdout(t *testing.T, run func() error) string {
+	t.Helper()
+	reader, writer, err := os.Pipe()
+	if err != nil {
+		t.Fatal(err)
+	}
+	stdout := os.Stdout
+	os.Stdout = writer
+	runErr := run()
+	os.Stdout = stdout
+	writer.Close()
+	out, err := io.ReadAll(reader)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if runErr != nil {
+		t.Fatal(runErr)
+	}
+	return string(out)
+}
+
+func TestPrintOneShotResult(t *testing.T) {
+	config := &Config{TruncationIndicator: IndicatorStyle{Text: "(truncated)", Color: "dim"}}
+	indicator := colorCodes["dim"] + "(truncated)" + colorReset + "\n"
+	normal := OneShotResult{Model: "test-model", Response: "**hello** world", FinishReason: "stop", PromptTokens: 3, CompletionTokens: 2}
+	cancelled := OneShotResult{Model: "test-model", Response: "**hello**", PromptTokens: 3, CompletionTokens: 1, Cancelled: true}
+
+	tests := []struct {
+		name   string
+		format string
+		result OneShotResult
+		want   string
+	}{
+		{name: "text", format: "text", result: normal, want: "**hello** world\n"},
+		{name: "text cancelled", format: "text", result: cancelled, want: "**hello**\n(truncated)\n"},
+		{name: "markdown", format: "markdown", result: normal, want: "**hello** world\n"},
+		{name: "markdown cancelled", format: "markdown", result: cancelled, want: "**hello**\n" + indicator},
+	}
+	for _, test := range tests {
+		t.Run(test.name, func(t *testing.T) {
+			out := captureStdout(t, func() error { return printOneShotResult(config, test.format, test.result) })
+			if out != test.want {
+				t.Errorf("got %q, want %q", out, test.want)
+			}
+		})
+	}
+
+	for name, result := range map[string]OneShotResult{"json": normal, "json cancelled": cancelled} {
+		t.Run(name, func(t *testing.T) {
+			out := captureStdout(t, func() error { return printOneShotResult(config, "json", result) })
+			var decoded map[string]interface{}
+			if err := json.Unmarshal([]byte(out), &decoded); err != nil {
+				t.Fatalf("output is not JSON: %v\n%s", err, out)
+			}
+			for _, key := range []string{"model", "response", "prompt_tokens", "completion_tokens"} {
+				if _, ok := decoded[key]; !ok {
+					t.Errorf("missing %q in %s", key, out)
+				}
+			}
+			if decoded["response"] != result.Response {
+				t.Errorf("response %v, want %q", decoded["response"], result.Response)
+			}
+			if _, ok := decoded["cancelled"]; ok != result.Cancelled {
+				t.Errorf("cancelled present = %v, want %v", ok, result.Cancelled)
+			}
+		})
+	}
+}
+
+func TestDecodeSavedConversation(t *testing.T) {
+	tests := []struct {
+		file     string
+		model    string
+		contents []string
+	}{
+		{file: "conversation_v0.json", contents: []string{"You are a helpful assistant.", "Hello", "Hi there!"}},
+		{file: "conversation_v1.json", contents: []string{"You are a helpful assistant.", "Hello", "Hi there!"}},
+		{file: "conversation_v2.json", model: "llama-3.1-70b-versatile", contents: []string{"You are a helpful assistant.", "Hello", "Hi there!"}},
+	}
+	for _, test := range tests {
+		t.Run(test.file, func(t *testing.T) {
+			data, err := os.ReadFile(filepath.Join("testdata", test.file))
+			if err != nil {
+				t.Fatal(err)
+			}
+			saved, err := decodeSavedConversation(data)
+			if err != nil {
+				t.Fatalf("decode failed: %v", err)
+			}
+			if saved.Version != saveFormatVersion {
+				t.Errorf("version %d, want %d", saved.Version, saveFormatVersion)
+			}
+			if got := messageRoles(saved.Messages); !slices.Equal(got, []string{"system", "user", "assistant"}) {
+				t.Errorf("roles %v", got)
+			}
+			for i, want := range test.contents {
+				if i < len(saved.Messages) && saved.Messages[i].Content != want {
+					t.Errorf("message %d content %q, want %q", i, saved.Messages[i].Content, want)
+				}
+			}
+			model := ""
+			if saved.Settings != nil {
+				model = saved.Settings.Model
+			}
+			if model != test.model {
+				t.Errorf("settings model %q, want %q", model, test.model)
+			}
+		})
+	}
+}
+
+func TestDecodeSavedConversationRejectsNewerVersion(t *testing.T) {
+	data, err := os.ReadFile(filepath.Join("testdata", "conversation_v99.json"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	_, err = decodeSavedConversation(data)
+	want := fmt.Sprintf("conversation file is version 99, which is newer than this client (supports up to %d)", saveFormatVersion)
+	if err == nil || !strings.Contains(err.Error(), want) {
+		t.Fatalf("got error %v, want %q", err, want)
+	}
+}
+
+func TestWrapLines(t *testing.T) {
+	tests := []struct {
+		name  string
+		text  string
+		width int
+		want  []string
+	}{
+		{name: "wraps on spaces", text: "one two three", width: 8, want: []string{"one two", "three"}},
+		{name: "keeps indentation", text: "func main() {\n    fmt.Println(a,  b)\n}", width: 30, want: []string{"func main() {", "    fmt.Println(a,  b)", "}"}},
+		{name: "indents continuation lines", text: "    alpha beta gamma", width: 14, want: []string{"    alpha beta", "    gamma"}},
+		{name: "breaks long words", text: "abcdefghij", width: 4, want: []string{"abcd", "efgh", "ij"}},
+		{name: "ignores color codes", text: colorRed + "abcdef" + colorReset, width: 3, want: []string{colorRed + "abc", "def" + colorReset}},
+	}
+	for _, test := range tests {
+		t.Run(test.name, func(t *testing.T) {
+			if got := wrapLines(test.text, test.width); !slices.Equal(got, test.want) {
+				t.Errorf("got %q, want %q", got, test.want)
+			}
+		})
+	}
+}
@@ -0,0 +1,210 @@
+package main
+
+import (
+	"regexp"
+	"strings"
+)
+
+var headingPattern = regexp.MustCompile(`^(#{1,6})\s+(.*)$`)
+
+var bulletPattern = regexp.MustCompile(`^(\s*)[-*+]\s+(.*)$`)
+
+var boldPattern = regexp.MustCompile(`\*\*([^*]+)\*\*|__([^_]+)__`)
+
+var italicPattern = regexp.MustCompile(`\*([^*\s][^*]*)\*`)
+
+func renderMarkdown(text string) string {
+	lines := strings.Split(text, "\n")
+	inCodeBlock := false
+
+	for i, line := range lines {
+		trimmed := strings.TrimSpace(line)
+		if strings.HasPrefix(trimmed, "```") {
+			inCodeBlock = !inCodeBlock
+			continue
+		}
+		if inCodeBlock {
+			continue
+		}
+
+		switch {
+		case headingPattern.MatchString(trimmed):
+			heading := headingPattern.FindStringSubmatch(trimmed)
+			lines[i] = colorBold + colorCyan + renderInline(heading[2]) + colorReset
+		case trimmed == "---" || trimmed == "***" || trimmed == "___":
+			lines[i] = colorDim + strings.Repeat("─", 40) + colorReset
+		case strings.HasPrefix(trimmed, ">"):
+			quote := strings.TrimSpace(strings.TrimPrefix(trimmed, ">"))
+			lines[i] = colorDim + "│ " + colorReset + colorItalic + renderInline(quote) + colorNoItal
+		case bulletPattern.MatchString(line):
+			bullet := bulletPattern.FindStringSubmatch(line)
+			lines[i] = bullet[1] + "  • " + renderInline(bullet[2])
+		default:
+			lines[i] = renderInline(line)
+		}
+	}
+
+	return strings.Join(lines, "\n")
+}
+
+func renderInline(text string) string {
+	segments := strings.Split(text, "`")
+	if len(segments)%2 == 0 {
+		return renderEmphasis(text)
+	}
+
+	for i := range segments {
+		if i%2 == 1 {
+			segments[i] = colorYellow + segments[i] + colorReset
+		} else {
+			segments[i] = renderEmphasis(segments[i])
+		}
+	}
+	return strings.Join(segments, "")
+}
+
+func renderEmphasis(text string) string {
+	text = boldPattern.ReplaceAllStringFunc(text, func(match string) string {
+		return colorBold + match[2:len(match)-2] + colorNoBold
+	})
+	return italicPattern.ReplaceAllString(text, colorItalic+"$1"+colorNoItal)
+}
+
+var codeTokenPattern = regexp.MustCompile(`"(?:\\.|[^"\\])*"|'(?:\\.|[^'\\])*'|\b\d+(?:\.\d+)?\b|\b[A-Za-z_]\w*\b`)
+
+var ansiCodeColors = map[string]string{
+	"string":  colorGreen,
+	"number":  colorPurple,
+	"keyword": colorBlue,
+	"comment": colorDim,
+}
+
+var languageAliases = map[string]string{
+	"golang":     "go",
+	"rs":         "rust",
+	"py":         "python",
+	"js":         "javascript",
+	"ts":         "javascript",
+	"typescript": "javascript",
+	"sh":         "shell",
+	"bash":       "shell",
+	"zsh":        "shell",
+	"cpp":        "c",
+	"c++":        "c",
+	"java":       "c",
+}
+
+var languageKeywords = map[string]string{
+	"go":         "break case chan const continue default defer else fallthrough for func go goto if import interface map package range return select struct switch type var nil true false",
+	"rust":       "as async await break const continue crate else enum extern false fn for if impl in let loop match mod move mut pub ref return self Self static struct super trait true type unsafe use where while",
+	"python":     "and as assert async await break class continue def del elif else except False finally for from global if import in is lambda None nonlocal not or pass raise return True try while with yield",
+	"javascript": "async await break case catch class const continue default delete do else export extends false finally for function if import in instanceof let new null return super switch this throw true try typeof undefined var void while yield interface type",
+	"shell":      "if then else elif fi for in do done while until case esac function return local export",
+	"c":          "auto break case char class const continue default do double else enum extern float for if int long new private protected public return short signed sizeof static struct switch this typedef union unsigned void volatile while true false null",
+}
+
+func highlightCodeBlocks(text string) string {
+	lines := strings.Split(text, "\n")
+	result := make([]string, 0, len(lines))
+	inCodeBlock := false
+	var keywords map[string]bool
+	comment := ""
+
+	for _, line := range lines {
+		trimmed := strings.TrimSpace(line)
+		if strings.HasPrefix(trimmed, "```") {
+			if inCodeBlock {
+				result = append(result, colorDim+"└─"+colorReset)
+				inCodeBlock = false
+				continue
+			}
+			language := strings.ToLower(strings.TrimSpace(strings.TrimPrefix(trimmed, "```")))
+			if alias, ok := languageAliases[language]; ok {
+				language = alias
+			}
+			keywords, comment = codeLanguage(language)
+			result = append(result, colorDim+"┌─ "+language+colorReset)
+			inCodeBlock = true
+			continue
+		}
+		if !inCodeBlock {
+			result = append(result, line)
+			continue
+		}
+		result = append(result, colorDim+"│ "+colorReset+highlightCodeLine(line, keywords, comment))
+	}
+
+	return strings.Join(result, "\n")
+}
+
+func codeLanguage(language string) (map[string]bool, string) {
+	keywords := make(map[string]bool)
+	for _, keyword := range strings.Fields(languageKeywords[language]) {
+		keywords[keyword] = true
+	}
+	switch language {
+	case "python", "shell", "ruby", "yaml", "toml":
+		return keywords, "#"
+	case "":
+		return keywords, ""
+	default:
+		return keywords, "//"
+	}
+}
+
+func highlightCodeLine(line string, keywords map[string]bool, comment string) string {
+	return styleCodeLine(line, keywords, comment, func(class, text string) string {
+		if color, ok := ansiCodeColors[class]; ok {
+			return color + text + colorReset
+		}
+		return text
+	})
+}
+
+func styleCodeLine(line string, keywords map[string]bool, comment string, style func(class, text string) string) string {
+	code, trailing := splitComment(line, comment)
+	var styled strings.Builder
+	last := 0
+	for _, loc := range codeTokenPattern.FindAllStringIndex(code, -1) {
+		styled.WriteString(style("", code[last:loc[0]]))
+		styled.WriteString(style(codeTokenClass(code[loc[0]:loc[1]], keywords), code[loc[0]:loc[1]]))
+		last = loc[1]
+	}
+	styled.WriteString(style("", code[last:]))
+	if trailing != "" {
+		styled.WriteString(style("comment", trailing))
+	}
+	return styled.String()
+}
+
+func codeTokenClass(token string, keywords map[string]bool) string {
+	switch {
+	case token[0] == '"' || token[0] == '\'':
+		return "string"
+	case token[0] >= '0' && token[0] <= '9':
+		return "number"
+	case keywords[token]:
+		return "keyword"
+	}
+	return ""
+}
+
+func splitComment(line, marker string) (string, string) {
+	if marker == "" {
+		return line, ""
+	}
+	var quote byte
+	for i := 0; i < len(line); i++ {
+		switch c := line[i]; {
+		case quote != 0 && c == '\\':
+			i++
+		case quote != 0 && c == quote:
+			quote = 0
+		case quote == 0 && (c == '"' || c == '\''):
+			quote = c
+		case quote == 0 && strings.HasPrefix(line[i:], marker):
+			return line[:i], line[i:]
+		}
+	}
+	return line, ""
+}
@@ -0,0 +1,459 @@
+package main
+
+import (
+	"bufio"
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"log"
+	"maps"
+	"os"
+	"os/exec"
+	"regexp"
+	"slices"
+	"sort"
+	"strings"
+	"sync"
+	"time"
+)
+
+const (
+	mcpProtocolVersion = "2024-11-05"
+	mcpCallTimeout     = 60 * time.Second
+	mcpMaxLineBytes    = 16 << 20
+	maxToolNameLength  = 64
+)
+
+var invalidToolNameChars = regexp.MustCompile(`[^a-zA-Z0-9_-]`)
+
+type MCPServerConfig struct {
+	Command string            `yaml:"command"`
+	Args    []string          `yaml:"args"`
+	Env     map[string]string `yaml:"env"`
+}
+
+type MCPClient struct {
+	name      string
+	cmd       *exec.Cmd
+	stdin     io.WriteCloser
+	writeMu   sync.Mutex
+	pendingMu sync.Mutex
+	pending   map[int]chan mcpMessage
+	nextID    int
+	done      chan struct{}
+	tools     []mcpTool
+	resources []mcpResource
+}
+
+type mcpMessage struct {
+	JSONRPC string          `json:"jsonrpc"`
+	ID      *int            `json:"id,omitempty"`
+	Method  string          `json:"method,omitempty"`
+	Params  interface{}     `json:"params,omitempty"`
+	Result  json.RawMessage `json:"result,omitempty"`
+	Error   *mcpError       `json:"error,omitempty"`
+}
+
+type mcpError struct {
+	Code    int    `json:"code"`
+	Message string `json:"message"`
+}
+
+type mcpTool struct {
+	Name        string                 `json:"name"`
+	Description string                 `json:"description"`
+	InputSchema map[string]interface{} `json:"inputSchema"`
+}
+
+type mcpResource struct {
+	URI         string `json:"uri"`
+	Name        string `json:"name"`
+	Description string `json:"description"`
+	MimeType    string `json:"mimeType"`
+}
+
+type mcpContent struct {
+	Type     string `json:"type"`
+	Text     string `json:"text"`
+	MimeType string `json:"mimeType"`
+	Resource *struct {
+		URI  string `json:"uri"`
+		Text string `json:"text"`
+	} `json:"resource"`
+}
+
+func (c MCPServerConfig) equal(other MCPServerConfig) bool {
+	return c.Command == other.Command && slices.Equal(c.Args, other.Args) && maps.Equal(c.Env, other.Env)
+}
+
+func (e *mcpError) Error() string {
+	return fmt.Sprintf("MCP error %d: %s", e.Code, e.Message)
+}
+
+func startMCPClient(ctx context.Context, name string, server MCPServerConfig) (*MCPClient, error) {
+	if server.Command == "" {
+		return nil, fmt.Errorf("MCP server %s has no command", name)
+	}
+	cmd := exec.Command(server.Command, server.Args...)
+	cmd.Env = os.Environ()
+	for key, value := range server.Env {
+		cmd.Env = append(cmd.Env, key+"="+os.ExpandEnv(value))
+	}
+	stdin, err := cmd.StdinPipe()
+	if err != nil {
+		return nil, fmt.Errorf("failed to connect to MCP server %s: %w", name, err)
+	}
+	stdout, err := cmd.StdoutPipe()
+	if err != nil {
+		return nil, fmt.Errorf("failed to connect to MCP server %s: %w", name, err)
+	}
+	if err := cmd.Start(); err != nil {
+		return nil, fmt.Errorf("failed to start MCP server %s: %w", name, err)
+	}
+
+	client := &MCPClient{name: name, cmd: cmd, stdin: stdin, pending: make(map[int]chan mcpMessage), done: make(chan struct{})}
+	go client.readLoop(stdout)
+	if err := client.initialize(ctx); err != nil {
+		client.Close()
+		return nil, fmt.Errorf("failed to initialize MCP server %s: %w", name, err)
+	}
+	return client, nil
+}
+
+func (c *MCPClient) initialize(ctx context.Context) error {
+	var result struct {
+		Capabilities struct {
+			Tools     *struct{} `json:"tools"`
+			Resources *struct{} `json:"resources"`
+		} `json:"capabilities"`
+	}
+	err := c.call(ctx, "initialize", map[string]interface{}{
+		"protocolVersion": mcpProtocolVersion,
+		"capabilities":    map[string]interface{}{},
+		"clientInfo":      map[string]string{"name": "aili", "version": "1.0"},
+	}, &result)
+	if err != nil {
+		return err
+	}
+	if err := c.send(mcpMessage{JSONRPC: "2.0", Method: "notifications/initialized"}); err != nil {
+		return err
+	}
+
+	if result.Capabilities.Tools != nil {
+		if err := c.listAll(ctx, "tools/list", "tools", &c.tools); err != nil {
+			return err
+		}
+	}
+	if result.Capabilities.Resources != nil {
+		if err := c.listAll(ctx, "resources/list", "resources", &c.resources); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func (c *MCPClient) listAll(ctx context.Context, method, field string, target interface{}) error {
+	var all []json.RawMessage
+	cursor := ""
+	for {
+		params := map[string]interface{}{}
+		if cursor != "" {
+			params["cursor"] = cursor
+		}
+		var page map[string]json.RawMessage
+		if err := c.call(ctx, method, params, &page); err != nil {
+			return err
+		}
+		var items []json.RawMessage
+		if raw, ok := page[field]; ok {
+			if err := json.Unmarshal(raw, &items); err != nil {
+				return fmt.Errorf("invalid %s response: %w", method, err)
+			}
+		}
+		all = append(all, items...)
+		cursor = ""
+		if raw, ok := page["nextCursor"]; ok {
+			json.Unmarshal(raw, &cursor)
+		}
+		if cursor == "" {
+			break
+		}
+	}
+
+	data, err := json.Marshal(all)
+	if err != nil {
+		return err
+	}
+	return json.Unmarshal(data, target)
+}
+
+func (c *MCPClient) send(msg mcpMessage) error {
+	data, err := json.Marshal(msg)
+	if err != nil {
+		return fmt.Errorf("failed to encode MCP message: %w", err)
+	}
+	c.writeMu.Lock()
+	defer c.writeMu.Unlock()
+	if _, err := c.stdin.Write(append(data, '\n')); err != nil {
+		return fmt.Errorf("failed to write to MCP server %s: %w", c.name, err)
+	}
+	return nil
+}
+
+func (c *MCPClient) call(ctx context.Context, method string, params, result interface{}) error {
+	ctx, cancel := context.WithTimeout(ctx, mcpCallTimeout)
+	defer cancel()
+
+	c.pendingMu.Lock()
+	c.nextID++
+	id := c.nextID
+	reply := make(chan mcpMessage, 1)
+	c.pending[id] = reply
+	c.pendingMu.Unlock()
+	defer func() {
+		c.pendingMu.Lock()
+		delete(c.pending, id)
+		c.pendingMu.Unlock()
+	}()
+
+	if err := c.send(mcpMessage{JSONRPC: "2.0", ID: &id, Method: method, Params: params}); err != nil {
+		return err
+	}
+	select {
+	case msg := <-reply:
+		if msg.Error != nil {
+			return msg.Error
+		}
+		if result == nil {
+			return nil
+		}
+		if err := json.Unmarshal(msg.Result, result); err != nil {
+			return fmt.Errorf("invalid %s response: %w", method, err)
+		}
+		return nil
+	case <-c.done:
+		return fmt.Errorf("MCP server %s exited", c.name)
+	case <-ctx.Done():
+		return fmt.Errorf("MCP %s on %s: %w", method, c.name, ctx.Err())
+	}
+}
+
+func (c *MCPClient) readLoop(stdout io.Reader) {
+	defer close(c.done)
+	reader := bufio.NewReaderSize(stdout, 64*1024)
+	for {
+		line, err := reader.ReadBytes('\n')
+		if len(line) > mcpMaxLineBytes {
+			log.Printf("Skipped a %d byte message from MCP server %s, the limit is %d bytes", len(line), c.name, mcpMaxLineBytes)
+		} else if len(line) > 0 {
+			c.dispatch(line)
+		}
+		if err != nil {
+			if !errors.Is(err, io.EOF) {
+				log.Printf("Failed to read from MCP server %s: %v", c.name, err)
+			}
+			return
+		}
+	}
+}
+
+func (c *MCPClient) dispatch(line []byte) {
+	var msg mcpMessage
+	if err := json.Unmarshal(line, &msg); err != nil {
+		return
+	}
+	switch {
+	case msg.ID != nil && msg.Method != "":
+		c.answerServerRequest(msg)
+	case msg.ID != nil:
+		c.pendingMu.Lock()
+		reply, ok := c.pending[*msg.ID]
+		c.pendingMu.Unlock()
+		if ok {
+			reply <- msg
+		}
+	}
+}
+
+func (c *MCPClient) answerServerRequest(request mcpMessage) {
+	response := mcpMessage{JSONRPC: "2.0", ID: request.ID}
+	if request.Method == "ping" {
+		response.Result = json.RawMessage("{}")
+	} else {
+		response.Error = &mcpError{Code: -32601, Message: "method not supported by client: " + request.Method}
+	}
+	if err := c.send(response); err != nil {
+		log.Printf("Failed to answer MCP server %s: %v", c.name, err)
+	}
+}
+
+func (c *MCPClient) callTool(ctx context.Context, name, arguments string) (string, error) {
+	var args map[string]interface{}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	var result struct {
+		Content []mcpContent `json:"content"`
+		IsError bool         `json:"isError"`
+	}
+	if err := c.call(ctx, "tools/call", map[string]interface{}{"name": name, "arguments": args}, &result); err != nil {
+		return "", err
+	}
+
+	text := mcpContentText(result.Content)
+	if result.IsError {
+		return "", errors.New(text)
+	}
+	return text, nil
+}
+
+func (c *MCPClient) readResource(ctx context.Context, uri string) (string, error) {
+	var result struct {
+		Contents []struct {
+			URI      string `json:"uri"`
+			Text     string `json:"text"`
+			MimeType string `json:"mimeType"`
+			Blob     string `json:"blob"`
+		} `json:"contents"`
+	}
+	if err := c.call(ctx, "resources/read", map[string]string{"uri": uri}, &result); err != nil {
+		return "", err
+	}
+
+	var parts []string
+	for _, content := range result.Contents {
+		if content.Blob != "" && content.Text == "" {
+			parts = append(parts, fmt.Sprintf("[binary %s content omitted]", content.MimeType))
+			continue
+		}
+		parts = append(parts, content.Text)
+	}
+	return strings.Join(parts, "\n\n"), nil
+}
+
+func mcpContentText(contents []mcpContent) string {
+	var parts []string
+	for _, content := range contents {
+		switch {
+		case content.Type == "text":
+			parts = append(parts, content.Text)
+		case content.Type == "resource" && content.Resource != nil:
+			parts = append(parts, content.Resource.Text)
+		default:
+			parts = append(parts, fmt.Sprintf("[%s %s content omitted]", content.Type, content.MimeType))
+		}
+	}
+	return strings.Join(parts, "\n")
+}
+
+func (c *MCPClient) Close() {
+	c.stdin.Close()
+	select {
+	case <-c.done:
+	case <-time.After(2 * time.Second):
+		c.cmd.Process.Kill()
+	}
+	c.cmd.Wait()
+}
+
+func mcpToolName(server, tool string) string {
+	name := invalidToolNameChars.ReplaceAllString(server+"__"+tool, "_")
+	if len(name) > maxToolNameLength {
+		name = name[:maxToolNameLength]
+	}
+	return name
+}
+
+func (c *MCPClient) Tools() []Tool {
+	tools := make([]Tool, 0, len(c.tools))
+	for _, tool := range c.tools {
+		schema := tool.InputSchema
+		if schema == nil {
+			schema = objectSchema(map[string]interface{}{})
+		}
+		tools = append(tools, Tool{
+			Name:        mcpToolName(c.name, tool.Name),
+			Description: fmt.Sprintf("[%s] %s", c.name, tool.Description),
+			Parameters:  schema,
+			Run: func(ctx context.Context, _ *ChatSession, arguments string) (string, error) {
+				return c.callTool(ctx, tool.Name, arguments)
+			},
+		})
+	}
+	return tools
+}
+
+func (s *ChatSession) connectMCPServers(ctx context.Context) {
+	names := make([]string, 0, len(s.config.MCPServers))
+	for name := range s.config.MCPServers {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+
+	for _, name := range names {
+		client, err := startMCPClient(ctx, name, s.config.MCPServers[name])
+		if err != nil {
+			log.Printf("%v", err)
+			continue
+		}
+		s.mcp = append(s.mcp, client)
+	}
+}
+
+func (s *ChatSession) closeMCPServers() {
+	for _, client := range s.mcp {
+		client.Close()
+	}
+	s.mcp = nil
+}
+
+func (s *ChatSession) mcpTools() []Tool {
+	var tools []Tool
+	for _, client := range s.mcp {
+		tools = append(tools, client.Tools()...)
+	}
+	return tools
+}
+
+func handleMCPCommand(ctx context.Context, session *ChatSession, args string) error {
+	if len(session.mcp) == 0 {
+		fmt.Printf("%sNo MCP servers connected. Add them under mcp_servers in the config file.%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	fields := strings.Fields(args)
+	if len(fields) == 0 {
+		for _, client := range session.mcp {
+			fmt.Printf("%s%s%s: %d tools, %d resources\n", colorCyan, client.name, colorReset, len(client.tools), len(client.resources))
+			for _, tool := range client.tools {
+				fmt.Printf("  tool %s  %s\n", mcpToolName(client.name, tool.Name), truncateString(tool.Description, maxSessionTitle*2))
+			}
+			for _, resource := range client.resources {
+				fmt.Printf("  resource %s  %s\n", resource.URI, resource.Name)
+			}
+		}
+		return nil
+	}
+	if fields[0] != "read" || len(fields) != 3 {
+		fmt.Printf("%sUsage: /mcp [read <server> <uri>]%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	for _, client := range session.mcp {
+		if client.name != fields[1] {
+			continue
+		}
+		text, err := client.readResource(ctx, fields[2])
+		if err != nil {
+			fmt.Printf("%sFailed to read %s: %v%s\n", colorRed, fields[2], err, colorReset)
+			return nil
+		}
+		session.attachments = append(session.attachments, formatAttachment(fields[2], text, ""))
+		fmt.Printf("%sAttached %s (%d tokens) to your next message.%s\n", colorGreen, fields[2], len(strings.Fields(text)), colorReset)
+		return nil
+	}
+	fmt.Printf("%sUnknown MCP server %s%s\n", colorRed, fields[1], colorReset)
+	return nil
+}
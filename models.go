@@ -0,0 +1,167 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"time"
+
+	"code/aili"
+)
+
+const (
+	defaultContextWindow = 2 * maxTokens
+	minPromptBudget      = 1024
+)
+
+var contextWindows = map[string]int{
+	"llama-3.1-70b-versatile":  131072,
+	"llama-3.3-70b-versatile":  131072,
+	"llama-3.1-8b-instant":     131072,
+	"mixtral-8x7b-32768":       32768,
+	"gemma2-9b-it":             8192,
+	"gpt-4o":                   128000,
+	"gpt-4o-mini":              128000,
+	"claude-3-5-sonnet-latest": 200000,
+	"claude-3-5-haiku-latest":  200000,
+	"llama3.1":                 131072,
+}
+
+func (c *APIClient) fetchModels(ctx context.Context) ([]ModelInfo, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
+	defer cancel()
+
+	req, err := c.Provider.NewModelsRequest(ctx)
+	if err != nil {
+		return nil, err
+	}
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return nil, fmt.Errorf("failed to list models: %w", err)
+	}
+	defer response.Body.Close()
+
+	if response.StatusCode != http.StatusOK {
+		body, _ := io.ReadAll(response.Body)
+		return nil, aili.NewAPIError(response, body)
+	}
+
+	models, err := c.Provider.ParseModels(response.Body)
+	if err != nil {
+		return nil, err
+	}
+	sort.Slice(models, func(i, j int) bool { return models[i].ID < models[j].ID })
+	return models, nil
+}
+
+func modelCachePath(provider string) (string, error) {
+	dir, err := os.UserCacheDir()
+	if err != nil {
+		return "", fmt.Errorf("failed to locate cache directory: %w", err)
+	}
+	return filepath.Join(dir, "aili", "models-"+provider+".json"), nil
+}
+
+func saveModelCache(provider string, models []ModelInfo) error {
+	path, err := modelCachePath(provider)
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+		return fmt.Errorf("failed to create cache directory: %w", err)
+	}
+	data, err := json.MarshalIndent(models, "", "  ")
+	if err != nil {
+		return fmt.Errorf("failed to marshal model list: %w", err)
+	}
+	return os.WriteFile(path, data, 0644)
+}
+
+func loadModelCache(provider string) []ModelInfo {
+	path, err := modelCachePath(provider)
+	if err != nil {
+		return nil
+	}
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return nil
+	}
+	var models []ModelInfo
+	if err := json.Unmarshal(data, &models); err != nil {
+		return nil
+	}
+	return models
+}
+
+func completeModel(prefix string, models []ModelInfo) (string, []string) {
+	var matches []string
+	for _, model := range models {
+		if model.ID == prefix {
+			return model.ID, nil
+		}
+		if strings.HasPrefix(model.ID, prefix) {
+			matches = append(matches, model.ID)
+		}
+	}
+	if len(matches) == 1 {
+		return matches[0], nil
+	}
+	return prefix, matches
+}
+
+func runModelsCommand(apiClient *APIClient, currentModel string) error {
+	models, err := apiClient.fetchModels(context.Background())
+	if err != nil {
+		return err
+	}
+	if err := saveModelCache(apiClient.Provider.Name(), models); err != nil {
+		fmt.Fprintf(os.Stderr, "Failed to cache model list: %v\n", err)
+	}
+
+	for _, model := range models {
+		marker := " "
+		if model.ID == currentModel {
+			marker = "*"
+		}
+		if model.ContextWindow > 0 {
+			fmt.Printf("%s %-48s %8d tokens\n", marker, model.ID, model.ContextWindow)
+		} else {
+			fmt.Printf("%s %s\n", marker, model.ID)
+		}
+	}
+	return nil
+}
+
+func contextWindow(config *Config, model string) int {
+	if config.ContextWindow > 0 {
+		return config.ContextWindow
+	}
+	for _, info := range loadModelCache(config.Provider) {
+		if info.ID == model && info.ContextWindow > 0 {
+			return info.ContextWindow
+		}
+	}
+	if window, ok := contextWindows[model]; ok {
+		return window
+	}
+	return defaultContextWindow
+}
+
+func promptBudget(config *Config, model string) int {
+	return max(contextWindow(config, model)-config.MaxTokens, minPromptBudget)
+}
+
+func hasModel(models []ModelInfo, id string) bool {
+	for _, model := range models {
+		if model.ID == id {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,79 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"sort"
+)
+
+type Persona struct {
+	SystemPrompt string   `yaml:"system_prompt"`
+	Model        string   `yaml:"model"`
+	Temperature  *float64 `yaml:"temperature"`
+}
+
+func (p Persona) equal(other Persona) bool {
+	if p.SystemPrompt != other.SystemPrompt || p.Model != other.Model {
+		return false
+	}
+	if p.Temperature == nil || other.Temperature == nil {
+		return p.Temperature == other.Temperature
+	}
+	return *p.Temperature == *other.Temperature
+}
+
+func (s *ChatSession) applyPersona(name string) error {
+	persona, ok := s.config.Personas[name]
+	if !ok {
+		return fmt.Errorf("unknown persona %q", name)
+	}
+
+	if persona.SystemPrompt != "" {
+		s.conversation.setSystemPrompt(persona.SystemPrompt)
+	}
+	if persona.Model != "" {
+		s.model = persona.Model
+	}
+	if persona.Temperature != nil {
+		s.config.Temperature = *persona.Temperature
+	}
+	s.persona = name
+	return nil
+}
+
+func handlePersonaCommand(_ context.Context, session *ChatSession, name string) error {
+	if name == "" {
+		printPersonas(session)
+		return nil
+	}
+
+	if err := session.applyPersona(name); err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		printPersonas(session)
+		return nil
+	}
+	fmt.Printf("%sSwitched to persona '%s' (model %s, temperature %g)%s\n", colorGreen, name, session.model, session.config.Temperature, colorReset)
+	return nil
+}
+
+func printPersonas(session *ChatSession) {
+	if len(session.config.Personas) == 0 {
+		fmt.Printf("%sNo personas configured.%s\n", colorYellow, colorReset)
+		return
+	}
+
+	names := make([]string, 0, len(session.config.Personas))
+	for name := range session.config.Personas {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+
+	fmt.Printf("%sPersonas:%s\n", colorCyan, colorReset)
+	for _, name := range names {
+		marker := " "
+		if name == session.persona {
+			marker = "*"
+		}
+		fmt.Printf(" %s %s\n", marker, name)
+	}
+}
@@ -0,0 +1,149 @@
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"time"
+)
+
+const tokensPerPriceUnit = 1_000_000
+
+type ModelPrice struct {
+	Input  float64 `yaml:"input"`
+	Output float64 `yaml:"output"`
+}
+
+type LedgerEntry struct {
+	PromptTokens     int     `json:"prompt_tokens"`
+	CompletionTokens int     `json:"completion_tokens"`
+	Cost             float64 `json:"cost"`
+}
+
+var defaultPricing = map[string]ModelPrice{
+	"llama-3.1-70b-versatile":  {Input: 0.59, Output: 0.79},
+	"llama-3.3-70b-versatile":  {Input: 0.59, Output: 0.79},
+	"llama-3.1-8b-instant":     {Input: 0.05, Output: 0.08},
+	"mixtral-8x7b-32768":       {Input: 0.24, Output: 0.24},
+	"gemma2-9b-it":             {Input: 0.20, Output: 0.20},
+	"gpt-4o":                   {Input: 2.50, Output: 10.00},
+	"gpt-4o-mini":              {Input: 0.15, Output: 0.60},
+	"claude-3-5-sonnet-latest": {Input: 3.00, Output: 15.00},
+	"claude-3-5-haiku-latest":  {Input: 0.80, Output: 4.00},
+}
+
+func priceFor(config *Config, model string) (ModelPrice, bool) {
+	if price, ok := config.Pricing[model]; ok {
+		return price, true
+	}
+	if config.Provider == "ollama" {
+		return ModelPrice{}, true
+	}
+	price, ok := defaultPricing[model]
+	return price, ok
+}
+
+func estimateCost(config *Config, model string, promptTokens, completionTokens int) float64 {
+	price, ok := priceFor(config, model)
+	if !ok {
+		return 0
+	}
+	return (float64(promptTokens)*price.Input + float64(completionTokens)*price.Output) / tokensPerPriceUnit
+}
+
+func formatCost(cost float64) string {
+	if cost < 0.01 {
+		return fmt.Sprintf("$%.4f", cost)
+	}
+	return fmt.Sprintf("$%.2f", cost)
+}
+
+func ledgerPath(month time.Time) (string, error) {
+	dir, err := dataDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "ledger", month.Format("2006-01")+".json"), nil
+}
+
+func loadLedger(month time.Time) (map[string]*LedgerEntry, error) {
+	path, err := ledgerPath(month)
+	if err != nil {
+		return nil, err
+	}
+	ledger := map[string]*LedgerEntry{}
+	data, err := os.ReadFile(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return ledger, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to read ledger: %w", err)
+	}
+	if err := json.Unmarshal(data, &ledger); err != nil {
+		return nil, fmt.Errorf("failed to parse ledger %s: %w", path, err)
+	}
+	return ledger, nil
+}
+
+func recordSpend(model string, promptTokens, completionTokens int, cost float64) error {
+	now := time.Now()
+	ledger, err := loadLedger(now)
+	if err != nil {
+		return err
+	}
+	entry, ok := ledger[model]
+	if !ok {
+		entry = &LedgerEntry{}
+		ledger[model] = entry
+	}
+	entry.PromptTokens += promptTokens
+	entry.CompletionTokens += completionTokens
+	entry.Cost += cost
+
+	path, err := ledgerPath(now)
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+		return fmt.Errorf("failed to create ledger directory: %w", err)
+	}
+	data, err := json.MarshalIndent(ledger, "", "  ")
+	if err != nil {
+		return fmt.Errorf("failed to marshal ledger: %w", err)
+	}
+	return os.WriteFile(path, data, 0644)
+}
+
+func monthlySpend() (float64, error) {
+	ledger, err := loadLedger(time.Now())
+	if err != nil {
+		return 0, err
+	}
+	total := 0.0
+	for _, entry := range ledger {
+		total += entry.Cost
+	}
+	return total, nil
+}
+
+func (c *Conversation) addCost(cost float64) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.cost += cost
+}
+
+func (c *Conversation) totalCost() float64 {
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	return c.cost
+}
+
+func printSpendSummary(conversation *Conversation) {
+	line := "Session cost: " + formatCost(conversation.totalCost())
+	if month, err := monthlySpend(); err == nil {
+		line += fmt.Sprintf(" (%s this month)", formatCost(month))
+	}
+	fmt.Printf("%s%s%s\n", colorDim, line, colorReset)
+}
@@ -0,0 +1,52 @@
+package main
+
+import (
+	"time"
+
+	"code/aili"
+)
+
+type (
+	ChatProvider = aili.Provider
+	ModelInfo    = aili.ModelInfo
+	Message      = aili.Message
+	Completion   = aili.Completion
+	ToolCall     = aili.ToolCall
+	ToolFunction = aili.ToolFunction
+	APIError     = aili.APIError
+)
+
+var (
+	errStreamCancelled   = aili.ErrStreamCancelled
+	errStreamInterrupted = aili.ErrStreamInterrupted
+)
+
+func (c *Config) clientConfig(provider, apiKey string) aili.Config {
+	settings := aili.Config{
+		Provider:           provider,
+		APIKey:             apiKey,
+		Temperature:        c.Temperature,
+		TopP:               c.TopP,
+		MaxTokens:          c.MaxTokens,
+		Candidates:         c.Candidates,
+		CandidateSelection: c.CandidateSelection,
+		JSONMode:           c.ResponseFormat == "json_object",
+		Schema:             c.schema,
+		MaxAttempts:        c.MaxAttempts,
+		RetryBackoff:       time.Duration(c.RetryBackoffMs) * time.Millisecond,
+		ConnectTimeout:     time.Duration(c.ConnectTimeout) * time.Second,
+		StreamReconnect:    c.StreamReconnect,
+	}
+	if provider == "ollama" {
+		settings.BaseURL, settings.Model = c.OllamaURL, c.OllamaModel
+	}
+	return settings
+}
+
+func newProvider(config *Config) ChatProvider {
+	return newKeyedProvider(config, config.Provider, config.providerKey(config.Provider))
+}
+
+func newKeyedProvider(config *Config, provider, apiKey string) ChatProvider {
+	return aili.NewProvider(config.clientConfig(provider, apiKey))
+}
@@ -0,0 +1,262 @@
+package main
+
+import (
+	"context"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"log"
+	"math"
+	"net/http"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"time"
+
+	"code/aili"
+)
+
+const (
+	ragChunkTokens = 200
+	ragBatchSize   = 64
+)
+
+type RAGIndex struct {
+	Root    string     `json:"root"`
+	Model   string     `json:"model"`
+	Created time.Time  `json:"created"`
+	Chunks  []RAGChunk `json:"chunks"`
+}
+
+type RAGChunk struct {
+	Path   string    `json:"path"`
+	Text   string    `json:"text"`
+	Vector []float64 `json:"vector"`
+}
+
+type scoredChunk struct {
+	chunk *RAGChunk
+	score float64
+}
+
+func embeddingModel(config *Config) (string, error) {
+	if config.EmbeddingModel != "" {
+		return config.EmbeddingModel, nil
+	}
+	switch config.Provider {
+	case "openai":
+		return "text-embedding-3-small", nil
+	case "ollama":
+		return "nomic-embed-text", nil
+	}
+	return "", fmt.Errorf("set embedding_model to use embeddings with the %s provider", config.Provider)
+}
+
+func (c *APIClient) embed(ctx context.Context, inputs []string, model string) ([][]float64, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
+	defer cancel()
+
+	req, err := c.Provider.NewEmbeddingsRequest(ctx, inputs, model)
+	if err != nil {
+		return nil, err
+	}
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return nil, fmt.Errorf("failed to fetch embeddings: %w", err)
+	}
+	defer response.Body.Close()
+
+	if response.StatusCode != http.StatusOK {
+		body, _ := io.ReadAll(response.Body)
+		return nil, aili.NewAPIError(response, body)
+	}
+	vectors, err := c.Provider.ParseEmbeddings(response.Body)
+	if err != nil {
+		return nil, err
+	}
+	if len(vectors) != len(inputs) {
+		return nil, fmt.Errorf("expected %d embeddings, got %d", len(inputs), len(vectors))
+	}
+	return vectors, nil
+}
+
+func ragIndexPath(root string) (string, error) {
+	dir, err := dataDir()
+	if err != nil {
+		return "", err
+	}
+	sum := sha256.Sum256([]byte(root))
+	return filepath.Join(dir, "index", hex.EncodeToString(sum[:8])+".json"), nil
+}
+
+func runIndexCommand(apiClient *APIClient, args []string) error {
+	if len(args) != 1 {
+		return errors.New("usage: index <dir>")
+	}
+	model, err := embeddingModel(apiClient.config)
+	if err != nil {
+		return err
+	}
+	workspace, err := openWorkspace(args[0])
+	if err != nil {
+		return err
+	}
+
+	index := &RAGIndex{Root: workspace.root, Model: model, Created: time.Now()}
+	for _, file := range workspace.files {
+		if file.Size > maxWorkspaceFileBytes {
+			continue
+		}
+		content, err := readAttachment(filepath.Join(workspace.root, filepath.FromSlash(file.Path)))
+		if err != nil {
+			continue
+		}
+		for _, chunk := range chunkByTokens(content, ragChunkTokens) {
+			if strings.TrimSpace(chunk) != "" {
+				index.Chunks = append(index.Chunks, RAGChunk{Path: file.Path, Text: chunk})
+			}
+		}
+	}
+
+	if err := embedChunks(context.Background(), apiClient, index.Chunks, model); err != nil {
+		return err
+	}
+
+	path, err := ragIndexPath(workspace.root)
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+		return fmt.Errorf("failed to create index directory: %w", err)
+	}
+	data, err := json.Marshal(index)
+	if err != nil {
+		return fmt.Errorf("failed to marshal index: %w", err)
+	}
+	if err := os.WriteFile(path, data, 0644); err != nil {
+		return fmt.Errorf("failed to write index: %w", err)
+	}
+	fmt.Printf("Indexed %d files into %d chunks at %s\n", len(workspace.files), len(index.Chunks), path)
+	return nil
+}
+
+func embedChunks(ctx context.Context, apiClient *APIClient, chunks []RAGChunk, model string) error {
+	for start := 0; start < len(chunks); start += ragBatchSize {
+		batch := chunks[start:min(start+ragBatchSize, len(chunks))]
+		inputs := make([]string, len(batch))
+		for i, chunk := range batch {
+			inputs[i] = chunk.Path + "\n" + chunk.Text
+		}
+		vectors, err := apiClient.embed(ctx, inputs, model)
+		if err != nil {
+			return err
+		}
+		for i := range batch {
+			batch[i].Vector = vectors[i]
+		}
+		fmt.Printf("\rEmbedded %d/%d chunks", start+len(batch), len(chunks))
+	}
+	fmt.Println()
+	return nil
+}
+
+func (s *ChatSession) indexAttachment(ctx context.Context, path, content string) error {
+	model := ""
+	if s.rag != nil {
+		model = s.rag.Model
+	} else {
+		var err error
+		if model, err = embeddingModel(s.config); err != nil {
+			return err
+		}
+	}
+
+	var chunks []RAGChunk
+	for _, chunk := range chunkByTokens(content, ragChunkTokens) {
+		if strings.TrimSpace(chunk) != "" {
+			chunks = append(chunks, RAGChunk{Path: path, Text: chunk})
+		}
+	}
+	if err := embedChunks(ctx, s.apiClient, chunks, model); err != nil {
+		return fmt.Errorf("failed to index %s: %w", path, err)
+	}
+	if s.rag == nil {
+		s.rag = &RAGIndex{Root: path, Model: model, Created: time.Now()}
+	}
+	s.rag.Chunks = append(s.rag.Chunks, chunks...)
+	fmt.Printf("%sIndexed %s into %d chunks, relevant excerpts will be added to each message.%s\n", colorGreen, path, len(chunks), colorReset)
+	return nil
+}
+
+func loadRAGIndex(dir string) (*RAGIndex, error) {
+	root, err := filepath.Abs(dir)
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve %s: %w", dir, err)
+	}
+	if resolved, err := filepath.EvalSymlinks(root); err == nil {
+		root = resolved
+	}
+	path, err := ragIndexPath(root)
+	if err != nil {
+		return nil, err
+	}
+	data, err := os.ReadFile(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return nil, fmt.Errorf("%s has not been indexed, run the index subcommand first", dir)
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to read index: %w", err)
+	}
+	var index RAGIndex
+	if err := json.Unmarshal(data, &index); err != nil {
+		return nil, fmt.Errorf("failed to parse index %s: %w", path, err)
+	}
+	return &index, nil
+}
+
+func cosineSimilarity(a, b []float64) float64 {
+	if len(a) != len(b) {
+		return 0
+	}
+	var dot, normA, normB float64
+	for i := range a {
+		dot += a[i] * b[i]
+		normA += a[i] * a[i]
+		normB += b[i] * b[i]
+	}
+	if normA == 0 || normB == 0 {
+		return 0
+	}
+	return dot / (math.Sqrt(normA) * math.Sqrt(normB))
+}
+
+func (index *RAGIndex) search(query []float64, k int) []scoredChunk {
+	scored := make([]scoredChunk, 0, len(index.Chunks))
+	for i := range index.Chunks {
+		scored = append(scored, scoredChunk{chunk: &index.Chunks[i], score: cosineSimilarity(query, index.Chunks[i].Vector)})
+	}
+	sort.Slice(scored, func(i, j int) bool { return scored[i].score > scored[j].score })
+	return scored[:min(k, len(scored))]
+}
+
+func (s *ChatSession) retrieve(ctx context.Context, query string) string {
+	if s.rag == nil || len(s.rag.Chunks) == 0 {
+		return ""
+	}
+	vectors, err := s.apiClient.embed(ctx, []string{query}, s.rag.Model)
+	if err != nil {
+		log.Printf("Failed to retrieve context: %v", err)
+		return ""
+	}
+
+	var note strings.Builder
+	note.WriteString("Relevant excerpts from the indexed documents, use them if they help answer:\n")
+	for _, match := range s.rag.search(vectors[0], s.config.RAGTopK) {
+		fmt.Fprintf(&note, "\n[%s]\n%s\n", match.chunk.Path, match.chunk.Text)
+	}
+	return note.String()
+}
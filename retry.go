@@ -0,0 +1,126 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"strconv"
+	"strings"
+)
+
+func (c *Conversation) dropLastExchange(keepUser bool) ([]Message, bool) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	index := -1
+	for i := len(c.History) - 1; i >= 0; i-- {
+		if c.History[i].Role == "user" {
+			index = i
+			break
+		}
+	}
+	if index < 0 {
+		return nil, false
+	}
+	if keepUser {
+		index++
+	}
+
+	removed := append([]Message(nil), c.History[index:]...)
+	c.History = c.History[:index]
+	c.tokenCount -= countTokens(removed)
+	return removed, true
+}
+
+func (c *Conversation) hasUserMessage() bool {
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	for _, msg := range c.History {
+		if msg.Role == "user" {
+			return true
+		}
+	}
+	return false
+}
+
+func lastReply(messages []Message) (Message, bool) {
+	for i := len(messages) - 1; i >= 0; i-- {
+		if messages[i].Role == "assistant" && messages[i].Content != "" {
+			return messages[i], true
+		}
+	}
+	return Message{}, false
+}
+
+func handleRetryCommand(ctx context.Context, session *ChatSession, args string) error {
+	temperature := session.config.Temperature
+	if args != "" {
+		value, err := strconv.ParseFloat(args, 64)
+		if err != nil || value < 0 || value > 2 {
+			fmt.Printf("%sUsage: /retry [temperature], temperature must be between 0 and 2%s\n", colorYellow, colorReset)
+			return nil
+		}
+		temperature = value
+	}
+	if !session.conversation.hasUserMessage() {
+		fmt.Printf("%sNothing to retry yet.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if !session.allowRequest() {
+		return nil
+	}
+
+	removed, ok := session.conversation.dropLastExchange(true)
+	if !ok {
+		fmt.Printf("%sNothing to retry yet.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if reply, ok := lastReply(removed); ok {
+		session.alternatives = append(session.alternatives, reply)
+	}
+	history := session.conversation.getHistory()
+	userInput := history[len(history)-1].Content
+
+	if temperature != session.config.Temperature {
+		previous := session.config.Temperature
+		session.config.Temperature = temperature
+		defer func() { session.config.Temperature = previous }()
+		fmt.Printf("%sRetrying at temperature %g (%d earlier replies in /alternatives)%s\n", colorDim, temperature, len(session.alternatives), colorReset)
+	} else {
+		fmt.Printf("%sRetrying (%d earlier replies in /alternatives)%s\n", colorDim, len(session.alternatives), colorReset)
+	}
+	return respond(ctx, session, userInput, session.model)
+}
+
+func handleAlternativesCommand(_ context.Context, session *ChatSession, args string) error {
+	if len(session.alternatives) == 0 {
+		fmt.Printf("%sNo alternative replies. Use /retry to generate one.%s\n", colorYellow, colorReset)
+		return nil
+	}
+	if args == "" {
+		fmt.Printf("%sAlternative replies:%s\n", colorCyan, colorReset)
+		for i, reply := range session.alternatives {
+			fmt.Printf("  %d. [%s] %s\n", i+1, reply.Model, truncateString(strings.Join(strings.Fields(reply.Content), " "), maxSessionTitle*2))
+		}
+		fmt.Printf("%sUse /alternatives <n> to switch to one.%s\n", colorDim, colorReset)
+		return nil
+	}
+
+	index, err := strconv.Atoi(args)
+	if err != nil || index < 1 || index > len(session.alternatives) {
+		fmt.Printf("%sAlternative must be between 1 and %d%s\n", colorRed, len(session.alternatives), colorReset)
+		return nil
+	}
+	chosen := session.alternatives[index-1]
+	removed, ok := session.conversation.dropLastExchange(true)
+	if !ok {
+		fmt.Printf("%sThe conversation has no user message to reply to.%s\n", colorRed, colorReset)
+		return nil
+	}
+	session.alternatives = append(session.alternatives[:index-1], session.alternatives[index:]...)
+	if reply, ok := lastReply(removed); ok {
+		session.alternatives = append(session.alternatives, reply)
+	}
+	session.conversation.appendMessage(chosen)
+	session.printResponse("AI", chosen.Model, false, renderForDisplay(session.config, chosen.Content))
+	session.saveChanges()
+	return nil
+}
@@ -0,0 +1,224 @@
+package main
+
+import (
+	"encoding/json"
+	"fmt"
+	"math"
+	"os"
+	"reflect"
+	"regexp"
+	"slices"
+	"sort"
+	"strings"
+	"unicode/utf8"
+)
+
+func (c *Config) loadResponseSchema() error {
+	c.schema = nil
+	if c.ResponseSchema == "" {
+		return nil
+	}
+	data, err := os.ReadFile(c.ResponseSchema)
+	if err != nil {
+		return fmt.Errorf("failed to read response schema: %w", err)
+	}
+	var schema map[string]interface{}
+	if err := json.Unmarshal(data, &schema); err != nil {
+		return fmt.Errorf("response schema %s is not a JSON object: %w", c.ResponseSchema, err)
+	}
+	c.schema = schema
+	return nil
+}
+
+func (c *Config) validateResponse(value json.RawMessage) []string {
+	if c.schema == nil {
+		return nil
+	}
+	var decoded interface{}
+	if err := json.Unmarshal(value, &decoded); err != nil {
+		return []string{err.Error()}
+	}
+	return validateSchema(decoded, c.schema, "$")
+}
+
+func validateSchema(value interface{}, schema map[string]interface{}, path string) []string {
+	var violations []string
+	fail := func(format string, args ...interface{}) {
+		violations = append(violations, path+" "+fmt.Sprintf(format, args...))
+	}
+
+	if types := schemaTypes(schema["type"]); len(types) > 0 && !slices.ContainsFunc(types, func(name string) bool { return matchesType(value, name) }) {
+		fail("must be %s, got %s", strings.Join(types, " or "), jsonTypeName(value))
+		return violations
+	}
+	if enum, ok := schema["enum"].([]interface{}); ok && !slices.ContainsFunc(enum, func(option interface{}) bool { return reflect.DeepEqual(option, value) }) {
+		fail("must be one of %s", compactJSON(enum))
+	}
+	if constant, ok := schema["const"]; ok && !reflect.DeepEqual(constant, value) {
+		fail("must be %s", compactJSON(constant))
+	}
+
+	switch v := value.(type) {
+	case string:
+		length := float64(utf8.RuneCountInString(v))
+		if limit, ok := schemaNumber(schema, "minLength"); ok && length < limit {
+			fail("must be at least %g characters", limit)
+		}
+		if limit, ok := schemaNumber(schema, "maxLength"); ok && length > limit {
+			fail("must be at most %g characters", limit)
+		}
+		if pattern, ok := schema["pattern"].(string); ok {
+			if re, err := regexp.Compile(pattern); err == nil && !re.MatchString(v) {
+				fail("must match %q", pattern)
+			}
+		}
+	case float64:
+		if limit, ok := schemaNumber(schema, "minimum"); ok && v < limit {
+			fail("must be at least %g", limit)
+		}
+		if limit, ok := schemaNumber(schema, "maximum"); ok && v > limit {
+			fail("must be at most %g", limit)
+		}
+		if limit, ok := schemaNumber(schema, "exclusiveMinimum"); ok && v <= limit {
+			fail("must be greater than %g", limit)
+		}
+		if limit, ok := schemaNumber(schema, "exclusiveMaximum"); ok && v >= limit {
+			fail("must be less than %g", limit)
+		}
+	case []interface{}:
+		if limit, ok := schemaNumber(schema, "minItems"); ok && float64(len(v)) < limit {
+			fail("must have at least %g items", limit)
+		}
+		if limit, ok := schemaNumber(schema, "maxItems"); ok && float64(len(v)) > limit {
+			fail("must have at most %g items", limit)
+		}
+		if items, ok := schema["items"].(map[string]interface{}); ok {
+			for i, item := range v {
+				violations = append(violations, validateSchema(item, items, fmt.Sprintf("%s[%d]", path, i))...)
+			}
+		}
+	case map[string]interface{}:
+		required, _ := schema["required"].([]interface{})
+		for _, name := range required {
+			if name, ok := name.(string); ok {
+				if _, present := v[name]; !present {
+					fail("is missing required property %q", name)
+				}
+			}
+		}
+		properties, _ := schema["properties"].(map[string]interface{})
+		names := make([]string, 0, len(v))
+		for name := range v {
+			names = append(names, name)
+		}
+		sort.Strings(names)
+		for _, name := range names {
+			if property, ok := properties[name].(map[string]interface{}); ok {
+				violations = append(violations, validateSchema(v[name], property, path+"."+name)...)
+				continue
+			}
+			if _, declared := properties[name]; declared {
+				continue
+			}
+			switch additional := schema["additionalProperties"].(type) {
+			case bool:
+				if !additional {
+					fail("has unexpected property %q", name)
+				}
+			case map[string]interface{}:
+				violations = append(violations, validateSchema(v[name], additional, path+"."+name)...)
+			}
+		}
+	}
+
+	for _, sub := range schemaList(schema["allOf"]) {
+		violations = append(violations, validateSchema(value, sub, path)...)
+	}
+	if options := schemaList(schema["anyOf"]); len(options) > 0 && countMatches(value, options, path) == 0 {
+		fail("must match at least one schema in anyOf")
+	}
+	if options := schemaList(schema["oneOf"]); len(options) > 0 && countMatches(value, options, path) != 1 {
+		fail("must match exactly one schema in oneOf")
+	}
+	return violations
+}
+
+func countMatches(value interface{}, schemas []map[string]interface{}, path string) int {
+	matches := 0
+	for _, schema := range schemas {
+		if len(validateSchema(value, schema, path)) == 0 {
+			matches++
+		}
+	}
+	return matches
+}
+
+func schemaList(raw interface{}) []map[string]interface{} {
+	list, _ := raw.([]interface{})
+	var schemas []map[string]interface{}
+	for _, item := range list {
+		if schema, ok := item.(map[string]interface{}); ok {
+			schemas = append(schemas, schema)
+		}
+	}
+	return schemas
+}
+
+func schemaTypes(raw interface{}) []string {
+	switch t := raw.(type) {
+	case string:
+		return []string{t}
+	case []interface{}:
+		var types []string
+		for _, item := range t {
+			if name, ok := item.(string); ok {
+				types = append(types, name)
+			}
+		}
+		return types
+	}
+	return nil
+}
+
+func schemaNumber(schema map[string]interface{}, key string) (float64, bool) {
+	value, ok := schema[key].(float64)
+	return value, ok
+}
+
+func matchesType(value interface{}, name string) bool {
+	switch name {
+	case "integer":
+		number, ok := value.(float64)
+		return ok && number == math.Trunc(number)
+	case "number":
+		_, ok := value.(float64)
+		return ok
+	}
+	return jsonTypeName(value) == name
+}
+
+func jsonTypeName(value interface{}) string {
+	switch value.(type) {
+	case nil:
+		return "null"
+	case bool:
+		return "boolean"
+	case float64:
+		return "number"
+	case string:
+		return "string"
+	case []interface{}:
+		return "array"
+	case map[string]interface{}:
+		return "object"
+	}
+	return fmt.Sprintf("%T", value)
+}
+
+func compactJSON(value interface{}) string {
+	data, err := json.Marshal(value)
+	if err != nil {
+		return fmt.Sprint(value)
+	}
+	return string(data)
+}
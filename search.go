@@ -0,0 +1,180 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"html"
+	"io"
+	"net/http"
+	"net/url"
+	"regexp"
+	"strings"
+	"time"
+)
+
+const (
+	maxSearchResults    = 10
+	searchSnippetLength = 300
+)
+
+var (
+	htmlTagPattern   = regexp.MustCompile(`<[^>]*>`)
+	searchProviders  = []string{"searxng", "brave", "bing"}
+	searchEndpoints  = map[string]string{"brave": "https://api.search.brave.com/res/v1/web/search", "bing": "https://api.bing.microsoft.com/v7.0/search"}
+	searchKeyHeaders = map[string]string{"brave": "X-Subscription-Token", "bing": "Ocp-Apim-Subscription-Key"}
+)
+
+type SearchResult struct {
+	Title   string
+	URL     string
+	Snippet string
+}
+
+func webSearchTool() Tool {
+	return Tool{
+		Name:        "web_search",
+		Description: "Search the web for current information. Returns numbered results with titles, URLs and snippets; cite the URLs you use.",
+		Parameters: objectSchema(map[string]interface{}{
+			"query": map[string]interface{}{"type": "string", "description": "search query"},
+			"count": map[string]interface{}{"type": "integer", "description": fmt.Sprintf("number of results, at most %d", maxSearchResults)},
+		}, "query"),
+		Run: runWebSearchTool,
+	}
+}
+
+func runWebSearchTool(ctx context.Context, session *ChatSession, arguments string) (string, error) {
+	var args struct {
+		Query string `json:"query"`
+		Count int    `json:"count"`
+	}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	if strings.TrimSpace(args.Query) == "" {
+		return "", errors.New("query must not be empty")
+	}
+	count := session.config.SearchResults
+	if args.Count > 0 {
+		count = min(args.Count, maxSearchResults)
+	}
+
+	results, err := session.apiClient.search(ctx, args.Query, count)
+	if err != nil {
+		return "", err
+	}
+	return formatSearchResults(args.Query, results), nil
+}
+
+func (c *APIClient) search(ctx context.Context, query string, count int) ([]SearchResult, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
+	defer cancel()
+
+	req, err := newSearchRequest(ctx, c.config, query, count)
+	if err != nil {
+		return nil, err
+	}
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return nil, fmt.Errorf("failed to search: %w", err)
+	}
+	defer response.Body.Close()
+
+	if response.StatusCode != http.StatusOK {
+		body, _ := io.ReadAll(io.LimitReader(response.Body, 512))
+		return nil, fmt.Errorf("search failed with %s: %s", response.Status, strings.TrimSpace(string(body)))
+	}
+	results, err := parseSearchResults(c.config.SearchProvider, response.Body)
+	if err != nil {
+		return nil, err
+	}
+	if len(results) > count {
+		results = results[:count]
+	}
+	return results, nil
+}
+
+func newSearchRequest(ctx context.Context, config *Config, query string, count int) (*http.Request, error) {
+	endpoint := searchEndpoints[config.SearchProvider]
+	if config.SearchURL != "" {
+		endpoint = config.SearchURL
+	}
+	params := url.Values{"q": {query}}
+	if config.SearchProvider == "searxng" {
+		endpoint = strings.TrimSuffix(endpoint, "/") + "/search"
+		params.Set("format", "json")
+	} else {
+		params.Set("count", fmt.Sprint(count))
+	}
+
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, endpoint+"?"+params.Encode(), nil)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create search request: %w", err)
+	}
+	req.Header.Set("Accept", "application/json")
+	if header, ok := searchKeyHeaders[config.SearchProvider]; ok {
+		req.Header.Set(header, config.SearchAPIKey)
+	}
+	return req, nil
+}
+
+func parseSearchResults(provider string, body io.Reader) ([]SearchResult, error) {
+	type item struct {
+		Title       string `json:"title"`
+		Name        string `json:"name"`
+		URL         string `json:"url"`
+		Content     string `json:"content"`
+		Description string `json:"description"`
+		Snippet     string `json:"snippet"`
+	}
+	var parsed struct {
+		Results []item `json:"results"`
+		Web     struct {
+			Results []item `json:"results"`
+		} `json:"web"`
+		WebPages struct {
+			Value []item `json:"value"`
+		} `json:"webPages"`
+	}
+	if err := json.NewDecoder(body).Decode(&parsed); err != nil {
+		return nil, fmt.Errorf("failed to decode %s search response: %w", provider, err)
+	}
+
+	items := parsed.Results
+	switch provider {
+	case "brave":
+		items = parsed.Web.Results
+	case "bing":
+		items = parsed.WebPages.Value
+	}
+	results := make([]SearchResult, 0, len(items))
+	for _, item := range items {
+		results = append(results, SearchResult{
+			Title:   cleanSnippet(item.Title + item.Name),
+			URL:     item.URL,
+			Snippet: cleanSnippet(item.Content + item.Description + item.Snippet),
+		})
+	}
+	return results, nil
+}
+
+func cleanSnippet(text string) string {
+	text = html.UnescapeString(htmlTagPattern.ReplaceAllString(text, ""))
+	return truncateString(strings.Join(strings.Fields(text), " "), searchSnippetLength)
+}
+
+func formatSearchResults(query string, results []SearchResult) string {
+	if len(results) == 0 {
+		return fmt.Sprintf("No results for %q.", query)
+	}
+	var b strings.Builder
+	fmt.Fprintf(&b, "Search results for %q (cite sources as [n] with their URL):\n", query)
+	for i, result := range results {
+		fmt.Fprintf(&b, "\n[%d] %s\n%s\n", i+1, result.Title, result.URL)
+		if result.Snippet != "" {
+			fmt.Fprintf(&b, "%s\n", result.Snippet)
+		}
+	}
+	return b.String()
+}
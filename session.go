@@ -0,0 +1,288 @@
+package main
+
+import (
+	"errors"
+	"fmt"
+	"log"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"time"
+)
+
+const maxSessionTitle = 40
+
+func dataDir() (string, error) {
+	if dataHome := os.Getenv("XDG_DATA_HOME"); dataHome != "" {
+		return filepath.Join(dataHome, "aili"), nil
+	}
+	home, err := os.UserHomeDir()
+	if err != nil {
+		return "", fmt.Errorf("failed to locate home directory: %w", err)
+	}
+	return filepath.Join(home, ".local", "share", "aili"), nil
+}
+
+func sessionsDir() (string, error) {
+	dir, err := dataDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "sessions"), nil
+}
+
+func sessionPath(id string) (string, error) {
+	if id == "" || filepath.Base(id) != id {
+		return "", fmt.Errorf("invalid session id %q", id)
+	}
+	dir, err := sessionsDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, id+".json"), nil
+}
+
+func newSessionID() string {
+	base := time.Now().Format("20060102-150405")
+	id := base
+	for n := 2; sessionExists(id); n++ {
+		id = fmt.Sprintf("%s-%d", base, n)
+	}
+	return id
+}
+
+func sessionExists(id string) bool {
+	path, err := sessionPath(id)
+	if err != nil {
+		return false
+	}
+	_, err = os.Stat(path)
+	return err == nil
+}
+
+func (s *ChatSession) persist() error {
+	path, err := sessionPath(s.sessionID)
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+		return fmt.Errorf("failed to create sessions directory: %w", err)
+	}
+	return writeHistoryFile(path, s.conversation.getHistory(), s.currentSettings())
+}
+
+func resumeSession(session *ChatSession, id string) error {
+	path, err := sessionPath(id)
+	if err != nil {
+		return err
+	}
+	resumed, settings, err := loadConversation(path)
+	if err != nil {
+		return fmt.Errorf("failed to resume session %s: %w", id, err)
+	}
+
+	session.sessionID = id
+	session.parentID, session.branchPoint = "", 0
+	session.conversation.replaceWith(resumed)
+	if settings != nil {
+		session.parentID, session.branchPoint = settings.Parent, settings.BranchPoint
+		if !session.ignoreSettings {
+			session.applySettings(settings)
+		}
+	}
+	fmt.Printf("%sResumed session %s%s\n", colorGreen, id, colorReset)
+	printConversationSummary(session.conversation)
+	return nil
+}
+
+type sessionInfo struct {
+	ID       string
+	Title    string
+	Messages int
+	Model    string
+	Parent   string
+	Modified time.Time
+}
+
+func runSessionsCommand(args []string) error {
+	if len(args) == 0 {
+		return listSessions()
+	}
+
+	switch args[0] {
+	case "list":
+		return listSessions()
+	case "rename":
+		if len(args) != 3 {
+			return errors.New("usage: sessions rename <id> <new-id>")
+		}
+		return renameSession(args[1], args[2])
+	case "delete":
+		if len(args) != 2 {
+			return errors.New("usage: sessions delete <id>")
+		}
+		return deleteSession(args[1])
+	case "search":
+		query := strings.TrimSpace(strings.Join(args[1:], " "))
+		if query == "" {
+			return errors.New("usage: sessions search <text>")
+		}
+		return searchSessions(query)
+	case "export":
+		if len(args) != 3 && len(args) != 4 {
+			return errors.New("usage: sessions export <id> md|json|html [path]")
+		}
+		path := ""
+		if len(args) == 4 {
+			path = args[3]
+		}
+		return exportSession(args[1], args[2], path)
+	default:
+		return fmt.Errorf("unknown sessions command %q, expected list, resume, rename, delete, search or export", args[0])
+	}
+}
+
+func loadSessionInfos() ([]sessionInfo, error) {
+	dir, err := sessionsDir()
+	if err != nil {
+		return nil, err
+	}
+	paths, err := filepath.Glob(filepath.Join(dir, "*.json"))
+	if err != nil {
+		return nil, fmt.Errorf("failed to list sessions: %w", err)
+	}
+
+	var infos []sessionInfo
+	for _, path := range paths {
+		stat, err := os.Stat(path)
+		if err != nil {
+			continue
+		}
+		conversation, settings, err := loadConversation(path)
+		if err != nil {
+			log.Printf("Skipping unreadable session %s: %v", path, err)
+			continue
+		}
+
+		info := sessionInfo{
+			ID:       strings.TrimSuffix(filepath.Base(path), ".json"),
+			Title:    sessionTitle(conversation.History),
+			Messages: len(conversation.History),
+			Modified: stat.ModTime(),
+		}
+		if settings != nil {
+			info.Model = settings.Model
+			info.Parent = settings.Parent
+		}
+		infos = append(infos, info)
+	}
+
+	sort.Slice(infos, func(i, j int) bool { return infos[i].Modified.After(infos[j].Modified) })
+	return infos, nil
+}
+
+func sessionTitle(history []Message) string {
+	for _, msg := range history {
+		if msg.Role == "user" {
+			return truncateString(strings.Join(strings.Fields(msg.Content), " "), maxSessionTitle)
+		}
+	}
+	return "(empty)"
+}
+
+func listSessions() error {
+	infos, err := loadSessionInfos()
+	if err != nil {
+		return err
+	}
+	if len(infos) == 0 {
+		fmt.Println("No saved sessions.")
+		return nil
+	}
+
+	for _, info := range infos {
+		fmt.Printf("%s%s%s  %s\n", colorCyan, info.ID, colorReset, info.Title)
+		fmt.Printf("    %d messages, model %s, modified %s\n", info.Messages, info.Model, info.Modified.Format("2006-01-02 15:04"))
+		if info.Parent != "" {
+			fmt.Printf("    forked from %s\n", info.Parent)
+		}
+	}
+	return nil
+}
+
+func renameSession(id, newID string) error {
+	oldPath, err := sessionPath(id)
+	if err != nil {
+		return err
+	}
+	newPath, err := sessionPath(newID)
+	if err != nil {
+		return err
+	}
+	if _, err := os.Stat(newPath); err == nil {
+		return fmt.Errorf("session %s already exists", newID)
+	}
+	if err := os.Rename(oldPath, newPath); err != nil {
+		return fmt.Errorf("failed to rename session: %w", err)
+	}
+
+	fmt.Printf("%sRenamed session %s to %s%s\n", colorGreen, id, newID, colorReset)
+	return nil
+}
+
+func deleteSession(id string) error {
+	path, err := sessionPath(id)
+	if err != nil {
+		return err
+	}
+	if err := os.Remove(path); err != nil {
+		return fmt.Errorf("failed to delete session: %w", err)
+	}
+
+	fmt.Printf("%sDeleted session %s%s\n", colorGreen, id, colorReset)
+	return nil
+}
+
+func searchSessions(query string) error {
+	dir, err := sessionsDir()
+	if err != nil {
+		return err
+	}
+	infos, err := loadSessionInfos()
+	if err != nil {
+		return err
+	}
+
+	needle := strings.ToLower(query)
+	matches := 0
+	for _, info := range infos {
+		conversation, _, err := loadConversation(filepath.Join(dir, info.ID+".json"))
+		if err != nil {
+			continue
+		}
+		for _, msg := range conversation.History {
+			if !strings.Contains(strings.ToLower(msg.Content), needle) {
+				continue
+			}
+			matches++
+			fmt.Printf("%s%s%s [%s] %s\n", colorCyan, info.ID, colorReset, msg.Role, searchSnippet(msg.Content, needle))
+		}
+	}
+
+	if matches == 0 {
+		fmt.Printf("No sessions mention %q.\n", query)
+	}
+	return nil
+}
+
+func searchSnippet(content, needle string) string {
+	content = strings.Join(strings.Fields(content), " ")
+	index := strings.Index(strings.ToLower(content), needle)
+	if index < 0 {
+		return truncateString(content, maxSessionTitle*2)
+	}
+	start := max(0, index-maxSessionTitle/2)
+	end := min(len(content), index+len(needle)+maxSessionTitle/2)
+	return "..." + content[start:end] + "..."
+}
@@ -0,0 +1,78 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"errors"
+	"fmt"
+	"os/exec"
+	"strings"
+	"time"
+)
+
+const (
+	shellToolTimeout   = 60 * time.Second
+	maxToolOutputBytes = 16 * 1024
+)
+
+func shellTool() Tool {
+	return Tool{
+		Name:        "run_shell",
+		Description: "Run a shell command on the user's machine after they confirm it. Returns the exit code, stdout and stderr.",
+		Parameters: objectSchema(map[string]interface{}{
+			"command": map[string]interface{}{"type": "string", "description": "command line passed to sh -c"},
+		}, "command"),
+		Run: runShellTool,
+	}
+}
+
+func (s *ChatSession) confirm(ctx context.Context, question string) bool {
+	answer, ok := s.prompt(ctx, question+" [y/N]")
+	answer = strings.ToLower(strings.TrimSpace(answer))
+	return ok && (answer == "y" || answer == "yes")
+}
+
+func runShellTool(ctx context.Context, session *ChatSession, arguments string) (string, error) {
+	var args struct {
+		Command string `json:"command"`
+	}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	if strings.TrimSpace(args.Command) == "" {
+		return "", errors.New("command must not be empty")
+	}
+
+	fmt.Printf("%sThe model wants to run:%s\n  %s\n", colorYellow, colorReset, args.Command)
+	if !session.confirm(ctx, "Run this command?") {
+		return "The user declined to run this command.", nil
+	}
+
+	runCtx, cancel := context.WithTimeout(ctx, shellToolTimeout)
+	defer cancel()
+	cmd := exec.CommandContext(runCtx, "sh", "-c", args.Command)
+	var stdout, stderr bytes.Buffer
+	cmd.Stdout, cmd.Stderr = &stdout, &stderr
+	err := cmd.Run()
+
+	exitCode := 0
+	var exitErr *exec.ExitError
+	switch {
+	case errors.As(err, &exitErr):
+		exitCode = exitErr.ExitCode()
+	case err != nil:
+		return "", fmt.Errorf("failed to run command: %w", err)
+	}
+	if runCtx.Err() == context.DeadlineExceeded {
+		return "", fmt.Errorf("command timed out after %v", shellToolTimeout)
+	}
+
+	return fmt.Sprintf("exit code: %d\nstdout:\n%s\nstderr:\n%s", exitCode, limitOutput(stdout.String()), limitOutput(stderr.String())), nil
+}
+
+func limitOutput(output string) string {
+	if len(output) <= maxToolOutputBytes {
+		return output
+	}
+	return output[:maxToolOutputBytes] + fmt.Sprintf("\n[output truncated, %d bytes omitted]", len(output)-maxToolOutputBytes)
+}
@@ -0,0 +1,92 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"log"
+	"strings"
+	"time"
+)
+
+const (
+	summaryKeepMessages = 4
+	summaryNotePrefix   = "Conversation so far: "
+	summaryPrompt       = "Summarize the conversation below in a few short paragraphs. Keep names, decisions, code identifiers and open questions; leave out greetings and filler."
+)
+
+func (c *Conversation) tokens() int {
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	return c.tokenCount
+}
+
+func (c *Conversation) summaryChunk(keep int) (int, []Message) {
+	c.mu.RLock()
+	defer c.mu.RUnlock()
+	start := 0
+	if len(c.History) > 0 && c.History[0].Role == "system" {
+		start = 1
+	}
+	end := len(c.History) - keep
+	for end > start && end < len(c.History) && c.History[end].Role != "user" {
+		end--
+	}
+	if end-start < 2 {
+		return start, nil
+	}
+	return start, append([]Message(nil), c.History[start:end]...)
+}
+
+func (c *Conversation) replaceChunk(start int, chunk []Message, summary Message) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	if start+len(chunk) > len(c.History) {
+		return
+	}
+	summary.Tokens = len(strings.Fields(summary.Content))
+	c.tokenCount += summary.Tokens - countTokens(chunk)
+	c.History = append(append(c.History[:start:start], summary), c.History[start+len(chunk):]...)
+}
+
+func summaryTranscript(messages []Message) string {
+	var transcript strings.Builder
+	for _, msg := range messages {
+		fmt.Fprintf(&transcript, "%s: %s\n\n", roleLabel(msg.Role), strings.TrimSpace(msg.Content))
+	}
+	return transcript.String()
+}
+
+func (s *ChatSession) summaryThreshold() int {
+	if s.config.SummarizeThreshold > 0 {
+		return s.config.SummarizeThreshold
+	}
+	return promptBudget(s.config, s.model) * 3 / 4
+}
+
+func (s *ChatSession) summarizeHistory(ctx context.Context) {
+	if s.config.TruncationStrategy != "summarize" || s.conversation.tokens() <= s.summaryThreshold() {
+		return
+	}
+	start, chunk := s.conversation.summaryChunk(summaryKeepMessages)
+	if len(chunk) == 0 {
+		return
+	}
+
+	fmt.Printf("%sSummarizing %d earlier messages...%s\n", colorDim, len(chunk), colorReset)
+	request := []Message{
+		{Role: "system", Content: summaryPrompt},
+		{Role: "user", Content: summaryTranscript(chunk)},
+	}
+	turnCtx, endTurn := s.beginTurn(ctx)
+	completion, err := Ask(turnCtx, s.apiClient, request, s.model)
+	endTurn()
+	if err != nil {
+		log.Printf("Failed to summarize conversation: %v", err)
+		return
+	}
+	summary := strings.TrimSpace(completion.Content)
+	if summary == "" {
+		return
+	}
+	s.conversation.replaceChunk(start, chunk, Message{Role: "system", Content: summaryNotePrefix + summary, Timestamp: time.Now()})
+}
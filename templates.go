@@ -0,0 +1,209 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"regexp"
+	"slices"
+	"sort"
+	"strings"
+
+	"golang.org/x/term"
+)
+
+const (
+	templateInputVar = "input"
+	templateFileVar  = "file"
+)
+
+var (
+	placeholderPattern = regexp.MustCompile(`\{\{\s*(\w+)\s*\}\}`)
+	templateArgPattern = regexp.MustCompile(`(?:^|\s)--(\w+)=`)
+	templateExtensions = []string{".md", ".txt", ""}
+)
+
+type templateVars map[string]string
+
+func (v templateVars) String() string {
+	pairs := make([]string, 0, len(v))
+	for name, value := range v {
+		pairs = append(pairs, name+"="+value)
+	}
+	sort.Strings(pairs)
+	return strings.Join(pairs, ",")
+}
+
+func (v templateVars) Set(value string) error {
+	name, text, ok := strings.Cut(value, "=")
+	if !ok || name == "" {
+		return fmt.Errorf("expected name=value, got %q", value)
+	}
+	v[name] = text
+	return nil
+}
+
+func templatesDir(config *Config) (string, error) {
+	if config.TemplatesDir != "" {
+		return config.TemplatesDir, nil
+	}
+	dir, err := dataDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "templates"), nil
+}
+
+func loadTemplate(config *Config, name string) (string, error) {
+	if name == "" || filepath.Base(name) != name {
+		return "", fmt.Errorf("invalid template name %q", name)
+	}
+	dir, err := templatesDir(config)
+	if err != nil {
+		return "", err
+	}
+	for _, ext := range templateExtensions {
+		data, err := os.ReadFile(filepath.Join(dir, name+ext))
+		if err == nil {
+			return strings.TrimSpace(string(data)), nil
+		}
+		if !errors.Is(err, os.ErrNotExist) {
+			return "", fmt.Errorf("failed to read template %s: %w", name, err)
+		}
+	}
+	return "", fmt.Errorf("template %q not found in %s", name, dir)
+}
+
+func listTemplates(config *Config) (string, []string, error) {
+	dir, err := templatesDir(config)
+	if err != nil {
+		return "", nil, err
+	}
+	entries, err := os.ReadDir(dir)
+	if err != nil && !errors.Is(err, os.ErrNotExist) {
+		return dir, nil, fmt.Errorf("failed to list templates: %w", err)
+	}
+
+	var names []string
+	for _, entry := range entries {
+		if !entry.IsDir() && !strings.HasPrefix(entry.Name(), ".") {
+			names = append(names, strings.TrimSuffix(entry.Name(), filepath.Ext(entry.Name())))
+		}
+	}
+	return dir, names, nil
+}
+
+func templateVariables(text string) []string {
+	var names []string
+	seen := make(map[string]bool)
+	for _, match := range placeholderPattern.FindAllStringSubmatch(text, -1) {
+		if !seen[match[1]] {
+			seen[match[1]] = true
+			names = append(names, match[1])
+		}
+	}
+	return names
+}
+
+func renderTemplate(text string, vars map[string]string) string {
+	return placeholderPattern.ReplaceAllStringFunc(text, func(placeholder string) string {
+		return vars[placeholderPattern.FindStringSubmatch(placeholder)[1]]
+	})
+}
+
+func parseTemplateArgs(args string) (string, templateVars) {
+	name, rest, _ := strings.Cut(strings.TrimSpace(args), " ")
+	vars := templateVars{}
+	matches := templateArgPattern.FindAllStringSubmatchIndex(rest, -1)
+	for i, match := range matches {
+		end := len(rest)
+		if i+1 < len(matches) {
+			end = matches[i+1][0]
+		}
+		vars[rest[match[2]:match[3]]] = strings.TrimSpace(rest[match[1]:end])
+	}
+	if len(matches) == 0 && strings.TrimSpace(rest) != "" {
+		vars[templateInputVar] = strings.TrimSpace(rest)
+	}
+	return name, vars
+}
+
+func (s *ChatSession) fillTemplate(ctx context.Context, name string, vars templateVars) (string, error) {
+	text, err := loadTemplate(s.config, name)
+	if err != nil {
+		return "", err
+	}
+	for _, variable := range templateVariables(text) {
+		if _, ok := vars[variable]; ok {
+			continue
+		}
+		value, ok := s.prompt(ctx, variable+":")
+		if !ok {
+			return "", fmt.Errorf("missing value for template variable %q", variable)
+		}
+		vars[variable] = value
+	}
+	return renderTemplate(text, vars), nil
+}
+
+func (s *ChatSession) expandTemplate(ctx context.Context, options *Options) (string, error) {
+	text, err := loadTemplate(s.config, options.Template)
+	if err != nil {
+		return "", err
+	}
+	variables := templateVariables(text)
+	if options.Prompt != "" && slices.Contains(variables, templateInputVar) {
+		options.Vars[templateInputVar] = options.Prompt
+		options.Prompt = ""
+	}
+	if options.File != "" && slices.Contains(variables, templateFileVar) {
+		content, err := readAttachment(options.File)
+		if err != nil {
+			return "", err
+		}
+		options.Vars[templateFileVar] = formatAttachment(options.File, content, "")
+		options.File = ""
+	}
+	if s.input == nil && term.IsTerminal(int(os.Stdin.Fd())) {
+		s.input = newInputReader(os.Stdin)
+	}
+
+	prompt, err := s.fillTemplate(ctx, options.Template, options.Vars)
+	if err != nil {
+		return "", err
+	}
+	if options.Prompt != "" {
+		prompt += "\n\n" + options.Prompt
+	}
+	return prompt, nil
+}
+
+func handleTemplateCommand(ctx context.Context, session *ChatSession, args string) error {
+	name, vars := parseTemplateArgs(args)
+	if name == "" {
+		dir, names, err := listTemplates(session.config)
+		if err != nil {
+			fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+			return nil
+		}
+		if len(names) == 0 {
+			fmt.Printf("%sNo templates found in %s. Save prompts there as <name>.md with {{placeholders}}.%s\n", colorYellow, dir, colorReset)
+			return nil
+		}
+		fmt.Printf("%sTemplates in %s:%s\n", colorCyan, dir, colorReset)
+		for _, name := range names {
+			fmt.Printf("  %s\n", name)
+		}
+		return nil
+	}
+
+	prompt, err := session.fillTemplate(ctx, name, vars)
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	fmt.Printf("%sYou:%s %s\n", colorUser, colorReset, prompt)
+	return sendChatTurn(ctx, session, prompt, session.model)
+}
@@ -0,0 +1,222 @@
+package main
+
+import (
+	"fmt"
+	"maps"
+	"os"
+	"slices"
+	"strconv"
+	"strings"
+
+	"golang.org/x/term"
+)
+
+type Theme struct {
+	Color       bool
+	Interactive bool
+}
+
+type ThemeConfig struct {
+	Preset string            `yaml:"preset"`
+	Styles map[string]string `yaml:",inline"`
+}
+
+var theme = Theme{Color: true, Interactive: true}
+
+var (
+	colorReset  = "\033[0m"
+	colorRed    = "\033[31m"
+	colorGreen  = "\033[32m"
+	colorYellow = "\033[33m"
+	colorBlue   = "\033[34m"
+	colorPurple = "\033[35m"
+	colorCyan   = "\033[36m"
+	colorDim    = "\033[2m"
+	colorWhite  = "\033[37m"
+	colorBold   = "\033[1m"
+	colorItalic = "\033[3m"
+	colorNoBold = "\033[22m"
+	colorNoItal = "\033[23m"
+
+	colorUser      = colorGreen
+	colorAssistant = colorPurple
+	colorBody      = ""
+)
+
+var themeRoles = map[string]*string{
+	"user":      &colorUser,
+	"assistant": &colorAssistant,
+	"body":      &colorBody,
+	"error":     &colorRed,
+	"success":   &colorGreen,
+	"warning":   &colorYellow,
+	"info":      &colorCyan,
+	"hint":      &colorBlue,
+	"muted":     &colorDim,
+}
+
+var themePresets = map[string]map[string]string{
+	"default": {},
+	"light": {
+		"user":         "bold green",
+		"assistant":    "bold purple",
+		"warning":      "bold red",
+		"info":         "blue",
+		"hint":         "purple",
+		"muted":        "gray",
+		"code_string":  "green",
+		"code_number":  "red",
+		"code_keyword": "bold blue",
+		"code_comment": "gray",
+	},
+	"monochrome": {
+		"user":         "bold",
+		"assistant":    "bold",
+		"error":        "bold",
+		"success":      "",
+		"warning":      "bold",
+		"info":         "",
+		"hint":         "",
+		"muted":        "dim",
+		"code_string":  "",
+		"code_number":  "",
+		"code_keyword": "bold",
+		"code_comment": "dim",
+	},
+	"high-contrast": {
+		"user":         "bold bright-green",
+		"assistant":    "bold bright-purple",
+		"body":         "bright-white",
+		"error":        "bold bright-red",
+		"success":      "bold bright-green",
+		"warning":      "bold bright-yellow",
+		"info":         "bold bright-cyan",
+		"hint":         "bold bright-blue",
+		"muted":        "white",
+		"code_string":  "bright-green",
+		"code_number":  "bright-purple",
+		"code_keyword": "bold bright-cyan",
+		"code_comment": "white",
+	},
+}
+
+var styleCodes = map[string]string{
+	"bold":           "1",
+	"dim":            "2",
+	"italic":         "3",
+	"underline":      "4",
+	"reverse":        "7",
+	"black":          "30",
+	"red":            "31",
+	"green":          "32",
+	"yellow":         "33",
+	"blue":           "34",
+	"purple":         "35",
+	"magenta":        "35",
+	"cyan":           "36",
+	"white":          "37",
+	"gray":           "90",
+	"bright-red":     "91",
+	"bright-green":   "92",
+	"bright-yellow":  "93",
+	"bright-blue":    "94",
+	"bright-purple":  "95",
+	"bright-magenta": "95",
+	"bright-cyan":    "96",
+	"bright-white":   "97",
+}
+
+func detectTheme(noColor bool) Theme {
+	interactive := term.IsTerminal(int(os.Stdout.Fd()))
+	return Theme{
+		Color:       interactive && !noColor && os.Getenv("NO_COLOR") == "" && os.Getenv("TERM") != "dumb",
+		Interactive: interactive,
+	}
+}
+
+func (t Theme) apply() {
+	theme = t
+	if t.Color {
+		return
+	}
+	for _, code := range []*string{&colorReset, &colorRed, &colorGreen, &colorYellow, &colorBlue, &colorPurple, &colorCyan, &colorDim, &colorWhite, &colorBold, &colorItalic, &colorNoBold, &colorNoItal} {
+		*code = ""
+	}
+	for name := range colorCodes {
+		colorCodes[name] = ""
+	}
+	for class := range ansiCodeColors {
+		ansiCodeColors[class] = ""
+	}
+	for _, code := range themeRoles {
+		*code = ""
+	}
+}
+
+func parseStyle(style string) (string, error) {
+	var codes []string
+	for _, word := range strings.Fields(strings.ToLower(style)) {
+		if word == "default" || word == "none" {
+			continue
+		}
+		if code, ok := styleCodes[word]; ok {
+			codes = append(codes, code)
+			continue
+		}
+		if hex, ok := strings.CutPrefix(word, "#"); ok && len(hex) == 6 {
+			if rgb, err := strconv.ParseUint(hex, 16, 32); err == nil {
+				codes = append(codes, fmt.Sprintf("38;2;%d;%d;%d", rgb>>16, rgb>>8&0xff, rgb&0xff))
+				continue
+			}
+		}
+		return "", fmt.Errorf("unknown style %q, expected names like %q or a #rrggbb color", word, "bold cyan")
+	}
+	if len(codes) == 0 {
+		return "", nil
+	}
+	return "\033[" + strings.Join(codes, ";") + "m", nil
+}
+
+func (c ThemeConfig) styles() (map[string]string, error) {
+	preset := c.Preset
+	if preset == "" {
+		preset = "default"
+	}
+	base, ok := themePresets[preset]
+	if !ok {
+		names := slices.Sorted(maps.Keys(themePresets))
+		return nil, fmt.Errorf("unknown theme preset %q, expected one of %s", c.Preset, strings.Join(names, ", "))
+	}
+
+	styles := make(map[string]string)
+	for _, overrides := range []map[string]string{base, c.Styles} {
+		for role, style := range overrides {
+			_, isRole := themeRoles[role]
+			_, isCode := ansiCodeColors[strings.TrimPrefix(role, "code_")]
+			if !isRole && !(strings.HasPrefix(role, "code_") && isCode) {
+				return nil, fmt.Errorf("unknown theme element %q", role)
+			}
+			code, err := parseStyle(style)
+			if err != nil {
+				return nil, fmt.Errorf("theme element %s: %w", role, err)
+			}
+			styles[role] = code
+		}
+	}
+	return styles, nil
+}
+
+func applyThemeConfig(config ThemeConfig) error {
+	styles, err := config.styles()
+	if err != nil || !theme.Color {
+		return err
+	}
+	for role, code := range styles {
+		if target, ok := themeRoles[role]; ok {
+			*target = code
+		} else {
+			ansiCodeColors[strings.TrimPrefix(role, "code_")] = code
+		}
+	}
+	return nil
+}
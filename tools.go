@@ -0,0 +1,300 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"math"
+	"os"
+	"strconv"
+	"strings"
+	"time"
+	"unicode"
+
+	"code/aili"
+)
+
+const (
+	maxToolRounds     = 8
+	toolPreviewLength = 80
+)
+
+type Tool struct {
+	Name        string
+	Description string
+	Parameters  map[string]interface{}
+	Run         func(ctx context.Context, session *ChatSession, arguments string) (string, error)
+}
+
+type calculator struct {
+	input string
+	pos   int
+}
+
+func builtinTools() []Tool {
+	return []Tool{
+		{
+			Name:        "current_time",
+			Description: "Get the current date and time, optionally in an IANA time zone such as Europe/Paris.",
+			Parameters: objectSchema(map[string]interface{}{
+				"timezone": map[string]interface{}{"type": "string", "description": "IANA time zone name, defaults to local time"},
+			}),
+			Run: runCurrentTimeTool,
+		},
+		{
+			Name:        "calculator",
+			Description: "Evaluate an arithmetic expression with + - * / % ^ and parentheses.",
+			Parameters: objectSchema(map[string]interface{}{
+				"expression": map[string]interface{}{"type": "string", "description": "expression to evaluate, e.g. (2 + 3) * 4"},
+			}, "expression"),
+			Run: runCalculatorTool,
+		},
+	}
+}
+
+func objectSchema(properties map[string]interface{}, required ...string) map[string]interface{} {
+	schema := map[string]interface{}{"type": "object", "properties": properties}
+	if len(required) > 0 {
+		schema["required"] = required
+	}
+	return schema
+}
+
+func toolDefinitions(tools []Tool) []aili.ToolDefinition {
+	definitions := make([]aili.ToolDefinition, 0, len(tools))
+	for _, tool := range tools {
+		definitions = append(definitions, aili.ToolDefinition{Name: tool.Name, Description: tool.Description, Parameters: tool.Parameters})
+	}
+	return definitions
+}
+
+func (s *ChatSession) enabledTools() []Tool {
+	var tools []Tool
+	if s.config.EnableTools {
+		tools = append(tools, builtinTools()...)
+	}
+	if s.config.ShellTool {
+		tools = append(tools, shellTool())
+	}
+	if s.config.SearchProvider != "" {
+		tools = append(tools, webSearchTool())
+	}
+	if s.workspace != nil {
+		tools = append(tools, workspaceTools()...)
+	}
+	return append(tools, s.mcpTools()...)
+}
+
+func (s *ChatSession) completeTurn(ctx context.Context, history []Message, model string) (Completion, string, bool, error) {
+	tools := s.enabledTools()
+	s.apiClient.tools = tools
+	defer func() { s.apiClient.tools = nil }()
+
+	completion, model, fallback, err := s.askWithFallback(ctx, history, model)
+	for round := 1; err == nil && len(completion.ToolCalls) > 0; round++ {
+		if round > maxToolRounds {
+			return completion, model, fallback, fmt.Errorf("model requested tools more than %d times in one turn", maxToolRounds)
+		}
+		history = append(history, s.runToolCalls(ctx, tools, completion, model, fallback)...)
+		var retried bool
+		completion, model, retried, err = s.askWithFallback(ctx, history, model)
+		fallback = fallback || retried
+	}
+	return completion, model, fallback, err
+}
+
+func (s *ChatSession) runToolCalls(ctx context.Context, tools []Tool, completion Completion, model string, fallback bool) []Message {
+	out := io.Writer(os.Stdout)
+	if s.options.Command == commandAsk {
+		out = os.Stderr
+	} else if completion.Content != "" {
+		s.printResponse("AI", model, fallback, renderForDisplay(s.config, completion.Content))
+	}
+	messages := []Message{{Role: "assistant", Content: completion.Content, ToolCalls: completion.ToolCalls, Timestamp: time.Now(), Model: model}}
+	for _, call := range completion.ToolCalls {
+		fmt.Fprintf(out, "%s⚙ %s(%s)%s\n", colorDim, call.Function.Name, call.Function.Arguments, colorReset)
+		result, err := runTool(ctx, s, tools, call)
+		if err != nil {
+			result = "error: " + err.Error()
+		}
+		fmt.Fprintf(out, "%s  → %s%s\n", colorDim, truncateString(strings.Join(strings.Fields(result), " "), toolPreviewLength), colorReset)
+		messages = append(messages, Message{Role: "tool", Content: result, ToolCallID: call.ID, Timestamp: time.Now()})
+	}
+	for _, msg := range messages {
+		s.conversation.appendMessage(msg)
+	}
+	return messages
+}
+
+func runTool(ctx context.Context, session *ChatSession, tools []Tool, call ToolCall) (string, error) {
+	for _, tool := range tools {
+		if tool.Name == call.Function.Name {
+			return tool.Run(ctx, session, call.Function.Arguments)
+		}
+	}
+	return "", fmt.Errorf("unknown tool %q", call.Function.Name)
+}
+
+func decodeToolArguments(arguments string, target interface{}) error {
+	if strings.TrimSpace(arguments) == "" {
+		arguments = "{}"
+	}
+	if err := json.Unmarshal([]byte(arguments), target); err != nil {
+		return fmt.Errorf("invalid arguments: %w", err)
+	}
+	return nil
+}
+
+func runCurrentTimeTool(_ context.Context, _ *ChatSession, arguments string) (string, error) {
+	var args struct {
+		Timezone string `json:"timezone"`
+	}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	location := time.Local
+	if args.Timezone != "" {
+		loaded, err := time.LoadLocation(args.Timezone)
+		if err != nil {
+			return "", fmt.Errorf("unknown time zone %q", args.Timezone)
+		}
+		location = loaded
+	}
+	return time.Now().In(location).Format("Monday, 2 January 2006 15:04:05 MST"), nil
+}
+
+func runCalculatorTool(_ context.Context, _ *ChatSession, arguments string) (string, error) {
+	var args struct {
+		Expression string `json:"expression"`
+	}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	value, err := evaluateExpression(args.Expression)
+	if err != nil {
+		return "", err
+	}
+	return strconv.FormatFloat(value, 'g', -1, 64), nil
+}
+
+func evaluateExpression(expression string) (float64, error) {
+	c := &calculator{input: expression}
+	value, err := c.parseSum()
+	if err != nil {
+		return 0, err
+	}
+	if c.skipSpaces(); c.pos < len(c.input) {
+		return 0, fmt.Errorf("unexpected %q at position %d", c.input[c.pos], c.pos+1)
+	}
+	if math.IsInf(value, 0) || math.IsNaN(value) {
+		return 0, errors.New("result is not a finite number")
+	}
+	return value, nil
+}
+
+func (c *calculator) skipSpaces() {
+	for c.pos < len(c.input) && c.input[c.pos] == ' ' {
+		c.pos++
+	}
+}
+
+func (c *calculator) peek() byte {
+	c.skipSpaces()
+	if c.pos >= len(c.input) {
+		return 0
+	}
+	return c.input[c.pos]
+}
+
+func (c *calculator) parseSum() (float64, error) {
+	left, err := c.parseProduct()
+	for err == nil {
+		op := c.peek()
+		if op != '+' && op != '-' {
+			break
+		}
+		c.pos++
+		var right float64
+		if right, err = c.parseProduct(); err == nil {
+			if op == '+' {
+				left += right
+			} else {
+				left -= right
+			}
+		}
+	}
+	return left, err
+}
+
+func (c *calculator) parseProduct() (float64, error) {
+	left, err := c.parsePower()
+	for err == nil {
+		op := c.peek()
+		if op != '*' && op != '/' && op != '%' {
+			break
+		}
+		c.pos++
+		var right float64
+		if right, err = c.parsePower(); err != nil {
+			break
+		}
+		switch {
+		case op == '*':
+			left *= right
+		case right == 0:
+			err = errors.New("division by zero")
+		case op == '/':
+			left /= right
+		default:
+			left = math.Mod(left, right)
+		}
+	}
+	return left, err
+}
+
+func (c *calculator) parsePower() (float64, error) {
+	base, err := c.parseUnary()
+	if err != nil || c.peek() != '^' {
+		return base, err
+	}
+	c.pos++
+	exponent, err := c.parsePower()
+	return math.Pow(base, exponent), err
+}
+
+func (c *calculator) parseUnary() (float64, error) {
+	switch c.peek() {
+	case '-':
+		c.pos++
+		value, err := c.parseUnary()
+		return -value, err
+	case '+':
+		c.pos++
+		return c.parseUnary()
+	case '(':
+		c.pos++
+		value, err := c.parseSum()
+		if err != nil {
+			return 0, err
+		}
+		if c.peek() != ')' {
+			return 0, errors.New("missing closing parenthesis")
+		}
+		c.pos++
+		return value, nil
+	}
+
+	start := c.pos
+	for c.pos < len(c.input) && (unicode.IsDigit(rune(c.input[c.pos])) || c.input[c.pos] == '.') {
+		c.pos++
+	}
+	if start == c.pos {
+		if c.pos >= len(c.input) {
+			return 0, errors.New("unexpected end of expression")
+		}
+		return 0, fmt.Errorf("unexpected %q at position %d", c.input[c.pos], c.pos+1)
+	}
+	return strconv.ParseFloat(c.input[start:c.pos], 64)
+}
@@ -0,0 +1,425 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"log"
+	"os"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+	"unicode/utf8"
+
+	"golang.org/x/sync/errgroup"
+	"golang.org/x/term"
+)
+
+const (
+	enterTUIScreen     = "\x1b[?1049h\x1b[?1000h\x1b[?1006h" + enableBracketedPaste
+	leaveTUIScreen     = disableBracketedPaste + "\x1b[?1006l\x1b[?1000l\x1b[?1049l"
+	reverseVideo       = "\x1b[7m"
+	maxTUILines        = 10000
+	tuiScrollStep      = 3
+	tuiRefreshInterval = 500 * time.Millisecond
+)
+
+var tuiScreen *os.File
+
+type TUI struct {
+	mu      sync.Mutex
+	screen  *os.File
+	session *ChatSession
+	lines   []string
+	current string
+	carry   string
+	input   []rune
+	pasting bool
+	scroll  int
+	height  int
+	dirty   chan struct{}
+	submit  chan string
+}
+
+func outputFile() *os.File {
+	if tuiScreen != nil {
+		return tuiScreen
+	}
+	return os.Stdout
+}
+
+func runTUI(session *ChatSession) error {
+	if !term.IsTerminal(int(os.Stdin.Fd())) || !term.IsTerminal(int(os.Stdout.Fd())) {
+		return errors.New("--tui needs an interactive terminal")
+	}
+	state, err := term.MakeRaw(int(os.Stdin.Fd()))
+	if err != nil {
+		return fmt.Errorf("failed to enter raw mode: %w", err)
+	}
+	r, w, err := os.Pipe()
+	if err != nil {
+		term.Restore(int(os.Stdin.Fd()), state)
+		return fmt.Errorf("failed to capture output: %w", err)
+	}
+
+	ui := &TUI{screen: os.Stdout, session: session, dirty: make(chan struct{}, 1), submit: make(chan string, 16)}
+	screen, stderr := os.Stdout, os.Stderr
+	tuiScreen = screen
+	os.Stdout, os.Stderr = w, w
+	log.SetOutput(w)
+	screen.WriteString(enterTUIScreen)
+	defer func() {
+		os.Stdout, os.Stderr = screen, stderr
+		log.SetOutput(stderr)
+		tuiScreen = nil
+		w.Close()
+		screen.WriteString(leaveTUIScreen)
+		term.Restore(int(os.Stdin.Fd()), state)
+	}()
+
+	ctx, cancel := context.WithCancel(context.Background())
+	defer cancel()
+	go ui.capture(r)
+	go ui.readKeys(cancel)
+
+	if err := startChat(session); err != nil {
+		return err
+	}
+
+	g, ctx := errgroup.WithContext(ctx)
+	g.Go(func() error {
+		return handleInterrupt(ctx, session)
+	})
+	g.Go(func() error {
+		ui.renderLoop(ctx)
+		return nil
+	})
+	g.Go(func() error {
+		reader := newLineReader(func() (string, bool) {
+			line, ok := <-ui.submit
+			return line, ok
+		})
+		if err := processChatInputLoop(ctx, session, reader); err != nil {
+			return err
+		}
+		return errInterrupted
+	})
+
+	err = g.Wait()
+	if err != nil && !errors.Is(err, errInterrupted) && !errors.Is(err, context.Canceled) {
+		return err
+	}
+	return nil
+}
+
+func (t *TUI) capture(r io.Reader) {
+	buf := make([]byte, 4096)
+	for {
+		n, err := r.Read(buf)
+		if n > 0 {
+			t.write(string(buf[:n]))
+		}
+		if err != nil {
+			return
+		}
+	}
+}
+
+func (t *TUI) write(text string) {
+	t.mu.Lock()
+	defer t.redraw()
+	defer t.mu.Unlock()
+
+	text, t.carry = t.carry+text, ""
+	for i := 0; i < len(text); {
+		c := text[i]
+		switch {
+		case c == '\x1b':
+			end := escapeEnd(text, i)
+			if end < 0 {
+				t.carry = text[i:]
+				return
+			}
+			if seq := text[i:end]; seq == "\x1b[2J" {
+				t.lines, t.current, t.scroll = nil, "", 0
+			} else if strings.HasPrefix(seq, "\x1b[") && strings.HasSuffix(seq, "m") {
+				t.current += seq
+			}
+			i = end
+		case c == '\n':
+			t.lines = append(t.lines, t.current)
+			if len(t.lines) > maxTUILines {
+				t.lines = t.lines[len(t.lines)-maxTUILines:]
+			}
+			t.current = ""
+			i++
+		case c == '\r':
+			if i+1 < len(text) && text[i+1] != '\n' {
+				t.current = ""
+			}
+			i++
+		case c == '\t':
+			t.current += "    "
+			i++
+		case c < ' ':
+			i++
+		default:
+			if !utf8.FullRuneInString(text[i:]) {
+				t.carry = text[i:]
+				return
+			}
+			_, size := utf8.DecodeRuneInString(text[i:])
+			t.current += text[i : i+size]
+			i += size
+		}
+	}
+}
+
+func escapeEnd(text string, start int) int {
+	if start+1 >= len(text) {
+		return -1
+	}
+	switch text[start+1] {
+	case '[':
+		for j := start + 2; j < len(text); j++ {
+			if text[j] >= 0x40 && text[j] <= 0x7e {
+				return j + 1
+			}
+		}
+		return -1
+	case ']':
+		for j := start + 2; j < len(text); j++ {
+			if text[j] == '\a' {
+				return j + 1
+			}
+			if text[j] == '\x1b' && j+1 < len(text) && text[j+1] == '\\' {
+				return j + 2
+			}
+		}
+		return -1
+	}
+	return start + 2
+}
+
+func (t *TUI) redraw() {
+	select {
+	case t.dirty <- struct{}{}:
+	default:
+	}
+}
+
+func (t *TUI) renderLoop(ctx context.Context) {
+	ticker := time.NewTicker(tuiRefreshInterval)
+	defer ticker.Stop()
+	for {
+		t.render()
+		select {
+		case <-ctx.Done():
+			return
+		case <-t.dirty:
+		case <-ticker.C:
+		}
+	}
+}
+
+func (t *TUI) render() {
+	width, height, err := term.GetSize(int(t.screen.Fd()))
+	if err != nil || width < minBoxWidth || height < 5 {
+		width, height = defaultBoxWidth, 24
+	}
+	paneHeight := height - 3
+
+	t.mu.Lock()
+	var rows []string
+	for _, line := range t.lines {
+		rows = append(rows, wrapVisible(line, width)...)
+	}
+	rows = append(rows, wrapVisible(t.current, width)...)
+	t.height = paneHeight
+	t.scroll = min(t.scroll, max(0, len(rows)-paneHeight))
+	end := len(rows) - t.scroll
+	visible := rows[max(0, end-paneHeight):end]
+	input := []rune(strings.ReplaceAll(string(t.input), "\n", "⏎"))
+	if len(input) > width-3 {
+		input = input[len(input)-(width-3):]
+	}
+	scroll := t.scroll
+	t.mu.Unlock()
+
+	var b strings.Builder
+	b.WriteString("\x1b[H")
+	for i := 0; i < paneHeight; i++ {
+		if i < len(visible) {
+			b.WriteString(visible[i])
+		}
+		b.WriteString(colorReset + "\x1b[K\r\n")
+	}
+	b.WriteString(colorDim + strings.Repeat("─", width) + colorReset + "\r\n")
+	b.WriteString(colorGreen + "> " + colorReset + string(input) + "\x1b[K\r\n")
+	b.WriteString(reverseVideo + t.statusLine(width, scroll) + colorReset)
+	b.WriteString(fmt.Sprintf("\x1b[%d;%dH", height-1, len(input)+3))
+	t.screen.WriteString(b.String())
+}
+
+func (t *TUI) statusLine(width, scroll int) string {
+	session := t.session
+	left := fmt.Sprintf(" %s · %d tokens · %s", session.model, session.conversation.tokens(), formatCost(session.conversation.totalCost()))
+	right := "PgUp/PgDn scroll · Ctrl+C cancel/quit "
+	if session.turnActive() {
+		right = "thinking… " + right
+	}
+	if scroll > 0 {
+		right = "↑" + strconv.Itoa(scroll) + " · " + right
+	}
+	padding := width - utf8.RuneCountInString(left) - utf8.RuneCountInString(right)
+	if padding < 1 {
+		return truncateString(left, width)
+	}
+	return left + strings.Repeat(" ", padding) + right
+}
+
+func (s *ChatSession) turnActive() bool {
+	s.turnMu.Lock()
+	defer s.turnMu.Unlock()
+	return s.cancelTurn != nil
+}
+
+func wrapVisible(line string, width int) []string {
+	var rows []string
+	var row strings.Builder
+	active, count := "", 0
+	for i := 0; i < len(line); {
+		if line[i] == '\x1b' {
+			end := escapeEnd(line, i)
+			if end < 0 {
+				break
+			}
+			active = line[i:end]
+			row.WriteString(active)
+			i = end
+			continue
+		}
+		if count == width {
+			rows = append(rows, row.String())
+			row.Reset()
+			row.WriteString(active)
+			count = 0
+		}
+		_, size := utf8.DecodeRuneInString(line[i:])
+		row.WriteString(line[i : i+size])
+		count++
+		i += size
+	}
+	return append(rows, row.String())
+}
+
+func (t *TUI) readKeys(quit context.CancelFunc) {
+	buf := make([]byte, 1024)
+	pending := ""
+	for {
+		n, err := os.Stdin.Read(buf)
+		if err != nil {
+			quit()
+			return
+		}
+		pending = t.handleKeys(pending+string(buf[:n]), quit)
+		t.redraw()
+	}
+}
+
+func (t *TUI) handleKeys(keys string, quit context.CancelFunc) string {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	for i := 0; i < len(keys); {
+		c := keys[i]
+		switch {
+		case c == '\x1b':
+			end := escapeEnd(keys, i)
+			if end < 0 {
+				return keys[i:]
+			}
+			t.handleEscape(keys[i:end])
+			i = end
+			continue
+		case c == '\r' || c == '\n':
+			if t.pasting {
+				t.input = append(t.input, '\n')
+			} else {
+				t.submitInput()
+			}
+		case c == 0x7f || c == '\b':
+			if len(t.input) > 0 {
+				t.input = t.input[:len(t.input)-1]
+			}
+		case c == 0x03:
+			if t.session.cancelInFlight() {
+				t.lines = append(t.lines, t.current, colorYellow+"Cancelling response..."+colorReset)
+				t.current = ""
+			} else {
+				quit()
+			}
+		case c == 0x04:
+			if len(t.input) == 0 {
+				quit()
+			}
+		case c == 0x15:
+			t.input = t.input[:0]
+		case c == '\t':
+			t.input = append(t.input, ' ')
+		case c < ' ':
+		default:
+			if !utf8.FullRuneInString(keys[i:]) {
+				return keys[i:]
+			}
+			r, size := utf8.DecodeRuneInString(keys[i:])
+			t.input = append(t.input, r)
+			i += size
+			continue
+		}
+		i++
+	}
+	return ""
+}
+
+func (t *TUI) handleEscape(seq string) {
+	page := max(1, t.height/2)
+	switch seq {
+	case pasteStart:
+		t.pasting = true
+	case pasteEnd:
+		t.pasting = false
+	case "\x1b[A":
+		t.scroll++
+	case "\x1b[B":
+		t.scroll = max(0, t.scroll-1)
+	case "\x1b[5~":
+		t.scroll += page
+	case "\x1b[6~":
+		t.scroll = max(0, t.scroll-page)
+	case "\x1b[H", "\x1b[1~":
+		t.scroll = maxTUILines
+	case "\x1b[F", "\x1b[4~":
+		t.scroll = 0
+	default:
+		if strings.HasPrefix(seq, "\x1b[<64;") {
+			t.scroll += tuiScrollStep
+		} else if strings.HasPrefix(seq, "\x1b[<65;") {
+			t.scroll = max(0, t.scroll-tuiScrollStep)
+		}
+	}
+}
+
+func (t *TUI) submitInput() {
+	line := string(t.input)
+	select {
+	case t.submit <- line:
+		t.input = t.input[:0]
+		t.scroll = 0
+		t.current += line
+		t.lines = append(t.lines, t.current)
+		t.current = ""
+	default:
+	}
+}
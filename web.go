@@ -0,0 +1,144 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"html"
+	"io"
+	"mime"
+	"net/http"
+	"net/url"
+	"regexp"
+	"strings"
+	"time"
+)
+
+const (
+	maxPageBytes  = 5 << 20
+	pageUserAgent = "aili/1.0"
+)
+
+var (
+	boilerplateTags    = []string{"script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer", "aside"}
+	boilerplateRes     = compileElementPatterns(boilerplateTags)
+	contentRes         = compileElementPatterns([]string{"article", "main"})
+	titlePattern       = regexp.MustCompile(`(?is)<title[^>]*>(.*?)</title>`)
+	commentPattern     = regexp.MustCompile(`(?s)<!--.*?-->`)
+	blockTagPattern    = regexp.MustCompile(`(?i)</?(p|div|section|article|main|br|hr|li|ul|ol|tr|table|h[1-6]|pre|blockquote|dd|dt)\b[^>]*>`)
+	htmlHeadingPattern = regexp.MustCompile(`(?i)<h([1-6])\b[^>]*>`)
+	listItemPattern    = regexp.MustCompile(`(?i)<li\b[^>]*>`)
+	blankRunPattern    = regexp.MustCompile(`\n{3,}`)
+)
+
+func compileElementPatterns(tags []string) []*regexp.Regexp {
+	patterns := make([]*regexp.Regexp, 0, len(tags))
+	for _, tag := range tags {
+		patterns = append(patterns, regexp.MustCompile(`(?is)<`+tag+`\b[^>]*>.*?</`+tag+`\s*>`))
+	}
+	return patterns
+}
+
+func (c *APIClient) fetchPage(ctx context.Context, pageURL string) (string, string, error) {
+	ctx, cancel := context.WithTimeout(ctx, time.Second*timeoutSeconds)
+	defer cancel()
+
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, pageURL, nil)
+	if err != nil {
+		return "", "", fmt.Errorf("failed to create request: %w", err)
+	}
+	req.Header.Set("User-Agent", pageUserAgent)
+	req.Header.Set("Accept", "text/html,application/xhtml+xml,text/plain;q=0.9")
+	response, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return "", "", fmt.Errorf("failed to fetch %s: %w", pageURL, err)
+	}
+	defer response.Body.Close()
+
+	if response.StatusCode != http.StatusOK {
+		return "", "", fmt.Errorf("failed to fetch %s: %s", pageURL, response.Status)
+	}
+	mediaType, _, _ := mime.ParseMediaType(response.Header.Get("Content-Type"))
+	if mediaType != "" && !strings.HasPrefix(mediaType, "text/") && !strings.Contains(mediaType, "html") && !strings.Contains(mediaType, "json") && !strings.Contains(mediaType, "xml") {
+		return "", "", fmt.Errorf("%s is %s, not a readable page", pageURL, mediaType)
+	}
+	data, err := io.ReadAll(io.LimitReader(response.Body, maxPageBytes+1))
+	if err != nil {
+		return "", "", fmt.Errorf("failed to read %s: %w", pageURL, err)
+	}
+	if len(data) > maxPageBytes {
+		return "", "", fmt.Errorf("%s is larger than %d MB", pageURL, maxPageBytes>>20)
+	}
+
+	if mediaType != "" && !strings.Contains(mediaType, "html") {
+		return "", strings.TrimSpace(string(data)), nil
+	}
+	title, text := htmlToText(string(data))
+	return title, text, nil
+}
+
+func htmlToText(page string) (string, string) {
+	title := ""
+	if match := titlePattern.FindStringSubmatch(page); match != nil {
+		title = strings.Join(strings.Fields(html.UnescapeString(match[1])), " ")
+	}
+
+	page = commentPattern.ReplaceAllString(page, "")
+	for _, pattern := range boilerplateRes {
+		page = pattern.ReplaceAllString(page, "")
+	}
+	for _, pattern := range contentRes {
+		if matches := pattern.FindAllString(page, -1); len(matches) > 0 {
+			page = strings.Join(matches, "\n")
+			break
+		}
+	}
+
+	page = htmlHeadingPattern.ReplaceAllStringFunc(page, func(tag string) string {
+		return "\n\n" + strings.Repeat("#", int(htmlHeadingPattern.FindStringSubmatch(tag)[1][0]-'0')) + " "
+	})
+	page = listItemPattern.ReplaceAllString(page, "\n- ")
+	page = blockTagPattern.ReplaceAllString(page, "\n")
+	page = html.UnescapeString(htmlTagPattern.ReplaceAllString(page, ""))
+
+	lines := strings.Split(page, "\n")
+	for i, line := range lines {
+		lines[i] = strings.Join(strings.Fields(line), " ")
+	}
+	text := blankRunPattern.ReplaceAllString(strings.Join(lines, "\n"), "\n\n")
+	return title, strings.TrimSpace(text)
+}
+
+func formatPage(pageURL, content, part string) string {
+	label := "Page: " + pageURL
+	if part != "" {
+		label += " (" + part + ")"
+	}
+	return fmt.Sprintf("%s\n```\n%s\n```", label, content)
+}
+
+func handleFetchCommand(ctx context.Context, session *ChatSession, args string) error {
+	parsed, err := url.Parse(strings.TrimSpace(args))
+	if err != nil || (parsed.Scheme != "http" && parsed.Scheme != "https") || parsed.Host == "" {
+		fmt.Printf("%sUsage: /fetch <http(s) url>%s\n", colorYellow, colorReset)
+		return nil
+	}
+
+	fmt.Printf("%sFetching %s...%s\n", colorDim, parsed, colorReset)
+	title, text, err := session.apiClient.fetchPage(ctx, parsed.String())
+	if err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+		return nil
+	}
+	if text == "" {
+		fmt.Printf("%sNo readable text found on %s%s\n", colorYellow, parsed, colorReset)
+		return nil
+	}
+	if title != "" {
+		fmt.Printf("%s%s%s\n", colorCyan, title, colorReset)
+		text = "# " + title + "\n\n" + text
+	}
+	if err := session.attachContent(ctx, parsed.String(), text, formatPage); err != nil {
+		fmt.Printf("%s%v%s\n", colorRed, err, colorReset)
+	}
+	return nil
+}
@@ -0,0 +1,266 @@
+package main
+
+import (
+	"bufio"
+	"context"
+	"errors"
+	"fmt"
+	"io/fs"
+	"os"
+	"path"
+	"path/filepath"
+	"sort"
+	"strings"
+)
+
+const (
+	maxWorkspaceFiles     = 2000
+	maxWorkspaceFileBytes = 256 * 1024
+	maxIndexEntries       = 300
+)
+
+type Workspace struct {
+	root    string
+	files   []WorkspaceFile
+	indexed map[string]bool
+}
+
+type WorkspaceFile struct {
+	Path string
+	Size int64
+}
+
+type ignoreRule struct {
+	base    string
+	pattern string
+	negate  bool
+	dirOnly bool
+}
+
+func openWorkspace(dir string) (*Workspace, error) {
+	root, err := filepath.Abs(dir)
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve %s: %w", dir, err)
+	}
+	if root, err = filepath.EvalSymlinks(root); err != nil {
+		return nil, fmt.Errorf("failed to resolve %s: %w", dir, err)
+	}
+	info, err := os.Stat(root)
+	if err != nil {
+		return nil, fmt.Errorf("failed to open workspace: %w", err)
+	}
+	if !info.IsDir() {
+		return nil, fmt.Errorf("%s is not a directory", dir)
+	}
+
+	workspace := &Workspace{root: root, indexed: make(map[string]bool)}
+	var rules []ignoreRule
+	err = filepath.WalkDir(root, func(current string, entry fs.DirEntry, err error) error {
+		if err != nil {
+			return nil
+		}
+		rel, _ := filepath.Rel(root, current)
+		rel = filepath.ToSlash(rel)
+		if rel == "." {
+			rules = append(rules, loadIgnoreRules(current, "")...)
+			return nil
+		}
+		if entry.Name() == ".git" || isIgnored(rules, rel, entry.IsDir()) {
+			if entry.IsDir() {
+				return filepath.SkipDir
+			}
+			return nil
+		}
+		if entry.IsDir() {
+			rules = append(rules, loadIgnoreRules(current, rel)...)
+			return nil
+		}
+		if !entry.Type().IsRegular() {
+			return nil
+		}
+		if len(workspace.files) >= maxWorkspaceFiles {
+			return fs.SkipAll
+		}
+		info, err := entry.Info()
+		if err != nil {
+			return nil
+		}
+		workspace.files = append(workspace.files, WorkspaceFile{Path: rel, Size: info.Size()})
+		workspace.indexed[rel] = true
+		return nil
+	})
+	if err != nil {
+		return nil, fmt.Errorf("failed to index workspace: %w", err)
+	}
+	sort.Slice(workspace.files, func(i, j int) bool { return workspace.files[i].Path < workspace.files[j].Path })
+	return workspace, nil
+}
+
+func loadIgnoreRules(dir, base string) []ignoreRule {
+	file, err := os.Open(filepath.Join(dir, ".gitignore"))
+	if err != nil {
+		return nil
+	}
+	defer file.Close()
+
+	var rules []ignoreRule
+	scanner := bufio.NewScanner(file)
+	for scanner.Scan() {
+		line := strings.TrimSpace(scanner.Text())
+		if line == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+		rule := ignoreRule{base: base}
+		if rule.negate = strings.HasPrefix(line, "!"); rule.negate {
+			line = line[1:]
+		}
+		if rule.dirOnly = strings.HasSuffix(line, "/"); rule.dirOnly {
+			line = strings.TrimSuffix(line, "/")
+		}
+		rule.pattern = strings.TrimPrefix(line, "**/")
+		rules = append(rules, rule)
+	}
+	return rules
+}
+
+func isIgnored(rules []ignoreRule, rel string, isDir bool) bool {
+	ignored := false
+	for _, rule := range rules {
+		if rule.dirOnly && !isDir {
+			continue
+		}
+		target := rel
+		if rule.base != "" {
+			if !strings.HasPrefix(rel, rule.base+"/") {
+				continue
+			}
+			target = strings.TrimPrefix(rel, rule.base+"/")
+		}
+		var matched bool
+		if strings.Contains(rule.pattern, "/") {
+			matched, _ = path.Match(strings.TrimPrefix(rule.pattern, "/"), target)
+		} else {
+			matched, _ = path.Match(rule.pattern, path.Base(target))
+		}
+		if matched {
+			ignored = !rule.negate
+		}
+	}
+	return ignored
+}
+
+func (w *Workspace) resolve(name string) (string, error) {
+	cleaned := filepath.Clean(filepath.FromSlash(name))
+	if filepath.IsAbs(cleaned) {
+		return "", errors.New("paths must be relative to the workspace root")
+	}
+	if !w.indexed[filepath.ToSlash(cleaned)] {
+		return "", fmt.Errorf("%s is not in the workspace index, use list_files to find it", name)
+	}
+	full := filepath.Join(w.root, cleaned)
+	resolved, err := filepath.EvalSymlinks(full)
+	if err != nil {
+		return "", fmt.Errorf("no such file %s", name)
+	}
+	if rel, err := filepath.Rel(w.root, resolved); err != nil || rel == ".." || strings.HasPrefix(rel, ".."+string(filepath.Separator)) {
+		return "", fmt.Errorf("%s is outside the workspace", name)
+	}
+	return resolved, nil
+}
+
+func (w *Workspace) indexMessage() string {
+	var index strings.Builder
+	fmt.Fprintf(&index, "The user shared a workspace at %s with %d files. Use the read_file tool to read a file and list_files to search the index; do not guess file contents.\n\n", w.root, len(w.files))
+	for i, file := range w.files {
+		if i == maxIndexEntries {
+			fmt.Fprintf(&index, "... and %d more files (use list_files)\n", len(w.files)-maxIndexEntries)
+			break
+		}
+		fmt.Fprintf(&index, "%s (%d bytes)\n", file.Path, file.Size)
+	}
+	return strings.TrimSpace(index.String())
+}
+
+func (s *ChatSession) addWorkspaceIndex() {
+	if s.workspace != nil {
+		s.conversation.addMessage("system", s.workspace.indexMessage())
+	}
+}
+
+func workspaceTools() []Tool {
+	return []Tool{
+		{
+			Name:        "list_files",
+			Description: "List files in the shared workspace whose path contains the given text.",
+			Parameters: objectSchema(map[string]interface{}{
+				"filter": map[string]interface{}{"type": "string", "description": "substring to match against file paths, empty lists everything"},
+			}),
+			Run: runListFilesTool,
+		},
+		{
+			Name:        "read_file",
+			Description: "Read a text file from the shared workspace, optionally limited to a line range.",
+			Parameters: objectSchema(map[string]interface{}{
+				"path":       map[string]interface{}{"type": "string", "description": "path relative to the workspace root"},
+				"start_line": map[string]interface{}{"type": "integer", "description": "first line to return, starting at 1"},
+				"end_line":   map[string]interface{}{"type": "integer", "description": "last line to return"},
+			}, "path"),
+			Run: runReadFileTool,
+		},
+	}
+}
+
+func runListFilesTool(_ context.Context, session *ChatSession, arguments string) (string, error) {
+	var args struct {
+		Filter string `json:"filter"`
+	}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	var matches []string
+	for _, file := range session.workspace.files {
+		if strings.Contains(file.Path, args.Filter) {
+			matches = append(matches, file.Path)
+		}
+	}
+	if len(matches) == 0 {
+		return "No matching files.", nil
+	}
+	return limitOutput(strings.Join(matches, "\n")), nil
+}
+
+func runReadFileTool(_ context.Context, session *ChatSession, arguments string) (string, error) {
+	var args struct {
+		Path      string `json:"path"`
+		StartLine int    `json:"start_line"`
+		EndLine   int    `json:"end_line"`
+	}
+	if err := decodeToolArguments(arguments, &args); err != nil {
+		return "", err
+	}
+	resolved, err := session.workspace.resolve(args.Path)
+	if err != nil {
+		return "", err
+	}
+	info, err := os.Stat(resolved)
+	if err != nil {
+		return "", fmt.Errorf("failed to read %s: %w", args.Path, err)
+	}
+	if info.Size() > maxWorkspaceFileBytes && args.StartLine == 0 {
+		return "", fmt.Errorf("%s is %d bytes, request a line range instead", args.Path, info.Size())
+	}
+	content, err := readAttachment(resolved)
+	if err != nil {
+		return "", err
+	}
+
+	lines := strings.Split(content, "\n")
+	start, end := max(args.StartLine, 1), len(lines)
+	if args.EndLine > 0 && args.EndLine < end {
+		end = args.EndLine
+	}
+	if start > end {
+		return "", fmt.Errorf("line range %d-%d is empty, the file has %d lines", start, end, len(lines))
+	}
+	return limitOutput(strings.Join(lines[start-1:end], "\n")), nil
+}